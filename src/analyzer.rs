@@ -2,11 +2,13 @@
 
 // --- 模块引入 ---
 
+use std::collections::HashMap;
+
 // 引入字面量用于分析
 use crate::token::Literal;
 
 // 引入诊断模块，用于将分析阶段发现的语义错误添加到错误收集中。
-use crate::diagnostics::{CompilerError, SemanticError, Span};
+use crate::diagnostics::{CompilerError, SemanticError, SemanticWarning, Span};
 
 // 引入抽象语法树 (AST) 模块。
 // 语义分析器的主要工作就是遍历这些 AST 节点。
@@ -15,6 +17,7 @@ use crate::ast::{
     Program,
     TopLevelStatement,
     FunctionDeclaration,
+    NodeId,
 
     // --- 语句 (Statements) ---
     Statement,
@@ -30,9 +33,12 @@ use crate::ast::{
     IfExpression,
     LoopExpression,
     CallExpression,
+    CastExpression,
     AssignmentExpression,
     PrefixExpression,
     InfixExpression,
+    IdentifierExpression,
+    LiteralExpression,
 
     // --- 运算符 ---
     Operator,
@@ -43,10 +49,19 @@ use crate::ast::{
 // `SymbolTable` 是语义分析器用来跟踪变量和函数定义的核心数据结构。
 use crate::scope::{Symbol, SymbolTable};
 
+// 引入基于 AST 的控制流分析，用于检查函数是否在所有路径上都有返回值。
+use crate::flow;
+
+// 引入确定初始化分析，用于检查没有初始值的变量是否在读取前被赋值。
+use crate::init;
+
 // 引入内部类型系统。
 // `Type` 枚举用于表示变量、表达式和函数返回值的类型。
 use crate::types::Type;
 
+// 引入内置函数，它们在分析开始前被注册到符号表中。
+use crate::prelude;
+
 
 /// 语义分析器结构体。
 ///
@@ -56,6 +71,7 @@ use crate::types::Type;
 ///
 /// 例如，它会检查：
 /// - 变量是否在使用前已被声明。
+/// - 没有初始值的变量是否在读取前已被赋值（见 `init` 模块）。
 /// - 函数调用的参数数量和类型是否正确。
 /// - 运算符两边的类型是否兼容（例如，不能将字符串和整数相加）。
 /// - `break` 或 `continue` 是否只在循环内部使用。
@@ -69,6 +85,12 @@ pub struct SemanticAnalyzer {
     /// 这使得语义分析器可以和词法、语法分析器一样，报告结构化的、
     /// 可携带位置信息的错误，完全融入了我们统一的诊断系统。
     pub errors: Vec<CompilerError>,
+
+    /// 警告收集器。
+    ///
+    /// 警告不会阻止编译。驱动程序会根据命令行中的 `-A` / `-W` / `-D`
+    /// 决定每个警告是被忽略、作为警告打印，还是升级为错误。
+    pub warnings: Vec<SemanticWarning>,
     
     /// 当前正在分析的函数的返回类型。
     ///
@@ -77,15 +99,41 @@ pub struct SemanticAnalyzer {
     /// 当不在任何函数内部时，它的值是 `None`。
     current_return_type: Option<Type>,
     
-    /// 当前所处的循环深度。
+    /// 循环上下文栈，栈顶是最内层的循环。
     ///
-    /// - `0` 表示当前不在任何循环内部。
-    /// - `1` 表示在最外层循环内。
-    /// - `> 1` 表示在嵌套循环内。
+    /// - 栈为空表示当前不在任何循环内部，此时 `break` 和 `continue` 都是非法的。
+    /// - 每个 `break <value>` 都会与栈顶循环已经确定的类型进行比较，
+    ///   `loop` 表达式的类型就是其中所有 `break` 的共同类型。
+    loop_stack: Vec<LoopContext>,
+
+    /// 每个表达式推断出的类型，以表达式的 `NodeId` 为键。
+    ///
+    /// 代码生成阶段通过它查询语义分析的结论（例如 `loop` 表达式的结果类型），
+    /// 而不必重新推断类型。
+    pub expression_types: HashMap<NodeId, Type>,
+
+    /// 每个变量声明的类型，以 `VarDeclaration` 的 `NodeId` 为键。
     ///
-    /// 这个计数器使得我们可以轻松地验证 `break` 和 `continue` 语句
-    /// 是否被合法地使用在循环体中。
-    loop_depth: u32,
+    /// 离开作用域时符号会被销毁，代码生成阶段通过它得知应为变量分配多大的栈空间。
+    pub variable_types: HashMap<NodeId, Type>,
+}
+
+/// 循环的种类。只有 `loop` 是表达式，可以通过 `break <value>` 产生值。
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LoopKind {
+    Loop,
+    While,
+}
+
+/// 分析器为每个正在分析的循环记录的信息。
+struct LoopContext {
+    kind: LoopKind,
+    /// 第一个 `break` 确定的循环类型（不带值的 `break` 记为 `Void`）。
+    /// 为 `None` 表示目前还没有遇到过 `break`。
+    break_type: Option<Type>,
+    /// 上下文对整个 `loop` 表达式期望的类型，在遇到第一个 `break` 之前
+    /// 用于推断 `break` 值中字面量的类型。
+    expected: Option<Type>,
 }
 
 impl SemanticAnalyzer {
@@ -93,9 +141,11 @@ impl SemanticAnalyzer {
     ///
     /// # Returns
     ///
-    /// 一个全新的 `SemanticAnalyzer`，其内部包含一个已经初始化好的、
-    /// 带有全局作用域的 `SymbolTable`，一个空的错误收集器，
-    /// 并且没有预设的当前函数返回类型或循环深度。
+    /// 一个全新的 `SemanticAnalyzer`，其内部包含一个已经初始化好的 `SymbolTable`，
+    /// 一个空的错误收集器，并且没有预设的当前函数返回类型或循环上下文。
+    ///
+    /// 符号表的最外层作用域中是 prelude 提供的内置函数（见 `prelude` 模块），
+    /// 程序自己的顶层符号则定义在它里面的全局作用域中，因此可以遮蔽内置函数。
     ///
     /// # Examples
     ///
@@ -103,11 +153,30 @@ impl SemanticAnalyzer {
     /// let mut analyzer = SemanticAnalyzer::new();
     /// ```
     pub fn new() -> Self {
+        let mut symbol_table = SymbolTable::new();
+        for (name, builtin_type) in prelude::builtin_functions() {
+            symbol_table
+                .define(Symbol {
+                    name: name.to_string(),
+                    symbol_type: builtin_type,
+                    is_mutable: false,
+                    // 内置函数没有源码位置
+                    span: Span::default(),
+                    used: true,
+                    initialized: true,
+                })
+                .expect("builtin function names are unique");
+        }
+        symbol_table.enter_scope();
+
         SemanticAnalyzer {
-            symbol_table: SymbolTable::new(),
+            symbol_table,
             errors: Vec::new(),
+            warnings: Vec::new(),
             current_return_type: None,
-            loop_depth: 0,
+            loop_stack: Vec::new(),
+            expression_types: HashMap::new(),
+            variable_types: HashMap::new(),
         }
     }
 
@@ -149,8 +218,10 @@ impl SemanticAnalyzer {
         for toplevel_stmt in &program.body {
             if let TopLevelStatement::Function(func_decl) = toplevel_stmt {
                 // NOTE: 此处也假设 `analyze_function_body` 返回 Result<(), SemanticError>
-                if let Err(e) = self.analyze_function_body(func_decl) {
-                    self.errors.push(CompilerError::Semantic(e));
+                match self.analyze_function_body(func_decl) {
+                    // 函数体通过检查后，所有名字都已确认可以解析，再检查变量是否在读取前被初始化
+                    Ok(()) => self.errors.extend(init::check_function(func_decl).into_iter().map(CompilerError::Semantic)),
+                    Err(e) => self.errors.push(CompilerError::Semantic(e)),
                 }
             }
         }
@@ -162,13 +233,17 @@ impl SemanticAnalyzer {
     /// 它会将这些信息组合成一个 `Type::Function`，然后作为一个 `Symbol`
     /// 定义在符号表的全局作用域中。它不会分析函数体内部的任何代码。
     fn register_function_signature(&mut self, func_decl: &FunctionDeclaration) -> Result<(), SemanticError> {
+        if prelude::RUNTIME_FUNCTIONS.contains(&func_decl.name.as_str()) {
+            return Err(SemanticError::ReservedFunctionName { name: func_decl.name.clone(), span: func_decl.span });
+        }
+
         let mut param_types = Vec::new();
         for p in &func_decl.params {
             // 使用 ? 操作符，如果 string_to_type 失败，错误会立即被传播出去。
-            param_types.push(self.string_to_type(&p.param_type)?);
+            param_types.push(self.string_to_type(&p.param_type, p.span)?);
         }
         
-        let ret_type = self.string_to_type(&func_decl.return_type)?;
+        let ret_type = self.string_to_type(&func_decl.return_type, func_decl.span)?;
         
         let func_type = Type::Function {
             params: param_types,
@@ -179,6 +254,9 @@ impl SemanticAnalyzer {
             name: func_decl.name.clone(),
             symbol_type: func_type,
             is_mutable: false, // 函数定义本身总是不可变的
+            span: func_decl.span,
+            used: false,
+            initialized: true,
         };
 
         // `self.symbol_table.define` 已经返回 Result<(), SemanticError>，
@@ -197,37 +275,71 @@ impl SemanticAnalyzer {
     /// 2. **设置状态**: 记录下当前函数的返回类型，用于检查 `ret` 语句。
     /// 3. **定义参数**: 将所有函数参数作为变量定义在新创建的局部作用域中。
     /// 4. **分析主体**: 递归地调用语句和表达式的分析函数，检查函数体内的每一行代码。
-    /// 5. **离开作用域**: 分析完成后，销毁局部作用域，并清理状态。
+    /// 5. **检查返回**: 有返回值的函数必须在所有路径上返回，见 `check_function_returns`。
+    /// 6. **离开作用域**: 分析完成后，销毁局部作用域，并清理状态。
     fn analyze_function_body(&mut self, func_decl: &FunctionDeclaration) -> Result<(), SemanticError> {
         // 1. 进入函数作用域
         self.symbol_table.enter_scope();
         
         // 2. 记录当前函数的返回类型
         // 在离开函数时，这个 Option 会被重置为 None
-        self.current_return_type = Some(self.string_to_type(&func_decl.return_type)?);
+        self.current_return_type = Some(self.string_to_type(&func_decl.return_type, func_decl.span)?);
 
         // 3. 将函数参数定义为新作用域中的变量
         for p in &func_decl.params {
-            let param_type = self.string_to_type(&p.param_type)?;
+            let param_type = self.string_to_type(&p.param_type, p.span)?;
             let param_symbol = Symbol {
                 name: p.name.clone(),
                 symbol_type: param_type,
-                // Tipy 规范中，函数参数默认是不可变的。
-                // 未来如果引入 `~` 修饰参数，这里可以修改。
-                is_mutable: false,
+                // 与变量一样，函数参数默认不可变，只有 `~` 修饰的参数才能被重新赋值。
+                is_mutable: p.is_mutable,
+                span: p.span,
+                used: false,
+                initialized: true,
             };
             self.symbol_table.define(param_symbol)?;
         }
         
-        // 4. 分析函数体代码块
-        // 我们将在下一步重构 analyze_block_statement
-        self.analyze_block_statement(&func_decl.body)?;
-        
-        // 5. 离开函数作用域并清理状态
-        self.symbol_table.leave_scope();
-        self.current_return_type = None;
+        // 4. 分析函数体代码块。函数体末尾的表达式会被隐式返回，所以期望它是返回类型。
+        let expected_body_type = self.current_return_type.clone();
+        let body_type = self.analyze_block_statement(&func_decl.body, expected_body_type.as_ref())?;
 
-        Ok(())
+        // 5. 检查所有路径是否都有返回值
+        let return_type = self.current_return_type.take().unwrap_or(Type::Void);
+        let result = Self::check_function_returns(func_decl, &return_type, body_type);
+
+        // 6. 离开函数作用域并清理状态
+        self.leave_scope();
+
+        result
+    }
+
+    /// 检查声明了返回类型的函数是否在每条执行路径上都返回了值。
+    ///
+    /// 满足以下任意一条即可：
+    /// - 函数体以一个类型正确的表达式结尾（隐式返回），e.g., `add(a: i64, b: i64) -> i64 { a + b }`。
+    /// - 函数体“发散”，即所有路径都以 `ret` 或无限 `loop` 结束（见 `flow::block_diverges`）。
+    ///
+    /// 如果函数体以表达式结尾但类型不对，报告 `TypeMismatch`；否则报告 `MissingReturn`，
+    /// 并指向函数体结尾的 `}`。
+    fn check_function_returns(func_decl: &FunctionDeclaration, return_type: &Type, body_type: Type) -> Result<(), SemanticError> {
+        if *return_type == Type::Void || body_type == *return_type || flow::block_diverges(&func_decl.body) {
+            return Ok(());
+        }
+
+        match func_decl.body.statements.last() {
+            Some(Statement::Expression(tail)) if body_type != Type::Void => Err(SemanticError::TypeMismatch {
+                expected: return_type.clone(),
+                found: body_type,
+                span: tail.span(),
+            }),
+            _ => Err(SemanticError::MissingReturn {
+                name: func_decl.name.clone(),
+                return_type: return_type.clone(),
+                span: func_decl.body.closing_brace,
+                signature_span: func_decl.span,
+            }),
+        }
     }
 
     // --- 语句与块分析 (Statement & Block Analysis) ---
@@ -246,7 +358,7 @@ impl SemanticAnalyzer {
             Statement::VarDeclaration(var_decl) => self.analyze_var_declaration(var_decl),
             Statement::Expression(expression) => self.analyze_expression(expression).map(|_| ()),
             Statement::Return(ret_stmt) => self.analyze_return_statement(ret_stmt),
-            Statement::Block(block_stmt) => self.analyze_block_statement(block_stmt).map(|_| ()),
+            Statement::Block(block_stmt) => self.analyze_block_statement(block_stmt, None).map(|_| ()),
             Statement::While(while_stmt) => self.analyze_while_statement(while_stmt),
             Statement::Break(break_stmt) => self.analyze_break_statement(break_stmt),
             Statement::Continue(cont_stmt) => self.analyze_continue_statement(cont_stmt),
@@ -257,54 +369,118 @@ impl SemanticAnalyzer {
     ///
     /// # 主要职责
     /// 1. 创建一个新的作用域。
-    /// 2. 逐一分析块内的所有语句。
+    /// 2. 逐一分析块内的所有语句。出现在 `ret` / `break` / `continue` 之后的
+    ///    第一条语句会产生一个 `unreachable_code` 警告。
     /// 3. 根据 Tipy 的“隐式返回”规则，确定整个块的类型。
     ///    - 如果块为空，或最后一个语句不是表达式语句，则类型为 `Void`。
-    ///    - 否则，类型为最后一个表达式的类型。
+    ///    - 否则，类型为最后一个表达式的类型。`expected` 是上下文对这个值的期望类型。
     /// 4. 离开作用域。
-    fn analyze_block_statement(&mut self, block: &BlockStatement) -> Result<Type, SemanticError> {
+    fn analyze_block_statement(&mut self, block: &BlockStatement, expected: Option<&Type>) -> Result<Type, SemanticError> {
         self.symbol_table.enter_scope();
-        
-        for statement in &block.statements {
-            self.analyze_statement(statement)?;
-        }
 
-        let block_type = if let Some(last_stmt) = block.statements.last() {
-            if let Statement::Expression(expr) = last_stmt {
-                self.analyze_expression(expr)?
-            } else {
-                Type::Void
+        let mut block_type = Type::Void;
+        // 使后续代码不可达的那条语句的位置
+        let mut diverged_at: Option<Span> = None;
+        let mut reported_unreachable = false;
+
+        let last_index = block.statements.len().saturating_sub(1);
+        for (index, statement) in block.statements.iter().enumerate() {
+            if let (Some(cause_span), false) = (diverged_at, reported_unreachable) {
+                self.warnings.push(SemanticWarning::UnreachableCode { span: statement.span(), cause_span });
+                reported_unreachable = true;
             }
-        } else {
-            Type::Void
-        };
 
-        self.symbol_table.leave_scope();
+            block_type = match statement {
+                // 表达式语句的类型可能成为整个块的类型
+                Statement::Expression(expr) if index == last_index => self.analyze_expression_expecting(expr, expected)?,
+                Statement::Expression(expr) => self.analyze_expression(expr)?,
+                _ => {
+                    self.analyze_statement(statement)?;
+                    Type::Void
+                }
+            };
+
+            if diverged_at.is_none()
+                && matches!(statement, Statement::Return(_) | Statement::Break(_) | Statement::Continue(_))
+            {
+                diverged_at = Some(statement.span());
+            }
+        }
+
+        self.leave_scope();
         Ok(block_type)
     }
 
+    /// 离开当前作用域，并为其中从未被读取过的变量报告 `unused_variables` 警告。
+    fn leave_scope(&mut self) {
+        let mut unused: Vec<_> = self
+            .symbol_table
+            .leave_scope()
+            .into_iter()
+            .filter(|symbol| !symbol.used && !symbol.name.starts_with('_'))
+            .collect();
+        // 作用域是 HashMap，排序以保证警告按源码顺序输出
+        unused.sort_by_key(|symbol| symbol.span.start_byte);
+        for symbol in unused {
+            self.warnings.push(SemanticWarning::UnusedVariable { name: symbol.name, span: symbol.span });
+        }
+    }
+
     // --- 具体语句分析(变量声明，函数返回等) ---
 
     /// 分析变量声明语句 `name: [~]type [= value];`
     fn analyze_var_declaration(&mut self, var_decl: &VarDeclaration) -> Result<(), SemanticError> {
-        let var_type = self.string_to_type(&var_decl.var_type)?;
-
-        if let Some(initial_value) = &var_decl.value {
-            let value_type = self.analyze_expression(initial_value)?;
-            if value_type != var_type {
-                // CHANGED: 使用结构化的 TypeMismatch 错误
-                return Err(SemanticError::TypeMismatch {
-                    expected: var_type,
-                    found: value_type,
-                    span: Span::default(), // TODO: 从 var_decl 获取 Span
-                });
+        let var_type = match (&var_decl.var_type, &var_decl.value) {
+            (Some(type_name), value) => {
+                let var_type = self.string_to_type(type_name, var_decl.span)?;
+                if let Some(initial_value) = value {
+                    let value_type = self.analyze_expression_expecting(initial_value, Some(&var_type))?;
+                    if value_type != var_type {
+                        // CHANGED: 使用结构化的 TypeMismatch 错误
+                        return Err(SemanticError::TypeMismatch {
+                            expected: var_type,
+                            found: value_type,
+                            span: initial_value.span(),
+                        });
+                    }
+                }
+                var_type
+            }
+            // `x := value`：变量的类型就是初始值的类型，没有期望类型的字面量取默认的 `i64` / `f64`
+            (None, Some(initial_value)) => {
+                self.analyze_expression(initial_value)?
             }
+            // 语法分析保证省略类型的声明一定有初始值
+            (None, None) => unreachable!("a variable declaration without a type must have an initializer"),
+        };
+
+        // 无论类型是写出来的还是推断出来的，都必须是能存放在变量中的类型
+        if !var_type.is_storable() {
+            return Err(match &var_decl.value {
+                Some(initial_value) if var_decl.var_type.is_none() => SemanticError::CannotInferType {
+                    name: var_decl.name.clone(),
+                    found: var_type,
+                    span: initial_value.span(),
+                },
+                _ => SemanticError::InvalidVariableType {
+                    name: var_decl.name.clone(),
+                    found: var_type,
+                    span: var_decl.span,
+                },
+            });
         }
 
+        // 代码生成阶段据此为变量分配栈空间，对于省略类型的声明，这就是推断出的类型
+        self.variable_types.insert(var_decl.id, var_type.clone());
+
         let symbol = Symbol {
             name: var_decl.name.clone(),
             symbol_type: var_type,
             is_mutable: var_decl.is_mutable,
+            span: var_decl.span,
+            used: false,
+            // 没有初始值的变量由 `init` 模块检查它是否在读取前被赋值
+            initialized: var_decl.value.is_some(),
         };
         
         // .define 已经返回 Result<(), SemanticError>，所以可以直接用 ?
@@ -319,15 +495,17 @@ impl SemanticAnalyzer {
         let expected = self.current_return_type.clone().unwrap_or(Type::Error);
 
         let actual = match &ret_stmt.value {
-            Some(expr) => self.analyze_expression(expr)?,
+            Some(expr) => self.analyze_expression_expecting(expr, Some(&expected))?,
             None => Type::Void,
         };
 
         if actual != expected {
+            // 有返回值时指向返回值本身，否则指向整个 `ret` 语句
+            let span = ret_stmt.value.as_ref().map_or(ret_stmt.span, |expr| expr.span());
             return Err(SemanticError::TypeMismatch {
                 expected,
                 found: actual,
-                span: Span::default(), // TODO: 从 ret_stmt 获取 Span
+                span,
             });
         }
         Ok(())
@@ -336,25 +514,43 @@ impl SemanticAnalyzer {
     // --- 控制流分析 ---
 
     /// 分析 `if-elif-else` 表达式，并返回整个表达式的类型。
-    fn analyze_if_expression(&mut self, if_expr: &IfExpression) -> Result<Type, SemanticError> {
+    ///
+    /// 两个分支的值都以 `expected` 为期望类型；没有期望类型时，`else` 分支
+    /// 以 `if` 分支的类型为期望类型，使得 `if c { x } else { 0 }` 中的 `0` 与 `x` 同类型。
+    fn analyze_if_expression(&mut self, if_expr: &IfExpression, expected: Option<&Type>) -> Result<Type, SemanticError> {
         let condition_type = self.analyze_expression(&if_expr.condition)?;
         if condition_type != Type::Bool {
             return Err(SemanticError::ConditionNotBoolean { 
                 found: condition_type, 
-                span: Span::default() // TODO: 从 if_expr.condition 获取 Span
+                span: if_expr.condition.span(),
             });
         }
 
-        let consequence_type = self.analyze_block_statement(&if_expr.consequence)?;
+        // 没有 `else` 的 `if` 不产生值，分支也就没有期望类型
+        let branch_expected = if_expr.alternative.as_ref().and(expected);
+        let consequence_type = self.analyze_block_statement(&if_expr.consequence, branch_expected)?;
 
         match &if_expr.alternative {
             Some(alt_expr) => {
-                let alternative_type = self.analyze_expression(alt_expr)?;
+                let alternative_expected = match expected {
+                    Some(t) => Some(t),
+                    None if !flow::block_diverges(&if_expr.consequence) => Some(&consequence_type),
+                    None => None,
+                };
+                let alternative_type = self.analyze_expression_expecting(alt_expr, alternative_expected)?;
+                // 发散的分支（e.g., 以 `ret` 结尾）不会产生值，整个表达式的类型由另一个分支决定，
+                // 这使得 `if a > b { ret a } else { b }` 这样的写法可以通过检查。
+                if flow::block_diverges(&if_expr.consequence) {
+                    return Ok(alternative_type);
+                }
+                if flow::expression_diverges(alt_expr) {
+                    return Ok(consequence_type);
+                }
                 if consequence_type != alternative_type {
                     return Err(SemanticError::TypeMismatch {
                         expected: consequence_type,
                         found: alternative_type,
-                        span: Span::default(), // TODO: 从 alt_expr 获取 Span
+                        span: alt_expr.span(),
                     });
                 }
                 Ok(consequence_type)
@@ -367,18 +563,17 @@ impl SemanticAnalyzer {
     }
 
     /// 分析 `loop` 表达式。
-    fn analyze_loop_expression(&mut self, loop_expr: &LoopExpression) -> Result<Type, SemanticError> {
-        self.loop_depth += 1;
-        
-        // TODO: 一个更高级的实现会分析所有 `break value` 语句，
-        //       并推断出它们的“共同类型”作为 loop 的类型。
-        //       目前，我们先简化处理。
-        self.analyze_block_statement(&loop_expr.body)?;
-        
-        self.loop_depth -= 1;
-        
-        // 暂时假定所有 loop 都返回 void，除非有带值的 break (待实现)。
-        Ok(Type::Void)
+    ///
+    /// `loop` 的类型由其中的 `break` 决定：所有 `break <value>` 的值必须是同一类型，
+    /// 这个类型就是整个 `loop` 表达式的类型。没有任何带值 `break` 的 `loop` 类型为 `Void`。
+    fn analyze_loop_expression(&mut self, loop_expr: &LoopExpression, expected: Option<&Type>) -> Result<Type, SemanticError> {
+        self.loop_stack.push(LoopContext { kind: LoopKind::Loop, break_type: None, expected: expected.cloned() });
+        let result = self.analyze_block_statement(&loop_expr.body, None);
+        // 无论循环体是否出错都要弹出上下文，避免影响外层循环中 `break` 的分析
+        let context = self.loop_stack.pop().expect("loop context pushed above");
+        result?;
+
+        Ok(context.break_type.unwrap_or(Type::Void))
     }
 
     /// 分析 `while` 语句。
@@ -387,31 +582,58 @@ impl SemanticAnalyzer {
         if condition_type != Type::Bool {
             return Err(SemanticError::ConditionNotBoolean {
                 found: condition_type,
-                span: Span::default(), // TODO: 从 while_stmt.condition 获取 Span
+                span: while_stmt.condition.span(),
             });
         }
 
-        self.loop_depth += 1;
+        self.loop_stack.push(LoopContext { kind: LoopKind::While, break_type: None, expected: None });
         // `while` 循环是语句，不返回值，所以我们忽略 `analyze_block_statement` 的结果。
-        self.analyze_block_statement(&while_stmt.body)?;
-        self.loop_depth -= 1;
-
-        Ok(())
+        let result = self.analyze_block_statement(&while_stmt.body, None);
+        self.loop_stack.pop();
+        result.map(|_| ())
     }
 
     /// 分析 `break` 语句。
-    fn analyze_break_statement(&mut self, _break_stmt: &BreakStatement) -> Result<(), SemanticError> {
-        if self.loop_depth == 0 {
-            return Err(SemanticError::IllegalBreak { span: Span::default() }); // TODO: 从 _break_stmt 获取 Span
+    ///
+    /// 带值的 `break` 只能出现在 `loop` 中，并且值的类型必须与同一个 `loop`
+    /// 中之前的 `break` 一致。不带值的 `break` 在 `loop` 中相当于 `Void` 类型的值。
+    fn analyze_break_statement(&mut self, break_stmt: &BreakStatement) -> Result<(), SemanticError> {
+        // 之前的 `break` 已经确定了循环的类型时，以它为准；否则使用上下文对 `loop` 的期望
+        let (kind, value_expected) = match self.loop_stack.last() {
+            Some(context) => (context.kind, context.break_type.clone().or_else(|| context.expected.clone())),
+            None => return Err(SemanticError::IllegalBreak { span: break_stmt.span }),
+        };
+
+        let (break_type, span) = match &break_stmt.value {
+            Some(value) => {
+                if kind == LoopKind::While {
+                    return Err(SemanticError::BreakWithValueInWhile { span: break_stmt.span });
+                }
+                (self.analyze_expression_expecting(value, value_expected.as_ref())?, value.span())
+            }
+            None => (Type::Void, break_stmt.span),
+        };
+
+        // `analyze_expression` 可能分析了嵌套的循环，所以在这里重新获取栈顶
+        let context = self.loop_stack.last_mut().expect("checked above");
+        match &context.break_type {
+            Some(expected) if *expected != break_type => Err(SemanticError::TypeMismatch {
+                expected: expected.clone(),
+                found: break_type,
+                span,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                context.break_type = Some(break_type);
+                Ok(())
+            }
         }
-        // TODO: 分析 _break_stmt.value 的类型，并与当前循环的期望返回类型比较。
-        Ok(())
     }
 
     /// 分析 `continue` 语句。
-    fn analyze_continue_statement(&mut self, _cont_stmt: &ContinueStatement) -> Result<(), SemanticError> {
-        if self.loop_depth == 0 {
-            return Err(SemanticError::IllegalContinue { span: Span::default() }); // TODO: 从 _cont_stmt 获取 Span
+    fn analyze_continue_statement(&mut self, cont_stmt: &ContinueStatement) -> Result<(), SemanticError> {
+        if self.loop_stack.is_empty() {
+            return Err(SemanticError::IllegalContinue { span: cont_stmt.span });
         }
         Ok(())
     }
@@ -427,134 +649,304 @@ impl SemanticAnalyzer {
     /// - `Ok(Type)` 如果表达式及其所有子表达式都语义正确。
     /// - `Err(SemanticError)` 如果发现任何类型错误、未定义符号等问题。
     fn analyze_expression(&mut self, expression: &Expression) -> Result<Type, SemanticError> {
-        match expression {
-            Expression::Literal(lit) => self.analyze_literal_expression(lit),
-            Expression::Identifier(name) => self.analyze_identifier_expression(name),
+        self.analyze_expression_expecting(expression, None)
+    }
+
+    /// 在已知上下文期望类型的情况下分析一个表达式。
+    ///
+    /// `expected` 只是一个提示：它决定了没有固定类型的数字字面量（如 `10`、`1.5`）
+    /// 的类型，但并不保证表达式的类型就是 `expected`，检查类型是否匹配仍是调用者的责任。
+    fn analyze_expression_expecting(&mut self, expression: &Expression, expected: Option<&Type>) -> Result<Type, SemanticError> {
+        let expression_type = match expression {
+            Expression::Literal(lit) => self.analyze_literal_expression(lit, expected, None),
+            Expression::Identifier(ident) => self.analyze_identifier_expression(ident),
             Expression::Assignment(assign_expr) => self.analyze_assignment_expression(assign_expr),
-            Expression::Prefix(prefix_expr) => self.analyze_prefix_expression(prefix_expr),
-            Expression::Infix(infix_expr) => self.analyze_infix_expression(infix_expr),
+            Expression::Prefix(prefix_expr) => self.analyze_prefix_expression(prefix_expr, expected),
+            Expression::Infix(infix_expr) => self.analyze_infix_expression(infix_expr, expected),
             Expression::Call(call_expr) => self.analyze_call_expression(call_expr),
-            Expression::If(if_expr) => self.analyze_if_expression(if_expr),
-            Expression::Loop(loop_expr) => self.analyze_loop_expression(loop_expr),
-            Expression::Block(block_stmt) => self.analyze_block_statement(block_stmt),
-        }
+            Expression::If(if_expr) => self.analyze_if_expression(if_expr, expected),
+            Expression::Loop(loop_expr) => self.analyze_loop_expression(loop_expr, expected),
+            Expression::Block(block_stmt) => self.analyze_block_statement(block_stmt, expected),
+            Expression::Cast(cast_expr) => self.analyze_cast_expression(cast_expr),
+        }?;
+
+        // 记录下推断结果，供代码生成阶段使用
+        self.expression_types.insert(expression.id(), expression_type.clone());
+        Ok(expression_type)
     }
 
     // --- 表达式分析辅助函数 (Expression Analysis Helpers) ---
 
-    fn analyze_literal_expression(&self, lit: &Literal) -> Result<Type, SemanticError> {
-        // 根据字面量的种类，直接返回其对应的内部类型。
-        // 这是类型推断递归的基准情形 (base case)。
-        match lit {
-            Literal::Integer(_) => Ok(Type::I64), // TODO: 根据字面量后缀（如 10u8）推断更精确的整数类型
-            Literal::Float(_) => Ok(Type::F64),   // TODO: 支持 f32
+    /// 推断字面量的类型。这是类型推断递归的基准情形 (base case)。
+    ///
+    /// 带类型后缀的数字字面量（如 `10u8`、`1.5f32`）的类型就是后缀所写的类型。
+    /// 没有后缀时，类型取决于上下文：期望类型是整数（浮点数）类型时，整数（浮点数）字面量
+    /// 就取该类型，否则分别默认为 `i64` 和 `f64`。整数字面量的值必须在类型的取值范围内，
+    /// `negation` 是字面量前面紧跟的负号所在的前缀表达式的位置，此时检查的是取负之后的值。
+    fn analyze_literal_expression(&self, lit: &LiteralExpression, expected: Option<&Type>, negation: Option<Span>) -> Result<Type, SemanticError> {
+        match &lit.value {
+            Literal::Integer(value, suffix) => {
+                let the_type = match (suffix, expected) {
+                    (Some(suffix), _) => self.string_to_type(suffix, lit.span)?,
+                    (None, Some(t)) if t.is_integer() => t.clone(),
+                    (None, _) => Type::I64,
+                };
+                let value = if negation.is_some() { -(*value as i128) } else { *value as i128 };
+                if !the_type.contains_integer(value) {
+                    return Err(SemanticError::LiteralOutOfRange { value, the_type, span: negation.unwrap_or(lit.span) });
+                }
+                Ok(the_type)
+            }
+            Literal::Float(_, suffix) => match (suffix, expected) {
+                (Some(suffix), _) => self.string_to_type(suffix, lit.span),
+                (None, Some(Type::F32)) => Ok(Type::F32),
+                (None, _) => Ok(Type::F64),
+            },
             Literal::Boolean(_) => Ok(Type::Bool),
             Literal::Char(_) => Ok(Type::Char),
             Literal::String(_) => Ok(Type::Str),
         }
     }
 
-    fn analyze_identifier_expression(&self, name: &str) -> Result<Type, SemanticError> {
+    fn analyze_identifier_expression(&mut self, ident: &IdentifierExpression) -> Result<Type, SemanticError> {
         // 对于一个标识符，它的类型就是它在符号表中记录的类型。
-        if let Some(symbol) = self.symbol_table.lookup(name) {
-            Ok(symbol.symbol_type.clone())
+        if let Some(symbol) = self.symbol_table.lookup(&ident.name) {
+            let symbol_type = symbol.symbol_type.clone();
+            // 读取变量的值才算“使用”；作为赋值目标不算（见 `analyze_assignment_expression`）
+            self.symbol_table.mark_used(&ident.name);
+            Ok(symbol_type)
         } else {
             // 如果在符号表中找不到，说明该变量或函数未被定义。
             Err(SemanticError::SymbolNotFound {
-                name: name.to_string(),
-                span: Span::default(), // TODO: 从 Expression 节点获取 Span
+                name: ident.name.clone(),
+                span: ident.span,
             })
         }
     }
 
     fn analyze_assignment_expression(&mut self, assign_expr: &AssignmentExpression) -> Result<Type, SemanticError> {
         // 分析赋值表达式 e.g., `x = 10`
-        let value_type = self.analyze_expression(&assign_expr.value)?;
+        // 被赋值变量的类型就是右侧值的期望类型
+        let target_type = match &*assign_expr.left {
+            Expression::Identifier(ident) => self.symbol_table.lookup(&ident.name).map(|s| s.symbol_type.clone()),
+            _ => None,
+        };
+        let value_type = self.analyze_expression_expecting(&assign_expr.value, target_type.as_ref())?;
 
         // 检查赋值目标（左值 L-Value）
         // 目前，我们只支持对简单标识符的赋值。
-        if let Expression::Identifier(name) = &*assign_expr.left {
-            let symbol = match self.symbol_table.lookup(name) {
+        if let Expression::Identifier(ident) = &*assign_expr.left {
+            let symbol = match self.symbol_table.lookup(&ident.name) {
                 Some(s) => s,
                 None => return Err(SemanticError::SymbolNotFound {
-                    name: name.clone(),
-                    span: Span::default(), // TODO: Span
+                    name: ident.name.clone(),
+                    span: ident.span,
                 }),
             };
 
-            if !symbol.is_mutable {
+            if !symbol.is_mutable && symbol.initialized {
                 // 如果变量不是用 `~` 声明的，则不允许赋值。
-                // return Err(...) // TODO: 添加 `CannotAssignToImmutable` 错误
+                // 声明时没有初始值的不可变变量可以被赋值一次，这由 `init` 模块检查。
+                return Err(SemanticError::CannotAssignToImmutable {
+                    name: ident.name.clone(),
+                    span: assign_expr.span,
+                    declaration_span: symbol.span,
+                });
             }
 
             if symbol.symbol_type != value_type {
                 return Err(SemanticError::TypeMismatch {
                     expected: symbol.symbol_type.clone(),
                     found: value_type,
-                    span: Span::default(), // TODO: Span
+                    span: assign_expr.value.span(),
                 });
             }
 
+            // 复合赋值 `x op= v` 相当于 `x = x op v`，`op` 必须能作用于 `x` 的类型。
+            // 这里不把 `x` 标记为“已使用”：只被复合赋值修改、从未被读取的变量仍然是无用的
+            if let Some(op) = assign_expr.op {
+                Self::check_operand_type(op, &value_type, assign_expr.left.span())?;
+                // 代码生成阶段需要知道 `x` 的类型（例如按有符号还是无符号做除法）
+                self.expression_types.insert(assign_expr.left.id(), value_type.clone());
+            }
+
             // 赋值表达式本身的类型就是被赋的值的类型。
             Ok(value_type)
         } else {
             // 如果赋值目标不是一个标识符（例如 `5 = 10`），则为非法赋值。
-            Err(SemanticError::InvalidAssignmentTarget { span: Span::default() }) // TODO: Span
+            Err(SemanticError::InvalidAssignmentTarget { span: assign_expr.left.span() })
         }
     }
     
-    fn analyze_prefix_expression(&mut self, prefix_expr: &PrefixExpression) -> Result<Type, SemanticError> {
-        let right_type = self.analyze_expression(&prefix_expr.right)?;
-        
+    fn analyze_prefix_expression(&mut self, prefix_expr: &PrefixExpression, expected: Option<&Type>) -> Result<Type, SemanticError> {
+        let right_type = match (&prefix_expr.op, &*prefix_expr.right) {
+            // `-128` 要作为一个整体检查范围，否则 `128` 本身就超出了 `i8` 的范围
+            (PrefixOperator::Minus, Expression::Literal(lit)) => {
+                let literal_type = self.analyze_literal_expression(lit, expected, Some(prefix_expr.span))?;
+                self.expression_types.insert(lit.id, literal_type.clone());
+                literal_type
+            }
+            _ => self.analyze_expression_expecting(&prefix_expr.right, expected)?,
+        };
+
         match prefix_expr.op {
             PrefixOperator::Minus => match right_type {
-                Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::I128 | Type::Isize |
-                Type::F32 | Type::F64 => Ok(right_type), // 负号不改变数字类型
+                // 负号不改变数字类型。无符号整数没有负数，不能取负。
+                ref t if t.is_signed_integer() || t.is_float() => Ok(right_type),
                 _ => {
                     // FIXED: 使用我们新的、更具体的错误类型
                     Err(SemanticError::InvalidOperatorForType {
                         operator: "-".to_string(),
                         the_type: right_type,
-                        span: Span::default(), // TODO: 从 prefix_expr 获取 Span
+                        span: prefix_expr.span,
                     })
                 }
             },
             PrefixOperator::Not => {
-                if right_type == Type::Bool {
-                    Ok(Type::Bool) // `!` 作用于布尔值，结果仍是布尔值
+                // `!` 作用于布尔值时是逻辑非，作用于整数时是按位取反，结果都是原类型
+                if right_type == Type::Bool || right_type.is_integer() {
+                    Ok(right_type)
                 } else {
                     // FIXED: 完整地构造错误
                     Err(SemanticError::InvalidOperatorForType {
                         operator: "!".to_string(),
                         the_type: right_type,
-                        span: Span::default(), // TODO: 从 prefix_expr 获取 Span
+                        span: prefix_expr.span,
                     })
                 }
             }
         }
     }
 
-    fn analyze_infix_expression(&mut self, infix_expr: &InfixExpression) -> Result<Type, SemanticError> {
-        let left_type = self.analyze_expression(&infix_expr.left)?;
-        let right_type = self.analyze_expression(&infix_expr.right)?;
+    /// 分析中缀表达式。
+    ///
+    /// 两个操作数的类型必须相同，所以一侧的类型就是另一侧的期望类型：通常先分析左侧，
+    /// 但如果只有左侧是数字字面量（e.g., `10 < x`），就先分析右侧，再让字面量采用右侧的类型。
+    /// 算术运算的结果与操作数同类型，因此整个表达式的期望类型也会传给操作数；比较运算则不会。
+    fn analyze_infix_expression(&mut self, infix_expr: &InfixExpression, expected: Option<&Type>) -> Result<Type, SemanticError> {
+        if matches!(infix_expr.op, Operator::LogicalAnd | Operator::LogicalOr) {
+            return self.analyze_logical_expression(infix_expr);
+        }
+
+        let operand_expected = match infix_expr.op {
+            Operator::Plus | Operator::Minus | Operator::Multiply | Operator::Divide | Operator::Remainder |
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::ShiftLeft | Operator::ShiftRight => expected,
+            _ => None,
+        };
+
+        let (left_type, right_type) =
+            if Self::is_numeric_literal(&infix_expr.left) && !Self::is_numeric_literal(&infix_expr.right) {
+                let right_type = self.analyze_expression_expecting(&infix_expr.right, operand_expected)?;
+                let left_type = self.analyze_expression_expecting(&infix_expr.left, Some(&right_type))?;
+                (left_type, right_type)
+            } else {
+                let left_type = self.analyze_expression_expecting(&infix_expr.left, operand_expected)?;
+                let right_type = self.analyze_expression_expecting(&infix_expr.right, Some(&left_type))?;
+                (left_type, right_type)
+            };
 
-        // TODO: 更复杂的类型规则，例如 i32 + f64 的类型提升
+        // Tipy 不做隐式的类型提升，`i32 + f64` 这样的运算需要先用 `as` 显式转换
         if left_type != right_type {
-            return Err(SemanticError::TypeMismatch { expected: left_type, found: right_type, span: Span::default() });
+            return Err(SemanticError::TypeMismatch { expected: left_type, found: right_type, span: infix_expr.right.span() });
         }
 
         match infix_expr.op {
-            // 算术运算返回原类型
-            Operator::Plus | Operator::Minus | Operator::Multiply | Operator::Divide => {
-                // 确保操作数是数字类型
+            // 算术、取余、位运算和移位返回原类型。
+            // 移位量也必须与被移位的值类型相同：`x << 3` 中的 `3` 会被推断为 `x` 的类型
+            Operator::Plus | Operator::Minus | Operator::Multiply | Operator::Divide | Operator::Remainder |
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::ShiftLeft | Operator::ShiftRight => {
+                Self::check_operand_type(infix_expr.op, &left_type, infix_expr.left.span())?;
                 Ok(left_type)
             },
             // 比较运算总是返回布尔类型
-            Operator::Equal | Operator::NotEqual | Operator::LessThan |
-            Operator::LessEqual | Operator::GreaterThan | Operator::GreaterEqual => {
+            Operator::Equal | Operator::NotEqual => {
+                // 函数和 `void` 没有可以比较的值
+                let comparable = left_type.is_numeric()
+                    || matches!(left_type, Type::Bool | Type::Char | Type::Str | Type::Pointer { .. });
+                if !comparable {
+                    return Err(SemanticError::InvalidOperatorForType {
+                        operator: infix_expr.op.to_string(),
+                        the_type: left_type,
+                        span: infix_expr.left.span(),
+                    });
+                }
+                Ok(Type::Bool)
+            },
+            Operator::LessThan | Operator::LessEqual | Operator::GreaterThan | Operator::GreaterEqual => {
+                // 字符串和指针只能比较是否相等，不能比较大小
+                if !(left_type.is_numeric() || matches!(left_type, Type::Bool | Type::Char)) {
+                    return Err(SemanticError::InvalidOperatorForType {
+                        operator: infix_expr.op.to_string(),
+                        the_type: left_type,
+                        span: infix_expr.left.span(),
+                    });
+                }
                 Ok(Type::Bool)
             },
+            // 已在函数开头交给 `analyze_logical_expression` 处理
+            Operator::LogicalAnd | Operator::LogicalOr => Ok(Type::Bool),
+        }
+    }
+
+    /// 检查算术、取余、位运算或移位运算符 `op` 能否作用于类型为 `the_type` 的操作数。
+    ///
+    /// `+ - * /` 要求数字类型，`% & | ^ << >>` 只用于整数。中缀表达式和复合赋值共用这个规则。
+    fn check_operand_type(op: Operator, the_type: &Type, span: Span) -> Result<(), SemanticError> {
+        let allowed = match op {
+            Operator::Plus | Operator::Minus | Operator::Multiply | Operator::Divide => the_type.is_numeric(),
+            _ => the_type.is_integer(),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(SemanticError::InvalidOperatorForType { operator: op.to_string(), the_type: the_type.clone(), span })
+        }
+    }
+
+    /// 分析逻辑运算 `&&` 和 `||`。两个操作数都必须是 `bool`，结果也是 `bool`。
+    ///
+    /// 与其他二元运算不同，这里先单独检查左操作数：`1 && true` 应该报告
+    /// “`&&` 不能用于 `i64`”，而不是“期望 `i64`，找到 `bool`”。
+    fn analyze_logical_expression(&mut self, infix_expr: &InfixExpression) -> Result<Type, SemanticError> {
+        let left_type = self.analyze_expression_expecting(&infix_expr.left, Some(&Type::Bool))?;
+        if left_type != Type::Bool {
+            return Err(SemanticError::InvalidOperatorForType {
+                operator: infix_expr.op.to_string(),
+                the_type: left_type,
+                span: infix_expr.left.span(),
+            });
+        }
+
+        let right_type = self.analyze_expression_expecting(&infix_expr.right, Some(&Type::Bool))?;
+        if right_type != Type::Bool {
+            return Err(SemanticError::TypeMismatch { expected: Type::Bool, found: right_type, span: infix_expr.right.span() });
+        }
+        Ok(Type::Bool)
+    }
+
+    /// 分析类型转换表达式 `value as T`，检查 `as` 是否支持从 `value` 的类型转换到 `T`。
+    ///
+    /// 被转换的值没有期望类型：`300 as u8` 中的 `300` 按默认的 `i64` 处理，再被截断为 `u8`。
+    fn analyze_cast_expression(&mut self, cast_expr: &CastExpression) -> Result<Type, SemanticError> {
+        let from = self.analyze_expression(&cast_expr.value)?;
+        let to = self.string_to_type(&cast_expr.target_type, cast_expr.span)?;
+
+        let is_pointer = |t: &Type| matches!(t, Type::Pointer { .. });
+        let allowed = from == to
+            // 数字类型之间可以任意转换
+            || (from.is_numeric() && to.is_numeric())
+            // `bool` 和 `char` 可以转换为整数
+            || (matches!(from, Type::Bool | Type::Char) && to.is_integer())
+            // 只有 `u8` 和 `u32` 可以转换为 `char`
+            || (matches!(from, Type::U8 | Type::U32) && to == Type::Char)
+            // 指针之间、指针与整数之间可以转换
+            || (is_pointer(&from) && (is_pointer(&to) || to.is_integer()))
+            || (from.is_integer() && is_pointer(&to));
+
+        if !allowed {
+            return Err(SemanticError::InvalidCast { from, to, span: cast_expr.span });
         }
+        Ok(to)
     }
 
     fn analyze_call_expression(&mut self, call_expr: &CallExpression) -> Result<Type, SemanticError> {
@@ -567,51 +959,114 @@ impl SemanticAnalyzer {
                     return Err(SemanticError::ArityMismatch {
                         expected: expected_params.len(),
                         found: call_expr.arguments.len(),
-                        span: Span::default(), // TODO: Span
+                        span: call_expr.span,
                     });
                 }
                 // 2. 检查每个参数的类型
                 for (arg_expr, expected_type) in call_expr.arguments.iter().zip(expected_params.iter()) {
-                    let arg_type = self.analyze_expression(arg_expr)?;
+                    let arg_type = self.analyze_expression_expecting(arg_expr, Some(expected_type))?;
                     if arg_type != *expected_type {
                         return Err(SemanticError::TypeMismatch {
                             expected: expected_type.clone(),
                             found: arg_type,
-                            span: Span::default(), // TODO: Span
+                            span: arg_expr.span(),
                         });
                     }
                 }
                 // 3. 所有检查通过，返回函数的返回类型
                 Ok(*ret_type)
             },
+            Type::Overloaded(overloads) => self.analyze_overloaded_call(call_expr, &overloads),
             other_type => Err(SemanticError::NotAFunction {
                 found: other_type,
-                span: Span::default(), // TODO: Span
+                span: call_expr.function.span(),
+            }),
+        }
+    }
+
+    /// 分析对重载的内置函数（如 `print`）的调用，根据实参的类型选出唯一匹配的重载。
+    ///
+    /// 实参在没有期望类型的情况下分析，所以 `print(10)` 中的 `10` 是 `i64`。
+    /// 代码生成阶段根据记录下的实参类型决定如何展开调用。
+    fn analyze_overloaded_call(&mut self, call_expr: &CallExpression, overloads: &[Type]) -> Result<Type, SemanticError> {
+        let mut arg_types = Vec::new();
+        for arg_expr in &call_expr.arguments {
+            arg_types.push(self.analyze_expression(arg_expr)?);
+        }
+
+        let selected = overloads.iter().find_map(|overload| match overload {
+            Type::Function { params, ret } if *params == arg_types => Some(ret),
+            _ => None,
+        });
+        // 重载只来自 prelude，被调用者总是一个标识符
+        let name = match &*call_expr.function {
+            Expression::Identifier(ident) => ident.name.clone(),
+            _ => "function".to_string(),
+        };
+        match selected {
+            Some(ret_type) => Ok((**ret_type).clone()),
+            None => Err(SemanticError::NoMatchingOverload {
+                name,
+                arg_types,
+                candidates: overloads.to_vec(),
+                span: call_expr.span,
             }),
         }
     }
 
+    /// 判断表达式是否是一个类型由上下文决定的数字字面量，e.g., `10`、`1.5`、`-1`。
+    /// 带类型后缀的字面量（如 `10u8`）类型是确定的，不算在内。
+    fn is_numeric_literal(expr: &Expression) -> bool {
+        match expr {
+            Expression::Literal(lit) => matches!(lit.value, Literal::Integer(_, None) | Literal::Float(_, None)),
+            Expression::Prefix(prefix) => prefix.op == PrefixOperator::Minus && Self::is_numeric_literal(&prefix.right),
+            _ => false,
+        }
+    }
+
     /// 将 AST 中的类型字符串（如 "i32", "^~bool"）解析为内部的 `Type` 枚举。
     ///
     /// 这是类型解析的核心。它能够处理原生类型、指针类型等。
     ///
     /// # Arguments
     /// * `type_str` - 从 AST 节点（如 `VarDeclaration`）中获取的类型字符串。
+    /// * `span` - 写出该类型的声明所在的位置，类型未知时用于报告错误。
     ///
     /// # Returns
     /// - `Ok(Type)` 如果字符串是一个合法的、已知的类型。
     /// - `Err(SemanticError)` 如果类型名称未知。
-    fn string_to_type(&self, type_str: &str) -> Result<Type, SemanticError> {
+    fn string_to_type(&self, type_str: &str, span: Span) -> Result<Type, SemanticError> {
         // TODO: 这是一个简化的实现。一个完整的实现会更健壮，
         //       并且能够解析用户自定义的类型（如类名）。
         //       目前，我们先支持原生类型和指针。
         
+        // 指针类型：`^T` 指向不可变的数据，`^~T` 指向可变的数据
+        if let Some(rest) = type_str.strip_prefix('^') {
+            let (is_mutable_pointee, pointee) = match rest.strip_prefix('~') {
+                Some(pointee) => (true, pointee),
+                None => (false, rest),
+            };
+            return Ok(Type::Pointer {
+                is_mutable_ptr: false,
+                is_mutable_pointee,
+                pointee: Box::new(self.string_to_type(pointee, span)?),
+            });
+        }
+
         // 暂时简单地根据字符串匹配返回类型
         match type_str {
             "i8" => Ok(Type::I8),
             "i16" => Ok(Type::I16),
             "i32" => Ok(Type::I32),
             "i64" => Ok(Type::I64),
+            "i128" => Ok(Type::I128),
+            "isize" => Ok(Type::Isize),
+            "u8" => Ok(Type::U8),
+            "u16" => Ok(Type::U16),
+            "u32" => Ok(Type::U32),
+            "u64" => Ok(Type::U64),
+            "u128" => Ok(Type::U128),
+            "usize" => Ok(Type::Usize),
             "f32" => Ok(Type::F32),
             "f64" => Ok(Type::F64),
             "bool" => Ok(Type::Bool),
@@ -623,13 +1078,245 @@ impl SemanticAnalyzer {
                 // 因为一个未知的类型名，本质上就是一个未定义的类型符号。
                 Err(SemanticError::SymbolNotFound {
                     name: type_str.to_string(),
-                    // TODO: 这里需要一个真实的 Span
-                    span: Span::default(),
+                    span,
                 })
             }
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    // 解析并分析源码，返回所有语义错误
+    fn analyze(source: &str) -> Vec<SemanticError> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "parse errors: {:?}", parser.errors);
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program);
+        analyzer
+            .errors
+            .into_iter()
+            .map(|e| match e {
+                CompilerError::Semantic(e) => e,
+                other => panic!("unexpected error: {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn if_without_else_is_missing_a_return() {
+        let source = "f(c: bool) -> i64 {\n    if c { ret 1; }\n}\n";
+        match &analyze(source)[..] {
+            [SemanticError::MissingReturn { name, return_type, span, signature_span }] => {
+                assert_eq!(name, "f");
+                assert_eq!(*return_type, Type::I64);
+                // 错误指向函数体结尾的 `}`
+                assert_eq!((span.line, span.column, span.start_byte, span.end_byte), (3, 1, 40, 41));
+                assert_eq!(&source[signature_span.start_byte..signature_span.end_byte], "f(c: bool) -> i64");
+            }
+            other => panic!("expected a missing return, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn if_chain_returning_on_every_branch() {
+        assert_eq!(analyze("f(a: bool, b: bool) -> i64 { if a { ret 1; } elif b { ret 2; } else { ret 3; } }"), vec![]);
+        assert!(matches!(
+            &analyze("f(a: bool, b: bool) -> i64 { if a { ret 1; } elif b { ret 2; } }")[..],
+            [SemanticError::MissingReturn { .. }],
+        ));
+    }
+
+    #[test]
+    fn infinite_loop_needs_no_return() {
+        assert_eq!(analyze("f() -> i64 { loop { } }"), vec![]);
+    }
+
+    #[test]
+    fn loop_with_break_is_missing_a_return() {
+        assert!(matches!(&analyze("f() -> i64 { loop { break; } }")[..], [SemanticError::MissingReturn { .. }]));
+    }
+
+    #[test]
+    fn trailing_expression_returns() {
+        assert_eq!(analyze("f(a: i64) -> i64 { a + 1 }"), vec![]);
+        assert_eq!(analyze("f(c: bool, a: i64) -> i64 { if c { a } else { ret 0; } }"), vec![]);
+        assert_eq!(
+            analyze("f() -> i64 { true }"),
+            vec![SemanticError::TypeMismatch {
+                expected: Type::I64,
+                found: Type::Bool,
+                span: Span { line: 1, column: 14, start_byte: 13, end_byte: 17 },
+            }],
+        );
+    }
 
+    #[test]
+    fn void_functions_need_no_return() {
+        assert_eq!(analyze("f(c: bool) { if c { ret; } }"), vec![]);
+    }
+
+    #[test]
+    fn integer_literal_takes_the_declared_type() {
+        assert_eq!(analyze("f() -> i32 { a: i32 = 10; a }"), vec![]);
+        assert_eq!(analyze("f() -> f32 { a: f32 = 1.5; a }"), vec![]);
+    }
 
+    #[test]
+    fn integer_literal_out_of_range() {
+        assert_eq!(
+            analyze("f() -> u8 {\n    b: u8 = 300;\n    b\n}\n"),
+            vec![SemanticError::LiteralOutOfRange {
+                value: 300,
+                the_type: Type::U8,
+                span: Span { line: 2, column: 13, start_byte: 24, end_byte: 27 },
+            }],
+        );
+    }
+
+    #[test]
+    fn negative_literal_is_checked_after_negation() {
+        assert_eq!(analyze("f() -> i8 { x: i8 = -128; x }"), vec![]);
+        // 错误覆盖负号和数字
+        assert_eq!(
+            analyze("f() -> i8 {\n    x: i8 = -129;\n    x\n}\n"),
+            vec![SemanticError::LiteralOutOfRange {
+                value: -129,
+                the_type: Type::I8,
+                span: Span { line: 2, column: 13, start_byte: 24, end_byte: 28 },
+            }],
+        );
+    }
+
+    #[test]
+    fn literal_operand_takes_the_type_of_the_other_operand() {
+        assert_eq!(analyze("f(x: u8) -> bool { 10 < x }"), vec![]);
+        assert_eq!(analyze("f(x: u8) -> bool { x < 10 }"), vec![]);
+        assert!(matches!(
+            &analyze("f(x: u8) -> bool { 300 < x }")[..],
+            [SemanticError::LiteralOutOfRange { value: 300, the_type: Type::U8, .. }],
+        ));
+    }
+
+    #[test]
+    fn literal_in_return_and_argument_position() {
+        assert_eq!(analyze("f() -> u8 { ret 255; }"), vec![]);
+        assert!(matches!(
+            &analyze("f() -> u8 { ret 256; }")[..],
+            [SemanticError::LiteralOutOfRange { value: 256, the_type: Type::U8, .. }],
+        ));
+        assert_eq!(analyze("g(v: u16) -> u16 { v }\nf() -> u16 { g(65535) }"), vec![]);
+        assert!(matches!(
+            &analyze("g(v: u16) -> u16 { v }\nf() -> u16 { g(65536) }")[..],
+            [SemanticError::LiteralOutOfRange { value: 65536, the_type: Type::U16, .. }],
+        ));
+    }
+
+    #[test]
+    fn allowed_casts() {
+        assert_eq!(analyze("f(x: i64) -> u8 { x as u8 }"), vec![]);
+        assert_eq!(analyze("f(x: u8) -> i64 { x as i64 }"), vec![]);
+        assert_eq!(analyze("f(x: i32) -> f64 { x as f64 }"), vec![]);
+        assert_eq!(analyze("f(x: f64) -> i16 { x as i16 }"), vec![]);
+        assert_eq!(analyze("f(x: bool) -> i32 { x as i32 }"), vec![]);
+        assert_eq!(analyze("f(c: char) -> u32 { c as u32 }"), vec![]);
+        assert_eq!(analyze("f(x: u32) -> char { x as char }"), vec![]);
+        assert_eq!(analyze("f(p: ^i64) -> ^~u8 { p as ^~u8 }"), vec![]);
+        assert_eq!(analyze("f(p: ^i64) -> usize { p as usize }"), vec![]);
+        assert_eq!(analyze("f(a: usize) -> ^i64 { a as ^i64 }"), vec![]);
+    }
+
+    #[test]
+    fn invalid_casts() {
+        assert_eq!(
+            analyze("f(x: f64) -> bool { x as bool }"),
+            vec![SemanticError::InvalidCast {
+                from: Type::F64,
+                to: Type::Bool,
+                span: Span { line: 1, column: 21, start_byte: 20, end_byte: 29 },
+            }],
+        );
+        let rejected = [
+            ("f(x: i64) -> bool { x as bool }", Type::I64, Type::Bool),
+            ("f(x: bool) -> f64 { x as f64 }", Type::Bool, Type::F64),
+            ("f(x: i32) -> char { x as char }", Type::I32, Type::Char),
+            ("f(c: char) -> f32 { c as f32 }", Type::Char, Type::F32),
+            ("f(s: str) -> i64 { s as i64 }", Type::Str, Type::I64),
+        ];
+        for (source, from, to) in rejected {
+            assert!(
+                matches!(&analyze(source)[..], [SemanticError::InvalidCast { from: f, to: t, .. }] if *f == from && *t == to),
+                "{} should be rejected",
+                source,
+            );
+        }
+        assert!(matches!(&analyze("f(p: ^i64) -> f64 { p as f64 }")[..], [SemanticError::InvalidCast { .. }]));
+    }
+
+    #[test]
+    fn equality_needs_comparable_operands() {
+        assert_eq!(analyze("f(a: str, b: str) -> bool { a == b }"), vec![]);
+        assert_eq!(analyze("f(a: ^i64, b: ^i64) -> bool { a != b }"), vec![]);
+        assert_eq!(analyze("f(a: char, b: char) -> bool { a == b }"), vec![]);
+        assert_eq!(
+            analyze("g() { }\nf() -> bool { g == g }"),
+            vec![SemanticError::InvalidOperatorForType {
+                operator: "==".to_string(),
+                the_type: Type::Function { params: vec![], ret: Box::new(Type::Void) },
+                span: Span { line: 2, column: 15, start_byte: 22, end_byte: 23 },
+            }],
+        );
+        assert!(matches!(
+            &analyze("g() { }\nf() -> bool { g() != g() }")[..],
+            [SemanticError::InvalidOperatorForType { the_type: Type::Void, .. }],
+        ));
+    }
+
+    #[test]
+    fn ordering_needs_ordered_operands() {
+        assert_eq!(analyze("f(a: bool, b: bool) -> bool { a < b }"), vec![]);
+        assert!(matches!(
+            &analyze("f(a: str, b: str) -> bool { a < b }")[..],
+            [SemanticError::InvalidOperatorForType { the_type: Type::Str, .. }],
+        ));
+        assert!(matches!(
+            &analyze("f(a: ^i64, b: ^i64) -> bool { a >= b }")[..],
+            [SemanticError::InvalidOperatorForType { the_type: Type::Pointer { .. }, .. }],
+        ));
+    }
+
+    #[test]
+    fn compound_assignment_needs_a_mutable_target() {
+        let source = "f() -> i64 {\n    x: i64 = 1;\n    x += 1;\n    x\n}\n";
+        match &analyze(source)[..] {
+            [SemanticError::CannotAssignToImmutable { name, span, .. }] => {
+                assert_eq!(name, "x");
+                assert_eq!(&source[span.start_byte..span.end_byte], "x += 1");
+            }
+            other => panic!("expected an assignment to an immutable variable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compound_assignment_checks_the_operator() {
+        assert_eq!(analyze("f(x: ~u8) -> u8 { x <<= 1; x %= 3; x }"), vec![]);
+        assert_eq!(analyze("f(x: ~f64) -> f64 { x /= 2.0; x }"), vec![]);
+        assert_eq!(
+            analyze("f(s: ~str) -> str { s += \"a\"; s }"),
+            vec![SemanticError::InvalidOperatorForType {
+                operator: "+".to_string(),
+                the_type: Type::Str,
+                span: Span { line: 1, column: 21, start_byte: 20, end_byte: 21 },
+            }],
+        );
+        assert!(matches!(
+            &analyze("f(x: ~f64) -> f64 { x %= 2.0; x }")[..],
+            [SemanticError::InvalidOperatorForType { operator, the_type: Type::F64, .. }] if operator == "%",
+        ));
+    }
+}
@@ -1,6 +1,17 @@
 // src/ast.rs
 
+use std::fmt;
+
 use crate::token::Literal;
+use crate::diagnostics::Span;
+
+/// AST 节点的编号，由语法分析器按创建顺序分配，在一个程序中唯一。
+///
+/// 语义分析以它为键记录每个表达式和变量声明的类型，供代码生成查询。
+/// 不能用 `Span` 作为键：两个节点可以覆盖同一段源码（e.g., 语法糖展开出的节点），
+/// 此时它们的类型会互相覆盖。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(pub u32);
 
 // 整个程序的根节点
 // 一个 Tipy 程序是由一系列顶层声明构成的集合。
@@ -8,11 +19,13 @@ use crate::token::Literal;
 #[derive(Debug, PartialEq, Clone)]
 pub struct Program {
     pub body: Vec<TopLevelStatement>,
+    /// 文件中 `//!` 内部文档注释的内容，每行一项，描述整个文件
+    pub docs: Vec<String>,
 }
 
 impl Program {
     pub fn new() -> Self {
-        Program { body: Vec::new() }
+        Program { body: Vec::new(), docs: Vec::new() }
     }
 }
 
@@ -47,9 +60,9 @@ pub enum Statement {
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     /// 标识符, e.g., `my_var`
-    Identifier(String),
+    Identifier(IdentifierExpression),
     /// 字面量, e.g., `123`, `"hello"`
-    Literal(Literal),
+    Literal(LiteralExpression),
     /// 前缀表达式, e.g., `-10`
     Prefix(PrefixExpression),
     /// 二元运算表达式, e.g., `a + b`
@@ -64,9 +77,64 @@ pub enum Expression {
     Loop(LoopExpression),
     /// 代码块本身也可以是一个表达式，其值为块中最后一条表达式的值
     Block(BlockStatement),
+    /// 类型转换表达式, e.g., `x as f64`
+    Cast(CastExpression),
+}
+
+impl Statement {
+    /// 返回该语句在源码中覆盖的范围。
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VarDeclaration(s) => s.span,
+            Statement::Expression(e) => e.span(),
+            Statement::Return(s) => s.span,
+            Statement::Block(s) => s.span,
+            Statement::While(s) => s.span,
+            Statement::Break(s) => s.span,
+            Statement::Continue(s) => s.span,
+        }
+    }
+}
+
+impl Expression {
+    /// 返回该表达式在源码中覆盖的范围。
+    /// 对于 `a + b` 这样的复合表达式，范围从 `a` 的开头一直延伸到 `b` 的结尾。
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Identifier(e) => e.span,
+            Expression::Literal(e) => e.span,
+            Expression::Prefix(e) => e.span,
+            Expression::Infix(e) => e.span,
+            Expression::Assignment(e) => e.span,
+            Expression::Call(e) => e.span,
+            Expression::If(e) => e.span,
+            Expression::Loop(e) => e.span,
+            Expression::Block(e) => e.span,
+            Expression::Cast(e) => e.span,
+        }
+    }
+
+    /// 返回该表达式的节点编号。代码块表达式使用代码块本身的编号。
+    pub fn id(&self) -> NodeId {
+        match self {
+            Expression::Identifier(e) => e.id,
+            Expression::Literal(e) => e.id,
+            Expression::Prefix(e) => e.id,
+            Expression::Infix(e) => e.id,
+            Expression::Assignment(e) => e.id,
+            Expression::Call(e) => e.id,
+            Expression::If(e) => e.id,
+            Expression::Loop(e) => e.id,
+            Expression::Block(e) => e.id,
+            Expression::Cast(e) => e.id,
+        }
+    }
 }
 
 // --- 具体的 AST 节点定义 ---
+//
+// 每个节点都带有一个 `span` 字段，记录它在源码中的位置，
+// 供语义分析报告错误，以及未来的工具（LSP、格式化、调试信息）使用。
 
 /// 函数声明节点
 /// e.g., `add(a: i32, b: i32) -> i32 { ... }`
@@ -78,6 +146,10 @@ pub struct FunctionDeclaration {
     // 如果没有返回箭头 `->`，则为 "void" 或类似的内部表示
     pub return_type: String, 
     pub body: BlockStatement, // 函数体总是一个代码块
+    /// 函数签名的范围，从函数名到返回类型（不含函数体）
+    pub span: Span,
+    /// 紧贴在函数前面的 `///` 文档注释，每行一项（不含 `///` 本身）
+    pub docs: Vec<String>,
 }
 
 /// 函数参数节点
@@ -85,8 +157,12 @@ pub struct FunctionDeclaration {
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionParameter {
     pub name: String,
+    /// 参数是否用 `~` 声明为可变，e.g., `count: ~i32`
+    pub is_mutable: bool,
     // 参数类型，同样用 String 存储
     pub param_type: String,
+    /// 整个参数 `a: i32` 的范围
+    pub span: Span,
 }
 
 /// 变量声明节点
@@ -94,8 +170,11 @@ pub struct FunctionParameter {
 pub struct VarDeclaration {
     pub name: String,
     pub is_mutable: bool,
-    pub var_type: String,
+    /// 声明的类型。`x := 10` 这样省略类型的声明为 `None`，由语义分析根据初始值推断
+    pub var_type: Option<String>,
     pub value: Option<Expression>, // 初始值可选
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// 返回语句节点
@@ -103,12 +182,34 @@ pub struct VarDeclaration {
 pub struct ReturnStatement {
     // `ret;` -> None, `ret value;` -> Some(value)
     pub value: Option<Expression>,
+    pub span: Span,
 }
 
 /// 代码块节点
 #[derive(Debug, PartialEq, Clone)]
 pub struct BlockStatement {
     pub statements: Vec<Statement>,
+    /// 从 `{` 到 `}` 的范围
+    pub span: Span,
+    pub id: NodeId,
+    /// 结尾的 `}` 本身，用于指向“代码块末尾”的诊断，e.g., 缺少返回值
+    pub closing_brace: Span,
+}
+
+/// 标识符表达式节点
+#[derive(Debug, PartialEq, Clone)]
+pub struct IdentifierExpression {
+    pub name: String,
+    pub span: Span,
+    pub id: NodeId,
+}
+
+/// 字面量表达式节点
+#[derive(Debug, PartialEq, Clone)]
+pub struct LiteralExpression {
+    pub value: Literal,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// 前缀表达式节点
@@ -116,6 +217,8 @@ pub struct BlockStatement {
 pub struct PrefixExpression {
     pub op: PrefixOperator,
     pub right: Box<Expression>,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// 二元(中缀)运算表达式节点
@@ -124,13 +227,20 @@ pub struct InfixExpression {
     pub op: Operator,
     pub left: Box<Expression>,
     pub right: Box<Expression>,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// 赋值表达式节点
+/// e.g., `x = 10`, `x += 1`
 #[derive(Debug, PartialEq, Clone)]
 pub struct AssignmentExpression {
     pub left: Box<Expression>, 
     pub value: Box<Expression>,
+    /// 复合赋值使用的运算符，e.g., `x += 1` 中的 `Plus`。普通赋值 `=` 为 `None`
+    pub op: Option<Operator>,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// 函数调用表达式节点
@@ -139,6 +249,19 @@ pub struct CallExpression {
     // 被调用的函数可以是一个标识符 `foo()`，也可以是另一个表达式 `get_func()()`
     pub function: Box<Expression>, 
     pub arguments: Vec<Expression>,
+    pub span: Span,
+    pub id: NodeId,
+}
+
+/// 类型转换表达式节点
+/// e.g., `x as f64`, `c as u32`
+#[derive(Debug, PartialEq, Clone)]
+pub struct CastExpression {
+    pub value: Box<Expression>,
+    /// 目标类型，与变量声明一样以字符串形式保存，由语义分析阶段解析。
+    pub target_type: String,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// If 表达式节点
@@ -151,12 +274,16 @@ pub struct IfExpression {
     // `else` 分支是可选的。如果存在，它也是一个表达式。
     // 这允许 `else if ...` 链式结构。
     pub alternative: Option<Box<Expression>>, 
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// loop 表达式节点
 #[derive(Debug, PartialEq, Clone)]
 pub struct LoopExpression {
     pub body: BlockStatement,
+    pub span: Span,
+    pub id: NodeId,
 }
 
 /// while 语句节点
@@ -164,6 +291,7 @@ pub struct LoopExpression {
 pub struct WhileStatement {
     pub condition: Expression,
     pub body: BlockStatement,
+    pub span: Span,
 }
 
 /// break 语句节点
@@ -171,11 +299,14 @@ pub struct WhileStatement {
 pub struct BreakStatement {
     // `break;` -> None, `break value;` -> Some(value)
     pub value: Option<Expression>,
+    pub span: Span,
 }
 
-/// continue 语句节点 (它没有额外数据)
+/// continue 语句节点 (除位置外没有额外数据)
 #[derive(Debug, PartialEq, Clone)]
-pub struct ContinueStatement;
+pub struct ContinueStatement {
+    pub span: Span,
+}
 
 // --- 操作符枚举 ---
 
@@ -186,6 +317,13 @@ pub enum Operator {
     Minus,
     Multiply,
     Divide,
+    Remainder, // %
+    // 位运算（只用于整数）
+    BitAnd,     // &
+    BitOr,      // |
+    BitXor,     // ^
+    ShiftLeft,  // <<
+    ShiftRight, // >>
     // 比较
     Equal,        // ==
     NotEqual,     // !=
@@ -193,10 +331,40 @@ pub enum Operator {
     LessEqual,    // <=
     GreaterThan,  // >
     GreaterEqual, // >=
+    // 逻辑（短路求值）
+    LogicalAnd, // &&
+    LogicalOr,  // ||
+}
+
+/// 打印运算符在源码中的写法，用于诊断信息，e.g., `cannot apply operator `+``。
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "^",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::LessThan => "<",
+            Operator::LessEqual => "<=",
+            Operator::GreaterThan => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::LogicalAnd => "&&",
+            Operator::LogicalOr => "||",
+        };
+        write!(f, "{}", symbol)
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PrefixOperator {
     Minus, // -
-    Not,   // !
+    Not,   // !，作用于布尔值时是逻辑非，作用于整数时是按位取反
 }
\ No newline at end of file
@@ -0,0 +1,457 @@
+// file: src/cli.rs
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::diagnostics::{LintLevel, LintLevels, LINT_NAMES};
+use crate::doc::DocFormat;
+use crate::emitter::{ColorChoice, ErrorFormat};
+
+/// `tipyc --help` 打印的用法说明。
+pub const USAGE: &str = "\
+Usage: tipyc [COMMAND] <FILE> [OPTIONS]
+
+Commands:
+    build      Compile FILE (default: a native executable)
+    check      Run lexing, parsing and semantic analysis only
+    emit-ir    Compile FILE to textual LLVM IR (same as `build --emit=llvm-ir`)
+    doc        Generate documentation for FILE from its `///` and `//!` doc comments
+
+Options:
+    -o <PATH>              Write the output to PATH (`-o -` prints LLVM IR or documentation to stdout)
+    --emit=<KIND>[,KIND]   Comma-separated list of artifacts to produce:
+                           tokens, ast, llvm-ir, asm, obj, exe
+    --color=<WHEN>         Colorize diagnostics: auto (default), always, never
+    --error-format=<FMT>   Diagnostic format: human (default) or json (one object per line)
+    --doc-format=<FMT>     Documentation format for `doc`: html (default) or markdown
+    -W, --warn <LINT>      Report LINT as a warning (the default)
+    -A, --allow <LINT>     Do not report LINT
+    -D, --deny <LINT>      Report LINT as an error; `-D warnings` turns every warning into an error
+                           Lints: unused_variables, unreachable_code
+    --explain <CODE>       Print a detailed explanation of an error code, e.g. `--explain E0101`
+    -h, --help             Print this help message
+    -V, --version          Print the compiler version
+
+If COMMAND is omitted, `build` is assumed.
+Executables are linked with `cc`; set the CC environment variable to use another driver.";
+
+/// `tipyc` 支持的子命令。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// `tipyc build`: 完整编译，默认产出可执行文件。
+    Build,
+    /// `tipyc check`: 只运行前端（词法、语法、语义分析），不生成任何代码。
+    Check,
+    /// `tipyc emit-ir`: `tipyc build --emit=llvm-ir` 的简写。
+    EmitIr,
+    /// `tipyc doc`: 运行前端，然后根据文档注释和函数签名生成文档。
+    Doc,
+}
+
+/// `--emit` 可以请求的产物种类。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitKind {
+    /// 词法分析得到的 Token 流，打印到标准输出。
+    Tokens,
+    /// 语法分析得到的 AST，打印到标准输出。
+    Ast,
+    /// 文本形式的 LLVM IR (`.ll`)。
+    LlvmIr,
+    /// 目标平台的汇编代码 (`.s`)。
+    Asm,
+    /// 目标平台的目标文件 (`.o`)。
+    Obj,
+    /// 链接好的可执行文件。
+    Exe,
+}
+
+impl EmitKind {
+    /// 将 `--emit` 中的一项解析为 `EmitKind`。
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tokens" => Some(EmitKind::Tokens),
+            "ast" => Some(EmitKind::Ast),
+            "llvm-ir" => Some(EmitKind::LlvmIr),
+            "asm" => Some(EmitKind::Asm),
+            "obj" => Some(EmitKind::Obj),
+            "exe" => Some(EmitKind::Exe),
+            _ => None,
+        }
+    }
+
+    /// 该产物是否需要运行代码生成阶段。
+    pub fn needs_codegen(self) -> bool {
+        matches!(self, EmitKind::LlvmIr | EmitKind::Asm | EmitKind::Obj | EmitKind::Exe)
+    }
+
+    /// 该产物默认使用的文件扩展名。可执行文件没有扩展名。
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmitKind::Tokens | EmitKind::Ast | EmitKind::Exe => "",
+            EmitKind::LlvmIr => "ll",
+            EmitKind::Asm => "s",
+            EmitKind::Obj => "o",
+        }
+    }
+}
+
+/// 一次编译调用的全部配置，由命令行参数解析而来。
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub command: Command,
+    /// 输入的 `.tp` 源文件。
+    pub input: PathBuf,
+    /// `-o` 指定的输出路径。为 `None` 时根据输入文件名推导。
+    pub output: Option<PathBuf>,
+    /// 需要产出的所有产物，按命令行中出现的顺序排列。
+    pub emit: Vec<EmitKind>,
+    /// 诊断信息是否着色。
+    pub color: ColorChoice,
+    /// 诊断信息的输出格式。
+    pub error_format: ErrorFormat,
+    /// `-W` / `-A` / `-D` 指定的 lint 级别。
+    pub lints: LintLevels,
+    /// `tipyc doc` 生成的文档格式。
+    pub doc_format: DocFormat,
+}
+
+impl Options {
+    /// 计算某种产物的输出路径。
+    ///
+    /// 如果用户给了 `-o`，就直接使用它（参数解析阶段已经保证此时只有一个
+    /// 需要写文件的产物）；否则将输入文件的扩展名替换为产物的扩展名，
+    /// e.g., `foo.tp` -> `foo.ll` / `foo.o` / `foo`。
+    pub fn output_path(&self, kind: EmitKind) -> PathBuf {
+        match &self.output {
+            Some(path) => path.clone(),
+            None => self.input.with_extension(kind.extension()),
+        }
+    }
+
+    /// `tipyc doc` 的输出路径：`-o` 指定的路径，或者把输入文件的扩展名替换为
+    /// 文档格式的扩展名，e.g., `foo.tp` -> `foo.html`。
+    pub fn doc_output_path(&self) -> PathBuf {
+        match &self.output {
+            Some(path) => path.clone(),
+            None => self.input.with_extension(self.doc_format.extension()),
+        }
+    }
+}
+
+/// 解析命令行后，驱动程序需要执行的动作。
+#[derive(Debug)]
+pub enum Action {
+    Compile(Options),
+    /// `--explain <CODE>`: 打印某个错误码的详细说明。
+    Explain(String),
+    Help,
+    Version,
+}
+
+/// 命令行参数不合法时返回的错误。
+#[derive(Debug)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 解析命令行参数（不包含程序名本身）。
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Action, UsageError> {
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        Some("build") => Some(Command::Build),
+        Some("check") => Some(Command::Check),
+        Some("emit-ir") => Some(Command::EmitIr),
+        Some("doc") => Some(Command::Doc),
+        _ => None,
+    };
+    if command.is_some() {
+        args.next(); // 消耗子命令
+    }
+    let command = command.unwrap_or(Command::Build);
+
+    let mut input: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut emit: Option<Vec<EmitKind>> = None;
+    let mut color = ColorChoice::Auto;
+    let mut error_format = ErrorFormat::Human;
+    let mut lints = LintLevels::default();
+    let mut doc_format = DocFormat::Html;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Action::Help),
+            "-V" | "--version" => return Ok(Action::Version),
+            "--explain" => {
+                let code = args.next().ok_or_else(|| UsageError("`--explain` requires an error code".to_string()))?;
+                return Ok(Action::Explain(code));
+            }
+            _ if arg.starts_with("--explain=") => {
+                return Ok(Action::Explain(arg["--explain=".len()..].to_string()));
+            }
+            "-o" => {
+                let path = args.next().ok_or_else(|| UsageError("`-o` requires a path".to_string()))?;
+                output = Some(PathBuf::from(path));
+            }
+            "--emit" => {
+                let list = args.next().ok_or_else(|| UsageError("`--emit` requires a value".to_string()))?;
+                emit = Some(parse_emit_list(&list)?);
+            }
+            _ if arg.starts_with("--emit=") => {
+                emit = Some(parse_emit_list(&arg["--emit=".len()..])?);
+            }
+            "--color" => {
+                let when = args.next().ok_or_else(|| UsageError("`--color` requires a value".to_string()))?;
+                color = parse_color(&when)?;
+            }
+            _ if arg.starts_with("--color=") => {
+                color = parse_color(&arg["--color=".len()..])?;
+            }
+            "-W" | "--warn" | "-A" | "--allow" | "-D" | "--deny" => {
+                let level = match arg.as_str() {
+                    "-W" | "--warn" => LintLevel::Warn,
+                    "-A" | "--allow" => LintLevel::Allow,
+                    _ => LintLevel::Deny,
+                };
+                let lint = args.next().ok_or_else(|| UsageError(format!("`{}` requires a lint name", arg)))?;
+                lints.set(parse_lint_name(&lint)?, level);
+            }
+            "--error-format" => {
+                let format = args.next().ok_or_else(|| UsageError("`--error-format` requires a value".to_string()))?;
+                error_format = parse_error_format(&format)?;
+            }
+            _ if arg.starts_with("--error-format=") => {
+                error_format = parse_error_format(&arg["--error-format=".len()..])?;
+            }
+            "--doc-format" => {
+                let format = args.next().ok_or_else(|| UsageError("`--doc-format` requires a value".to_string()))?;
+                doc_format = parse_doc_format(&format)?;
+            }
+            _ if arg.starts_with("--doc-format=") => {
+                doc_format = parse_doc_format(&arg["--doc-format=".len()..])?;
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(UsageError(format!("unknown option `{}`", arg)));
+            }
+            _ => {
+                if input.is_some() {
+                    return Err(UsageError(format!("unexpected extra input file `{}`", arg)));
+                }
+                input = Some(PathBuf::from(arg));
+            }
+        }
+    }
+
+    let input = input.ok_or_else(|| UsageError("no input file given".to_string()))?;
+
+    // 每个子命令都有自己的默认产物，`--emit` 会覆盖它。
+    let emit = emit.unwrap_or_else(|| match command {
+        Command::Build => vec![EmitKind::Exe],
+        Command::Check | Command::Doc => Vec::new(),
+        Command::EmitIr => vec![EmitKind::LlvmIr],
+    });
+
+    if matches!(command, Command::Check | Command::Doc) && emit.iter().any(|kind| kind.needs_codegen()) {
+        let name = if command == Command::Check { "check" } else { "doc" };
+        return Err(UsageError(format!("`{}` does not run code generation; only `--emit=tokens,ast` are allowed", name)));
+    }
+
+    // `-o` 只能对应一个输出文件，否则多个产物会互相覆盖。
+    let file_outputs = emit.iter().filter(|kind| kind.needs_codegen()).count();
+    if output.is_some() && file_outputs > 1 {
+        return Err(UsageError("`-o` cannot be used when emitting more than one file".to_string()));
+    }
+
+    Ok(Action::Compile(Options { command, input, output, emit, color, error_format, lints, doc_format }))
+}
+
+/// 解析 `--emit` 的值，e.g., `llvm-ir,obj`。
+fn parse_emit_list(list: &str) -> Result<Vec<EmitKind>, UsageError> {
+    let mut kinds = Vec::new();
+    for name in list.split(',') {
+        let kind = EmitKind::from_name(name).ok_or_else(|| {
+            UsageError(format!("unknown emit kind `{}` (expected one of: tokens, ast, llvm-ir, asm, obj, exe)", name))
+        })?;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    Ok(kinds)
+}
+
+/// 解析 `--color` 的值。
+fn parse_color(when: &str) -> Result<ColorChoice, UsageError> {
+    ColorChoice::from_name(when)
+        .ok_or_else(|| UsageError(format!("unknown color choice `{}` (expected one of: auto, always, never)", when)))
+}
+
+/// 解析 `--error-format` 的值。
+fn parse_error_format(format: &str) -> Result<ErrorFormat, UsageError> {
+    ErrorFormat::from_name(format)
+        .ok_or_else(|| UsageError(format!("unknown error format `{}` (expected one of: human, json)", format)))
+}
+
+/// 解析 `--doc-format` 的值。
+fn parse_doc_format(format: &str) -> Result<DocFormat, UsageError> {
+    DocFormat::from_name(format)
+        .ok_or_else(|| UsageError(format!("unknown doc format `{}` (expected one of: html, markdown)", format)))
+}
+
+/// 校验 `-W` / `-A` / `-D` 的 lint 名称。与 rustc 一样，`-` 与 `_` 等价。
+fn parse_lint_name(name: &str) -> Result<String, UsageError> {
+    let name = name.replace('-', "_");
+    if name == "warnings" || LINT_NAMES.contains(&name.as_str()) {
+        Ok(name)
+    } else {
+        Err(UsageError(format!(
+            "unknown lint `{}` (expected `warnings` or one of: {})",
+            name,
+            LINT_NAMES.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Action, UsageError> {
+        parse_args(args.split_whitespace().map(str::to_string))
+    }
+
+    // 解析一条应当成功的编译命令
+    fn options(args: &str) -> Options {
+        match parse(args) {
+            Ok(Action::Compile(options)) => options,
+            other => panic!("expected a compile action for `{}`, got {:?}", args, other),
+        }
+    }
+
+    // 解析一条应当失败的命令，返回错误信息
+    fn usage_error(args: &str) -> String {
+        match parse(args) {
+            Err(UsageError(message)) => message,
+            other => panic!("expected a usage error for `{}`, got {:?}", args, other),
+        }
+    }
+
+    #[test]
+    fn build_with_output() {
+        let options = options("build foo.tp -o foo");
+        assert_eq!(options.command, Command::Build);
+        assert_eq!(options.input, PathBuf::from("foo.tp"));
+        assert_eq!(options.output, Some(PathBuf::from("foo")));
+        assert_eq!(options.emit, vec![EmitKind::Exe]);
+        assert_eq!(options.output_path(EmitKind::Exe), PathBuf::from("foo"));
+    }
+
+    #[test]
+    fn command_defaults_to_build() {
+        let options = options("foo.tp");
+        assert_eq!(options.command, Command::Build);
+        assert_eq!(options.emit, vec![EmitKind::Exe]);
+        assert_eq!(options.output_path(EmitKind::Exe), PathBuf::from("foo"));
+    }
+
+    #[test]
+    fn default_emit_for_each_command() {
+        assert_eq!(options("check foo.tp").emit, vec![]);
+        assert_eq!(options("emit-ir foo.tp").emit, vec![EmitKind::LlvmIr]);
+        assert_eq!(options("emit-ir foo.tp").output_path(EmitKind::LlvmIr), PathBuf::from("foo.ll"));
+    }
+
+    #[test]
+    fn emit_each_kind() {
+        let kinds = [
+            ("tokens", EmitKind::Tokens),
+            ("ast", EmitKind::Ast),
+            ("llvm-ir", EmitKind::LlvmIr),
+            ("asm", EmitKind::Asm),
+            ("obj", EmitKind::Obj),
+            ("exe", EmitKind::Exe),
+        ];
+        for (name, kind) in kinds {
+            assert_eq!(options(&format!("build foo.tp --emit={}", name)).emit, vec![kind]);
+            assert_eq!(options(&format!("build foo.tp --emit {}", name)).emit, vec![kind]);
+        }
+    }
+
+    #[test]
+    fn emit_list_keeps_order_and_drops_duplicates() {
+        assert_eq!(
+            options("build foo.tp --emit=obj,llvm-ir,obj").emit,
+            vec![EmitKind::Obj, EmitKind::LlvmIr],
+        );
+    }
+
+    #[test]
+    fn help_and_version() {
+        assert!(matches!(parse("--help"), Ok(Action::Help)));
+        assert!(matches!(parse("build foo.tp -h"), Ok(Action::Help)));
+        assert!(matches!(parse("-V"), Ok(Action::Version)));
+    }
+
+    #[test]
+    fn unknown_command_is_taken_as_the_input_file() {
+        assert_eq!(options("run").input, PathBuf::from("run"));
+        assert_eq!(usage_error("run foo.tp"), "unexpected extra input file `foo.tp`");
+    }
+
+    #[test]
+    fn unknown_flag_or_emit_kind() {
+        assert_eq!(usage_error("build foo.tp --release"), "unknown option `--release`");
+        assert_eq!(
+            usage_error("build foo.tp --emit=wasm"),
+            "unknown emit kind `wasm` (expected one of: tokens, ast, llvm-ir, asm, obj, exe)",
+        );
+    }
+
+    #[test]
+    fn missing_input_or_values() {
+        assert_eq!(usage_error("build"), "no input file given");
+        assert_eq!(usage_error("build --emit=obj"), "no input file given");
+        assert_eq!(usage_error("build foo.tp -o"), "`-o` requires a path");
+        assert_eq!(usage_error("build foo.tp --emit"), "`--emit` requires a value");
+    }
+
+    #[test]
+    fn check_rejects_codegen_outputs() {
+        assert_eq!(options("check foo.tp --emit=tokens,ast").emit, vec![EmitKind::Tokens, EmitKind::Ast]);
+        assert_eq!(
+            usage_error("check foo.tp --emit=obj"),
+            "`check` does not run code generation; only `--emit=tokens,ast` are allowed",
+        );
+    }
+
+    #[test]
+    fn single_output_path_for_several_files() {
+        assert_eq!(
+            usage_error("build foo.tp --emit=llvm-ir,obj -o out"),
+            "`-o` cannot be used when emitting more than one file",
+        );
+        // tokens 和 ast 打印到标准输出，不占用 `-o`
+        assert_eq!(options("build foo.tp --emit=ast,obj -o out").output, Some(PathBuf::from("out")));
+    }
+
+    #[test]
+    fn lint_flags_are_recorded_in_order() {
+        let lints = options("build foo.tp -A unused-variables -D warnings").lints;
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Allow, "unused_variables"));
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Deny, "warnings"));
+        let lints = options("build foo.tp --deny unreachable_code --warn unreachable_code").lints;
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Warn, "unreachable_code"));
+    }
+
+    #[test]
+    fn unknown_or_missing_lint_name() {
+        assert_eq!(
+            usage_error("build foo.tp -D unused"),
+            "unknown lint `unused` (expected `warnings` or one of: unused_variables, unreachable_code)",
+        );
+        assert_eq!(usage_error("build foo.tp -A"), "`-A` requires a lint name");
+    }
+}
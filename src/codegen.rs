@@ -2,21 +2,24 @@
 
 use std::collections::HashMap;
 use std::path::Path;
+use std::process::Command;
 
 // --- LLVM 后端库 (Inkwell) 引入 ---
 // 这里引入了与 LLVM IR 生成直接相关的核心类型。
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
-use inkwell::types::{BasicType, BasicTypeEnum};
-use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue};
-use inkwell::{AddressSpace, FloatPredicate, IntPredicate};
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine};
+use inkwell::module::Linkage;
+use inkwell::types::{BasicType, BasicTypeEnum, FunctionType, IntType, StructType};
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, IntValue, PointerValue, StructValue};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate, OptimizationLevel};
 
 // --- Tipy 编译器内部模块引入 ---
 
 // 引入抽象语法树 (AST)。代码生成器将遍历这些 AST 节点来生成代码。
 use crate::ast::{
-    BlockStatement, Expression, FunctionDeclaration, IfExpression, LoopExpression, Program, Statement,
+    BlockStatement, CastExpression, Expression, FunctionDeclaration, IfExpression, LiteralExpression, LoopExpression, NodeId, Program, Statement,
     TopLevelStatement, WhileStatement,VarDeclaration, BreakStatement, ContinueStatement,
 };
 
@@ -25,7 +28,7 @@ use crate::ast::Operator;
 
 // 引入我们为后端错误处理定义的新类型。
 // CRITICAL: 移除了旧的 `error::CompileError`，换用统一的诊断系统。
-use crate::diagnostics::{CodegenError, CompilerError};
+use crate::diagnostics::{CodegenError, CompilerError, Span};
 
 // 引入我们内部的类型系统，并使用 `as` 关键字重命名，以避免与 LLVM 的类型定义冲突。
 // e.g., TipyType::I32 (我们的) vs inkwell::types::IntType (LLVM 的)
@@ -55,6 +58,10 @@ pub struct CodeGen<'ctx> {
     /// 中创建和插入 LLVM 指令（如加法、跳转、函数调用等）。
     builder: Builder<'ctx>,
 
+    /// 本机的 `TargetMachine`。创建模块时就用它设置目标三元组和数据布局，
+    /// 这样无论先输出哪种产物，`.ll`、`.s` 和 `.o` 描述的都是同一个平台。
+    target_machine: TargetMachine,
+
     /// 用于代码生成的“符号表”。
     ///
     /// 与语义分析的 `SymbolTable` 不同，这里存储的不是类型信息，
@@ -68,6 +75,11 @@ pub struct CodeGen<'ctx> {
     /// 它属于哪个函数。
     current_function: Option<FunctionValue<'ctx>>,
 
+    /// 当前函数在 Tipy 中声明的返回类型。
+    ///
+    /// 对于 `main`，它与 LLVM 函数的返回类型 (`i32`) 不一定相同，见 `build_return_value`。
+    current_return_type: Option<TipyType>,
+
     /// 循环上下文栈，用于正确生成 `break` 和 `continue` 的跳转指令。
     ///
     /// 每当进入一个循环 (`loop` 或 `while`)，我们会将该循环的
@@ -83,6 +95,13 @@ pub struct CodeGen<'ctx> {
         inkwell::basic_block::BasicBlock<'ctx>, // exit_block (循环结束后的块)
         Option<PointerValue<'ctx>>,             // result_alloca (存放 loop 返回值的地方)
     )>,
+
+    /// 语义分析器推断出的表达式类型（见 `SemanticAnalyzer::expression_types`），
+    /// 在 `compile` 开始时从分析器复制过来。
+    expression_types: HashMap<NodeId, TipyType>,
+
+    /// 每个变量声明的类型（见 `SemanticAnalyzer::variable_types`）。
+    variable_types: HashMap<NodeId, TipyType>,
 }
 
 impl<'ctx> CodeGen<'ctx> {
@@ -95,19 +114,28 @@ impl<'ctx> CodeGen<'ctx> {
     ///
     /// # Returns
     /// 一个全新的 `CodeGen` 实例，它内部已经创建好了 `Module` 和 `Builder`，
-    /// 并初始化了一个包含全局作用域的变量表。
-    pub fn new(context: &'ctx Context, module_name: &str) -> Self {
+    /// 并初始化了一个包含全局作用域的变量表。模块的目标三元组和数据布局
+    /// 已经设置为本机平台。如果无法为本机创建 `TargetMachine`，返回 `CodegenError::Target`。
+    pub fn new(context: &'ctx Context, module_name: &str) -> Result<Self, CodegenError> {
         let module = context.create_module(module_name);
         let builder = context.create_builder();
-        
-        CodeGen {
+
+        let target_machine = Self::create_native_target_machine()?;
+        module.set_triple(&target_machine.get_triple());
+        module.set_data_layout(&target_machine.get_target_data().get_data_layout());
+
+        Ok(CodeGen {
             context,
             module,
             builder,
+            target_machine,
             variables: vec![HashMap::new()], // 初始化全局作用域
             current_function: None,
+            current_return_type: None,
             loop_context_stack: Vec::new(),
-        }
+            expression_types: HashMap::new(),
+            variable_types: HashMap::new(),
+        })
     }
 
     /// 将代码生成器的主入口点，负责将整个程序的 AST 编译成 LLVM IR。
@@ -133,6 +161,9 @@ impl<'ctx> CodeGen<'ctx> {
         program: &Program,
         analyzer: &crate::analyzer::SemanticAnalyzer,
     ) -> Result<(), CodegenError> {
+        self.expression_types = analyzer.expression_types.clone();
+        self.variable_types = analyzer.variable_types.clone();
+
         // --- 第一遍：声明所有函数 ---
         for toplevel_stmt in &program.body {
             if let TopLevelStatement::Function(func_decl) = toplevel_stmt {
@@ -141,26 +172,25 @@ impl<'ctx> CodeGen<'ctx> {
             }
         }
         
-        // (可选) 在这里声明所有外部函数，如 C 的 printf
-        // self.declare_externs();
+        // 运行时库中的外部函数（如 C 的 `printf`）在第一次用到时由 `runtime_function` 声明
 
         // --- 第二遍：编译所有函数体 ---
         for toplevel_stmt in &program.body {
             if let TopLevelStatement::Function(func_decl) = toplevel_stmt {
                 // compile_function_body 现在应返回 Result<(), CodegenError>
-                self.compile_function_body(func_decl)?;
+                self.compile_function_body(func_decl, &analyzer.symbol_table)?;
             }
         }
         
         Ok(())
     }
 
-    /// **[调试辅助]** 将当前生成的 LLVM IR 打印到标准错误输出。
+    /// 以字符串形式返回当前生成的 LLVM IR。
     ///
-    /// 这是一个非常有用的调试工具，可以让你在开发过程中随时查看
-    /// 生成的中间代码是否符合预期。
-    pub fn print_ir_to_stderr(&self) {
-        self.module.print_to_stderr();
+    /// 用于 `tipyc emit-ir -o -`，把 IR 直接打印到标准输出，
+    /// 方便在开发过程中随时查看生成的中间代码是否符合预期。
+    pub fn ir_to_string(&self) -> String {
+        self.module.print_to_string().to_string()
     }
 
     /// 将最终生成的 LLVM IR 保存到指定的文件路径。
@@ -174,10 +204,54 @@ impl<'ctx> CodeGen<'ctx> {
     pub fn save_ir_to_file(&self, path: &Path) -> Result<(), CodegenError> {
         self.module.print_to_file(path).map_err(|e| {
             // CHANGED: 将 inkwell 返回的字符串错误包装成我们自己的结构化错误。
-            CodegenError::Message(format!("Error writing IR to file: {}", e.to_string()))
+            CodegenError::Message(format!("Error writing IR to file: {}", e))
         })
     }
     
+    /// 将模块编译为本机目标文件 (`.o`) 并写入 `path`。
+    pub fn write_object_file(&self, path: &Path) -> Result<(), CodegenError> {
+        self.write_native_file(path, FileType::Object)
+    }
+
+    /// 将模块编译为本机汇编代码 (`.s`) 并写入 `path`。
+    pub fn write_assembly_file(&self, path: &Path) -> Result<(), CodegenError> {
+        self.write_native_file(path, FileType::Assembly)
+    }
+
+    /// 使用本机的 `TargetMachine` 把模块降级为目标文件或汇编。
+    ///
+    /// 在写文件之前会先校验模块，避免把非法 IR 交给 LLVM 后端导致崩溃。
+    fn write_native_file(&self, path: &Path, file_type: FileType) -> Result<(), CodegenError> {
+        self.module.verify().map_err(|e| {
+            CodegenError::Message(format!("Generated invalid LLVM IR: {}", e))
+        })?;
+
+        self.target_machine.write_to_file(&self.module, file_type, path).map_err(|e| {
+            CodegenError::Target(format!("Error writing '{}': {}", path.display(), e))
+        })
+    }
+
+    /// 初始化本机目标并创建对应的 `TargetMachine`。
+    fn create_native_target_machine() -> Result<TargetMachine, CodegenError> {
+        Target::initialize_native(&InitializationConfig::default()).map_err(CodegenError::Target)?;
+
+        let triple = TargetMachine::get_default_triple();
+        let target = Target::from_triple(&triple).map_err(|e| CodegenError::Target(e.to_string()))?;
+        target
+            .create_target_machine(
+                &triple,
+                &TargetMachine::get_host_cpu_name().to_string(),
+                &TargetMachine::get_host_cpu_features().to_string(),
+                OptimizationLevel::Default,
+                // 与 README 中 `llc -relocation-model=pic` 保持一致，便于链接成 PIE
+                RelocMode::PIC,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| {
+                CodegenError::Target(format!("Cannot create a target machine for '{}'.", triple))
+            })
+    }
+
     // --- 作用域与变量管理 (Scope & Variable Management) ---
 
     /// 进入一个新的作用域。
@@ -248,26 +322,103 @@ impl<'ctx> CodeGen<'ctx> {
         Ok(builder.build_alloca(llvm_type, name)?)
     }
 
+    /// 把整数值转换为另一个宽度的整数类型：变窄时截断，变宽时根据 `signed`
+    /// 做符号扩展 (`sext`) 或零扩展 (`zext`)，宽度相同时原样返回。
+    fn build_int_resize(
+        &self,
+        value: IntValue<'ctx>,
+        target: IntType<'ctx>,
+        signed: bool,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        let from = value.get_type().get_bit_width();
+        let to = target.get_bit_width();
+        let resized = if from > to {
+            self.builder.build_int_truncate(value, target, "trunc")?
+        } else if from < to && signed {
+            self.builder.build_int_s_extend(value, target, "sext")?
+        } else if from < to {
+            self.builder.build_int_z_extend(value, target, "zext")?
+        } else {
+            value
+        };
+        Ok(resized)
+    }
+
+    /// 语义分析器推断出的表达式类型是否按无符号数处理。
+    ///
+    /// LLVM 的整数类型不带符号信息，除法和比较需要据此选择 `udiv` / `ult` 等指令。
+    fn is_unsigned(&self, expr: &Expression) -> bool {
+        // `bool` 是 `i1`，按有符号比较时 `true` 是 -1，`true > false` 就会得到 false；
+        // 码点总是非负的，所以 `char` 的比较也按无符号进行
+        self.expression_types
+            .get(&expr.id())
+            .is_some_and(|t| t.is_unsigned_integer() || matches!(t, TipyType::Bool | TipyType::Char))
+    }
+
+    /// builder 当前所在的基本块是否已经有了终结指令（`ret`、`br` 等）。
+    ///
+    /// 一个基本块只能有一条终结指令，并且必须位于末尾。在 `ret` 或 `break`
+    /// 之后继续生成代码之前，需要先用它检查一下。
+    fn current_block_terminated(&self) -> bool {
+        self.builder.get_insert_block().and_then(|bb| bb.get_terminator()).is_some()
+    }
+
     // --- 类型转换 (Type Conversion) ---
 
     /// 将 Tipy 的内部类型 (`TipyType`) 转换为 `inkwell` 的基础 LLVM 类型 (`BasicTypeEnum`)。
     ///
     /// 这是连接我们的类型系统和 LLVM 类型系统的核心桥梁。
     /// 注意：此函数不处理 `Void` 或 `Function` 类型，因为它们不是“基础类型”。
-    fn to_llvm_basic_type(&self, tipy_type: &TipyType) -> BasicTypeEnum<'ctx> {
-        match tipy_type {
-            TipyType::I8 => self.context.i8_type().as_basic_type_enum(),
-            TipyType::I16 => self.context.i16_type().as_basic_type_enum(),
-            TipyType::I32 => self.context.i32_type().as_basic_type_enum(),
-            TipyType::I64 => self.context.i64_type().as_basic_type_enum(),
-            // ... 其他整数类型 ...
+    /// 语义分析保证变量、参数等位置不会出现这些类型，遇到时返回 `CodegenError`。
+    fn to_llvm_basic_type(&self, tipy_type: &TipyType) -> Result<BasicTypeEnum<'ctx>, CodegenError> {
+        let llvm_type = match tipy_type {
+            // 所有整数类型：LLVM 的整数类型不区分有无符号，符号性体现在所用的指令上
+            // (e.g., `sdiv` / `udiv`，`slt` / `ult`)
+            t if t.is_integer() => {
+                let bits = t.integer_bits().expect("integer types have a bit width");
+                self.context.custom_width_int_type(bits).as_basic_type_enum()
+            }
             TipyType::F32 => self.context.f32_type().as_basic_type_enum(),
             TipyType::F64 => self.context.f64_type().as_basic_type_enum(),
             TipyType::Bool => self.context.bool_type().as_basic_type_enum(),
-            // 对于指针类型，我们统一使用泛型指针
-            TipyType::Pointer { .. } => self.context.i8_type().ptr_type(AddressSpace::default()).as_basic_type_enum(),
+            // `char` 保存的是 Unicode 码点，用 32 位整数表示
+            TipyType::Char => self.context.i32_type().as_basic_type_enum(),
+            TipyType::Str => self.str_type().as_basic_type_enum(),
+            // 对于指针类型，我们统一使用不透明指针 `ptr`
+            TipyType::Pointer { .. } => self.context.ptr_type(AddressSpace::default()).as_basic_type_enum(),
             // 其他类型...
-            _ => unimplemented!("LLVM type conversion for {:?} is not implemented.", tipy_type),
+            _ => {
+                return Err(CodegenError::Message(format!(
+                    "Type '{}' has no LLVM representation as a value.",
+                    tipy_type
+                )));
+            }
+        };
+        Ok(llvm_type)
+    }
+
+    /// `str` 在 LLVM 中的表示：`{ ptr, i64 }`，即指向 UTF-8 字节的指针和字节长度。
+    ///
+    /// 字符串按值传递，复制的只是指针和长度，所以可以直接作为函数参数和返回值。
+    /// 目前所有字符串都来自字面量，指针指向只读的全局常量，不需要释放；
+    /// 在堆上分配的字符串会在支持拼接等操作时引入。
+    fn str_type(&self) -> StructType<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        self.context.struct_type(&[ptr_type.into(), self.context.i64_type().into()], false)
+    }
+
+    /// 获取一个由 C 运行时库提供的函数（如 `memcmp`），第一次使用时在模块中声明它。
+    ///
+    /// 语义分析不允许程序定义这些名字的函数（见 `prelude::RUNTIME_FUNCTIONS`）。
+    /// 如果模块中已有的同名函数类型不同，说明遗漏了这项检查，此时报错而不是生成错误的调用。
+    fn runtime_function(&self, name: &str, fn_type: FunctionType<'ctx>) -> Result<FunctionValue<'ctx>, CodegenError> {
+        match self.module.get_function(name) {
+            Some(function) if function.get_type() == fn_type => Ok(function),
+            Some(_) => Err(CodegenError::Message(format!(
+                "`{}` is already defined with a different signature than the C runtime function.",
+                name
+            ))),
+            None => Ok(self.module.add_function(name, fn_type, Some(Linkage::External))),
         }
     }
 
@@ -294,17 +445,20 @@ impl<'ctx> CodeGen<'ctx> {
             let param_types: Vec<BasicTypeEnum<'ctx>> = params
                 .iter()
                 .map(|p_type| self.to_llvm_basic_type(p_type))
-                .collect();
+                .collect::<Result<_, _>>()?;
             
             // inkwell 需要一个 `BasicMetadataTypeEnum` 的Vec数组
             let param_types_as_metadata: Vec<inkwell::types::BasicMetadataTypeEnum<'ctx>> =
                 param_types.iter().map(|&t| t.into()).collect();
 
-            // 根据 Tipy 的返回类型，创建 LLVM 的函数类型
-            let fn_type = if **ret == TipyType::Void {
+            // 根据 Tipy 的返回类型，创建 LLVM 的函数类型。
+            // `main` 是程序的入口，C 运行时要求它返回 `int`，其值就是进程的退出码。
+            let fn_type = if func_decl.name == "main" {
+                self.context.i32_type().fn_type(&param_types_as_metadata, false)
+            } else if **ret == TipyType::Void {
                 self.context.void_type().fn_type(&param_types_as_metadata, false)
             } else {
-                self.to_llvm_basic_type(ret).fn_type(&param_types_as_metadata, false)
+                self.to_llvm_basic_type(ret)?.fn_type(&param_types_as_metadata, false)
             };
             
             // 在模块中添加函数声明
@@ -328,16 +482,22 @@ impl<'ctx> CodeGen<'ctx> {
     /// 2. 为所有函数参数在栈上分配空间 (`alloca`)，并将传入的参数值存入其中。
     /// 3. 将参数的 `PointerValue` 注册到代码生成器的变量表中。
     /// 4. 递归地调用 `compile_block_statement` 来编译函数体内的所有语句。
-    /// 5. 检查函数是否被正确地“终结”（例如，有 `ret` 指令），如果没有，则为其添加一个隐式的返回。
+    /// 5. 检查函数是否被正确地“终结”（例如，有 `ret` 指令），如果没有，则为其添加一个隐式的返回：
+    ///    函数体最后一个表达式的值就是返回值。
     fn compile_function_body(
         &mut self,
-        func_decl: &FunctionDeclaration
+        func_decl: &FunctionDeclaration,
+        symbol_table: &crate::scope::SymbolTable,
     ) -> Result<(), CodegenError> {
         // CHANGED: 移除 unwrap()，使用安全的错误处理
         let function = self.module.get_function(&func_decl.name).ok_or_else(||
             CodegenError::SymbolNotFound(func_decl.name.clone())
         )?;
         self.current_function = Some(function);
+        self.current_return_type = match symbol_table.lookup(&func_decl.name).map(|s| &s.symbol_type) {
+            Some(TipyType::Function { ret, .. }) => Some((**ret).clone()),
+            _ => return Err(CodegenError::SymbolNotFound(func_decl.name.clone())),
+        };
         
         // 创建函数入口块并定位 builder
         let entry_block = self.context.append_basic_block(function, "entry");
@@ -363,33 +523,60 @@ impl<'ctx> CodeGen<'ctx> {
         }
 
         // 编译函数体
-        // 我们将在下一步实现 compile_block_statement
-        self.compile_block_statement(&func_decl.body)?;
-
-        // 检查函数是否在所有路径上都有返回
-        if function.get_last_basic_block().and_then(|bb| bb.get_terminator()).is_none() {
-            // 如果函数是 void 返回，且最后没有 ret，我们隐式添加一个
-            if function.get_type().get_return_type().is_none() {
-                self.builder.build_return(None)?;
-            } else if func_decl.name == "main" {
-                // 特殊处理 main 函数，使其默认返回 0
-                let i32_type = self.context.i32_type();
-                self.builder.build_return(Some(&i32_type.const_int(0, false)))?;
+        let body_value = self.compile_block_statement(&func_decl.body)?;
+
+        // 函数体的最后一个块还没有终结指令时，为它补上返回
+        if !self.current_block_terminated() {
+            if self.current_return_type == Some(TipyType::Void) {
+                // 如果函数是 void 返回，且最后没有 ret，我们隐式添加一个
+                self.build_return_value(None)?;
+            } else if let Some(value) = body_value {
+                // 隐式返回：函数体以表达式结尾，它的值就是返回值
+                self.build_return_value(Some(value))?;
             } else {
-                // 如果一个非 void 函数没有在所有代码路径上返回值，这是一个错误
-                return Err(CodegenError::Message(format!(
-                    "Function '{}' must return a value on all code paths.",
-                    func_decl.name
-                )));
+                // 语义分析已经保证了有返回值的函数在所有路径上都会返回 (见 `MissingReturn`)，
+                // 所以能走到这里的块一定不可达，e.g., 无限 `loop` 之后的块。
+                self.builder.build_unreachable()?;
             }
         }
         
         // 离开函数作用域
         self.leave_scope();
         self.current_function = None; // 清理状态
+        self.current_return_type = None;
 
         Ok(())
     }
+
+    /// 为当前函数生成返回指令。
+    ///
+    /// `main` 是一个例外：无论声明的返回类型是什么，它在 LLVM 中总是返回 `i32`。
+    /// 整数返回值会被截断或扩展为 `i32`（有符号类型做符号扩展，无符号类型做零扩展），
+    /// 没有返回值的 `main` 返回 0。
+    fn build_return_value(&self, value: Option<BasicValueEnum<'ctx>>) -> Result<(), CodegenError> {
+        let function = self.current_function.ok_or_else(|| {
+            CodegenError::Message("Cannot build return: not in a function context.".to_string())
+        })?;
+
+        let value = match (value, function.get_type().get_return_type()) {
+            (_, None) => None,
+            (Some(value), Some(ret_type)) if value.get_type() == ret_type => Some(value),
+            // 以下两种情况只会出现在 `main` 中
+            (Some(value), Some(ret_type)) => {
+                if !value.is_int_value() {
+                    return Err(CodegenError::Message(
+                        "`main` can only return an integer, a `bool` or nothing.".to_string(),
+                    ));
+                }
+                let signed = self.current_return_type.as_ref().is_some_and(|t| t.is_signed_integer());
+                Some(self.build_int_resize(value.into_int_value(), ret_type.into_int_type(), signed)?.into())
+            }
+            (None, Some(ret_type)) => Some(ret_type.into_int_type().const_zero().into()),
+        };
+
+        self.builder.build_return(value.as_ref().map(|v| v as &dyn inkwell::values::BasicValue))?;
+        Ok(())
+    }
     
     // --- 语句与块编译 (Statement & Block Compilation) ---
 
@@ -406,15 +593,12 @@ impl<'ctx> CodeGen<'ctx> {
                     Some(expr) => Some(self.compile_expression(expr)?), // 编译表达式
                     None => None, // void 返回
                 };
-                
-                // .as_ref().map(...) 是处理 Option<T> 到 Option<&T> 的标准方法
-                self.builder.build_return(ret_val.as_ref().map(|v| v as &dyn inkwell::values::BasicValue))?;
-                Ok(())
+                self.build_return_value(ret_val)
             }
             Statement::Expression(expr) => {
                 // 表达式作为语句使用时，我们只关心它的编译过程（及其副作用，如函数调用），
                 // 而不关心其返回值。
-                self.compile_expression(expr).map(|_| ())
+                self.compile_expression_statement(expr).map(|_| ())
             }
             Statement::Block(block_stmt) => {
                 // 代码块作为语句使用时，我们同样不关心其返回值。
@@ -448,12 +632,18 @@ impl<'ctx> CodeGen<'ctx> {
         if let Some((last_stmt, other_stmts)) = block.statements.split_last() {
             for stmt in other_stmts {
                 self.compile_statement(stmt)?;
+                // `ret` / `break` / `continue` 之后的语句不可达，
+                // 不能再向已经终结的基本块中追加指令
+                if self.current_block_terminated() {
+                    self.leave_scope();
+                    return Ok(None);
+                }
             }
             
             // 特别处理最后一个语句
             if let Statement::Expression(expr) = last_stmt {
-                // 如果是表达式，它的值就是块的返回值
-                last_val = Some(self.compile_expression(expr)?);
+                // 如果是表达式，它的值就是块的返回值（不产生值的 `loop` / `if` 除外）
+                last_val = self.compile_expression_statement(expr)?;
             } else {
                 // 如果是其他语句，正常编译，块没有返回值
                 self.compile_statement(last_stmt)?;
@@ -469,10 +659,11 @@ impl<'ctx> CodeGen<'ctx> {
 
     /// 编译一个变量声明语句 `name: [~]type [= value];`
     fn compile_var_declaration(&mut self, var_decl: &VarDeclaration) -> Result<(), CodegenError> {
-        // 从符号表或分析器获取变量的 Tipy 类型
-        // (这里我们假设可以通过某种方式获取，或直接从 AST 解析)
-        let var_tipy_type = TipyType::I32; // 简化：应从 analyzer 获取
-        let var_llvm_type = self.to_llvm_basic_type(&var_tipy_type);
+        // 变量的 Tipy 类型由语义分析器记录
+        let var_tipy_type = self.variable_types.get(&var_decl.id).ok_or_else(|| {
+            CodegenError::SymbolNotFound(var_decl.name.clone())
+        })?;
+        let var_llvm_type = self.to_llvm_basic_type(var_tipy_type)?;
 
         // 在当前函数的入口块为变量分配栈空间
         let alloca = self.create_entry_block_alloca(var_llvm_type, &var_decl.name)?;
@@ -519,8 +710,10 @@ impl<'ctx> CodeGen<'ctx> {
         // 3. 编译循环体块
         self.builder.position_at_end(loop_block);
         self.compile_block_statement(&while_stmt.body)?;
-        // 循环体结束后，无条件跳回条件检查块
-        self.builder.build_unconditional_branch(cond_block)?;
+        // 循环体结束后，无条件跳回条件检查块（除非循环体以 `ret` / `break` 等结尾）
+        if !self.current_block_terminated() {
+            self.builder.build_unconditional_branch(cond_block)?;
+        }
 
         // 4. 将 builder 定位到循环结束后的块，以继续生成后续代码
         self.builder.position_at_end(after_block);
@@ -567,6 +760,24 @@ impl<'ctx> CodeGen<'ctx> {
         Ok(())
     }
 
+    /// 编译一个处于语句位置的表达式。
+    ///
+    /// 与 `compile_expression` 不同，这里允许表达式没有值：
+    /// 没有 `else` 的 `if`、不带值 `break` 的 `loop`、以语句结尾的代码块和
+    /// 没有返回值的函数调用都可以作为语句使用。
+    fn compile_expression_statement(
+        &mut self,
+        expr: &Expression,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodegenError> {
+        match expr {
+            Expression::If(if_expr) => self.compile_if_expression(if_expr),
+            Expression::Loop(loop_expr) => self.compile_loop_expression(loop_expr),
+            Expression::Block(block_stmt) => self.compile_block_statement(block_stmt),
+            Expression::Call(call_expr) => self.compile_call_expression(call_expr),
+            _ => self.compile_expression(expr).map(Some),
+        }
+    }
+
     // --- 表达式编译 (Expression Compilation) ---
 
     /// 编译一个表达式 AST 节点，并返回其对应的 LLVM 值 (`BasicValueEnum`)。
@@ -579,31 +790,66 @@ impl<'ctx> CodeGen<'ctx> {
     ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         match expr {
             Expression::Literal(lit) => self.compile_literal(lit),
-            Expression::Identifier(name) => self.compile_identifier(name),
+            Expression::Identifier(ident) => self.compile_identifier(&ident.name),
             Expression::Prefix(prefix_expr) => self.compile_prefix_expression(prefix_expr),
             Expression::Infix(infix_expr) => self.compile_infix_expression(infix_expr),
             Expression::Assignment(assign_expr) => self.compile_assignment_expression(assign_expr),
-            Expression::Call(call_expr) => self.compile_call_expression(call_expr),
-            Expression::If(if_expr) => self.compile_if_expression(if_expr),
-            Expression::Loop(loop_expr) => self.compile_loop_expression(loop_expr),
+            Expression::Call(call_expr) => self
+                .compile_call_expression(call_expr)?
+                .ok_or_else(|| CodegenError::Message("Cannot use a void function as an expression.".to_string())),
+            Expression::If(if_expr) => self
+                .compile_if_expression(if_expr)?
+                .ok_or_else(|| CodegenError::Message(
+                    "An `if` used as an expression must have an `else` branch and produce a value.".to_string()
+                )),
+            Expression::Loop(loop_expr) => self
+                .compile_loop_expression(loop_expr)?
+                .ok_or_else(|| CodegenError::Message(
+                    "A `loop` used as an expression must `break` with a value.".to_string()
+                )),
             Expression::Block(block_stmt) => self
                 .compile_block_statement(block_stmt)?
                 .ok_or_else(|| CodegenError::Message(
                     "A block used as an expression must return a value.".to_string()
                 )),
+            Expression::Cast(cast_expr) => self.compile_cast_expression(cast_expr),
         }
     }
 
     // --- 表达式编译辅助函数 (Expression Compilation Helpers) ---
 
-    /// 编译字面量
-    fn compile_literal(&self, lit: &Literal) -> Result<BasicValueEnum<'ctx>, CodegenError> {
-        match lit {
-            Literal::Integer(val) => Ok(self.context.i64_type().const_int(*val as u64, true).into()),
-            Literal::Float(val) => Ok(self.context.f64_type().const_float(*val).into()),
+    /// 编译字面量。数字字面量的具体类型（e.g., `u8` 还是 `i64`）由语义分析根据上下文推断得出。
+    fn compile_literal(&self, lit: &LiteralExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        let literal_type = self.expression_types.get(&lit.id);
+        match &lit.value {
+            // 字面量的值总是非负的（负号是单独的前缀运算），所以不需要符号扩展
+            Literal::Integer(val, _) => {
+                let int_type = match literal_type {
+                    Some(t) if t.is_integer() => self.to_llvm_basic_type(t)?.into_int_type(),
+                    _ => self.context.i64_type(),
+                };
+                Ok(int_type.const_int(*val, false).into())
+            }
+            Literal::Float(val, _) => match literal_type {
+                Some(TipyType::F32) => Ok(self.context.f32_type().const_float(*val).into()),
+                _ => Ok(self.context.f64_type().const_float(*val).into()),
+            },
             Literal::Boolean(val) => Ok(self.context.bool_type().const_int(*val as u64, false).into()),
-            // 其他字面量...
-            _ => Err(CodegenError::Message("This literal type is not yet supported in codegen.".to_string())),
+            // 字符串的内容保存在一个全局常量中（末尾额外带一个 `\0`，方便传给 C 函数），
+            // 字面量的值是指向它的 `{ ptr, len }`，长度不包含 `\0`
+            // 内容可能含有 `\0` 转义，所以不能用 `build_global_string_ptr`（它按 C 字符串截断）
+            Literal::String(s) => {
+                let bytes = self.context.const_string(s.as_bytes(), true);
+                let global = self.module.add_global(bytes.get_type(), None, "str");
+                global.set_initializer(&bytes);
+                global.set_constant(true);
+                global.set_linkage(Linkage::Private);
+                global.set_unnamed_addr(true);
+                let len = self.context.i64_type().const_int(s.len() as u64, false);
+                Ok(self.str_type().const_named_struct(&[global.as_pointer_value().into(), len.into()]).into())
+            }
+            // `char` 是一个 Unicode 标量值，用 i32 保存它的码点
+            Literal::Char(c) => Ok(self.context.i32_type().const_int(*c as u64, false).into()),
         }
     }
 
@@ -627,41 +873,76 @@ impl<'ctx> CodeGen<'ctx> {
                     Err(CodegenError::Message("Unary minus can only be applied to numbers.".to_string()))
                 }
             }
-            crate::ast::PrefixOperator::Not => {
-                let bool_true = self.context.bool_type().const_int(1, false);
-                Ok(self.builder.build_xor(value.into_int_value(), bool_true, "not")?.into())
-            }
+            // `build_not` 与全 1 做异或：对 i1 是逻辑非，对其他整数是按位取反
+            crate::ast::PrefixOperator::Not => Ok(self.builder.build_not(value.into_int_value(), "not")?.into()),
         }
     }
     
     /// 编译中缀表达式
     fn compile_infix_expression(&mut self, infix_expr: &crate::ast::InfixExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // NEW: 引入 use 语句，简化后续代码
-        
+        if matches!(infix_expr.op, Operator::LogicalAnd | Operator::LogicalOr) {
+            return self.compile_logical_expression(infix_expr);
+        }
+
         let left = self.compile_expression(&infix_expr.left)?;
         let right = self.compile_expression(&infix_expr.right)?;
+        // 两个操作数类型相同（语义分析保证），看左边即可
+        let unsigned = self.is_unsigned(&infix_expr.left);
+        self.build_binary_operation(infix_expr.op, left, right, unsigned)
+    }
 
+    /// 对两个已经求值的操作数生成二元运算（`&&` 和 `||` 除外，它们需要短路求值）。
+    ///
+    /// 中缀表达式和复合赋值（`x += 1`）共用这里的逻辑。
+    /// `unsigned` 表示整数操作数是否按无符号处理，它决定除法、取余、右移和大小比较使用的指令。
+    fn build_binary_operation(
+        &self,
+        op: Operator,
+        left: BasicValueEnum<'ctx>,
+        right: BasicValueEnum<'ctx>,
+        unsigned: bool,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         if left.is_int_value() && right.is_int_value() {
             let l = left.into_int_value();
             let r = right.into_int_value();
-            match infix_expr.op {
+            // 有符号与无符号整数的大小比较使用不同的谓词，e.g., `SLT` / `ULT`
+            let (lt, le, gt, ge) = if unsigned {
+                (IntPredicate::ULT, IntPredicate::ULE, IntPredicate::UGT, IntPredicate::UGE)
+            } else {
+                (IntPredicate::SLT, IntPredicate::SLE, IntPredicate::SGT, IntPredicate::SGE)
+            };
+            match op {
                 // --- 算术运算 ---
                 Operator::Plus => Ok(self.builder.build_int_add(l, r, "add")?.into()),
                 Operator::Minus => Ok(self.builder.build_int_sub(l, r, "sub")?.into()),
                 Operator::Multiply => Ok(self.builder.build_int_mul(l, r, "mul")?.into()),
+                Operator::Divide if unsigned => Ok(self.builder.build_int_unsigned_div(l, r, "udiv")?.into()),
                 Operator::Divide => Ok(self.builder.build_int_signed_div(l, r, "div")?.into()),
+                Operator::Remainder if unsigned => Ok(self.builder.build_int_unsigned_rem(l, r, "urem")?.into()),
+                Operator::Remainder => Ok(self.builder.build_int_signed_rem(l, r, "rem")?.into()),
+                // --- 位运算 ---
+                Operator::BitAnd => Ok(self.builder.build_and(l, r, "and")?.into()),
+                Operator::BitOr => Ok(self.builder.build_or(l, r, "or")?.into()),
+                Operator::BitXor => Ok(self.builder.build_xor(l, r, "xor")?.into()),
+                // 有符号整数右移时复制符号位（算术右移），无符号整数补 0（逻辑右移）
+                Operator::ShiftLeft => Ok(self.builder.build_left_shift(l, self.mask_shift_amount(r)?, "shl")?.into()),
+                Operator::ShiftRight => {
+                    Ok(self.builder.build_right_shift(l, self.mask_shift_amount(r)?, !unsigned, "shr")?.into())
+                }
                 // --- 比较运算 ---
                 Operator::Equal => Ok(self.builder.build_int_compare(IntPredicate::EQ, l, r, "eq")?.into()),
                 Operator::NotEqual => Ok(self.builder.build_int_compare(IntPredicate::NE, l, r, "ne")?.into()),
-                Operator::LessThan => Ok(self.builder.build_int_compare(IntPredicate::SLT, l, r, "lt")?.into()),
-                Operator::LessEqual => Ok(self.builder.build_int_compare(IntPredicate::SLE, l, r, "le")?.into()),
-                Operator::GreaterThan => Ok(self.builder.build_int_compare(IntPredicate::SGT, l, r, "gt")?.into()),
-                Operator::GreaterEqual => Ok(self.builder.build_int_compare(IntPredicate::SGE, l, r, "ge")?.into()),
+                Operator::LessThan => Ok(self.builder.build_int_compare(lt, l, r, "lt")?.into()),
+                Operator::LessEqual => Ok(self.builder.build_int_compare(le, l, r, "le")?.into()),
+                Operator::GreaterThan => Ok(self.builder.build_int_compare(gt, l, r, "gt")?.into()),
+                Operator::GreaterEqual => Ok(self.builder.build_int_compare(ge, l, r, "ge")?.into()),
+                Operator::LogicalAnd | Operator::LogicalOr => unreachable!("`&&` and `||` are handled by compile_logical_expression"),
             }
         } else if left.is_float_value() && right.is_float_value() {
             let l = left.into_float_value();
             let r = right.into_float_value();
-            match infix_expr.op {
+            match op {
                 // --- 算术运算 ---
                 Operator::Plus => Ok(self.builder.build_float_add(l, r, "fadd")?.into()),
                 Operator::Minus => Ok(self.builder.build_float_sub(l, r, "fsub")?.into()),
@@ -674,18 +955,186 @@ impl<'ctx> CodeGen<'ctx> {
                 Operator::LessEqual => Ok(self.builder.build_float_compare(FloatPredicate::OLE, l, r, "fle")?.into()),
                 Operator::GreaterThan => Ok(self.builder.build_float_compare(FloatPredicate::OGT, l, r, "fgt")?.into()),
                 Operator::GreaterEqual => Ok(self.builder.build_float_compare(FloatPredicate::OGE, l, r, "fge")?.into()),
+                _ => Err(CodegenError::Message(format!("Operator `{}` is not supported for floats.", op))),
+            }
+        } else if left.is_struct_value() && right.is_struct_value() {
+            // 字符串只支持 `==` 和 `!=`（由语义分析保证）
+            let equal = self.build_str_equal(left.into_struct_value(), right.into_struct_value())?;
+            match op {
+                Operator::Equal => Ok(equal.into()),
+                Operator::NotEqual => Ok(self.builder.build_not(equal, "str_ne")?.into()),
+                _ => Err(CodegenError::Message(format!("Operator `{}` is not supported for strings.", op))),
+            }
+        } else if left.is_pointer_value() && right.is_pointer_value() {
+            // 指针只支持 `==` 和 `!=`（由语义分析保证），比较的是地址
+            let int_type = self.context.i64_type();
+            let l = self.builder.build_ptr_to_int(left.into_pointer_value(), int_type, "lhs_addr")?;
+            let r = self.builder.build_ptr_to_int(right.into_pointer_value(), int_type, "rhs_addr")?;
+            match op {
+                Operator::Equal => Ok(self.builder.build_int_compare(IntPredicate::EQ, l, r, "ptr_eq")?.into()),
+                Operator::NotEqual => Ok(self.builder.build_int_compare(IntPredicate::NE, l, r, "ptr_ne")?.into()),
+                _ => Err(CodegenError::Message(format!("Operator `{}` is not supported for pointers.", op))),
             }
         } else {
             Err(CodegenError::Message("Mismatched or unsupported types in binary operation.".to_string()))
         }
     }
+
+    /// 将移位量对位宽取模，e.g., 对 `u8` 来说 `x << 9` 等价于 `x << 1`。
+    ///
+    /// 在 LLVM 中，移位量大于等于位宽的 `shl` / `lshr` / `ashr` 会得到 poison 值，
+    /// 取模之后的结果是确定的（与 Rust 的 `wrapping_shl` / `wrapping_shr` 相同）。
+    fn mask_shift_amount(&self, amount: IntValue<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let int_type = amount.get_type();
+        let mask = int_type.const_int(int_type.get_bit_width() as u64 - 1, false);
+        Ok(self.builder.build_and(amount, mask, "shamt")?)
+    }
+
+    /// 编译短路求值的 `&&` 和 `||`。
+    ///
+    /// 只有左操作数无法决定结果时才会求值右操作数：
+    /// - `a && b`：`a` 为假时直接得到 `false`，否则跳到 `rhs` 块求值 `b`。
+    /// - `a || b`：`a` 为真时直接得到 `true`，否则跳到 `rhs` 块求值 `b`。
+    ///
+    /// 两条路径在 `merge` 块中用 PHI 节点合并。
+    fn compile_logical_expression(&mut self, infix_expr: &crate::ast::InfixExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        let function = self.current_function.ok_or_else(|| {
+            CodegenError::Message("Cannot compile a logical operator: not in a function context.".to_string())
+        })?;
+        let is_and = infix_expr.op == Operator::LogicalAnd;
+
+        let left = self.compile_expression(&infix_expr.left)?.into_int_value();
+        // 左操作数本身可能包含分支，短路时 PHI 的来源是它结束时所在的块
+        let left_block = self.builder.get_insert_block().unwrap();
+
+        let rhs_block = self.context.append_basic_block(function, if is_and { "and_rhs" } else { "or_rhs" });
+        let merge_block = self.context.append_basic_block(function, if is_and { "and_merge" } else { "or_merge" });
+        if is_and {
+            self.builder.build_conditional_branch(left, rhs_block, merge_block)?;
+        } else {
+            self.builder.build_conditional_branch(left, merge_block, rhs_block)?;
+        }
+
+        // --- 编译右操作数 ---
+        self.builder.position_at_end(rhs_block);
+        let right = self.compile_expression(&infix_expr.right)?.into_int_value();
+        let right_end = if self.current_block_terminated() {
+            None
+        } else {
+            self.builder.build_unconditional_branch(merge_block)?;
+            self.builder.get_insert_block()
+        };
+
+        // --- 合并结果 ---
+        self.builder.position_at_end(merge_block);
+        let bool_type = self.context.bool_type();
+        // 短路时的结果：`&&` 为 false，`||` 为 true
+        let short_circuit_value = bool_type.const_int(!is_and as u64, false);
+        let phi = self.builder.build_phi(bool_type, if is_and { "and" } else { "or" })?;
+        phi.add_incoming(&[(&short_circuit_value, left_block)]);
+        if let Some(right_end) = right_end {
+            phi.add_incoming(&[(&right, right_end)]);
+        }
+        Ok(phi.as_basic_value())
+    }
+
+    /// 比较两个字符串是否相等：长度相同，并且 `memcmp` 比较所有字节的结果为 0。
+    fn build_str_equal(&self, lhs: StructValue<'ctx>, rhs: StructValue<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let lhs_ptr = self.builder.build_extract_value(lhs, 0, "lhs_ptr")?.into_pointer_value();
+        let lhs_len = self.builder.build_extract_value(lhs, 1, "lhs_len")?.into_int_value();
+        let rhs_ptr = self.builder.build_extract_value(rhs, 0, "rhs_ptr")?.into_pointer_value();
+        let rhs_len = self.builder.build_extract_value(rhs, 1, "rhs_len")?.into_int_value();
+
+        let len_equal = self.builder.build_int_compare(IntPredicate::EQ, lhs_len, rhs_len, "len_eq")?;
+        // 长度不同时也只比较较短的那部分，避免读取越界
+        let lhs_shorter = self.builder.build_int_compare(IntPredicate::ULT, lhs_len, rhs_len, "lhs_shorter")?;
+        let common_len = self.builder.build_select(lhs_shorter, lhs_len, rhs_len, "common_len")?.into_int_value();
+
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let memcmp_type = self.context.i32_type().fn_type(
+            &[ptr_type.into(), ptr_type.into(), self.context.i64_type().into()],
+            false,
+        );
+        let memcmp = self.runtime_function("memcmp", memcmp_type)?;
+        let cmp = self
+            .builder
+            .build_call(memcmp, &[lhs_ptr.into(), rhs_ptr.into(), common_len.into()], "memcmp")?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| CodegenError::Message("`memcmp` must return a value.".to_string()))?
+            .into_int_value();
+        let bytes_equal = self.builder.build_int_compare(IntPredicate::EQ, cmp, self.context.i32_type().const_zero(), "bytes_eq")?;
+
+        Ok(self.builder.build_and(len_equal, bytes_equal, "str_eq")?)
+    }
     
+    /// 编译类型转换表达式 `value as T`。
+    ///
+    /// 源类型和目标类型都来自语义分析的结果，`bool` 和 `char` 按无符号整数处理：
+    /// - 整数之间：`trunc` / `sext` / `zext`
+    /// - 整数与浮点数之间：`sitofp` / `uitofp`，`fptosi` / `fptoui`
+    /// - 浮点数之间：`fpext` / `fptrunc`
+    /// - 指针与整数之间：`ptrtoint` / `inttoptr`。指针之间的转换不需要任何指令。
+    fn compile_cast_expression(&mut self, cast_expr: &CastExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        let value = self.compile_expression(&cast_expr.value)?;
+        let (from, to) = match (self.expression_types.get(&cast_expr.value.id()), self.expression_types.get(&cast_expr.id)) {
+            (Some(from), Some(to)) => (from.clone(), to.clone()),
+            _ => return Err(CodegenError::Message("Missing type information for `as` cast.".to_string())),
+        };
+        if from == to {
+            return Ok(value);
+        }
+
+        let target = self.to_llvm_basic_type(&to)?;
+        let signed = from.is_signed_integer();
+        let cast: BasicValueEnum<'ctx> = match (value, target) {
+            (BasicValueEnum::IntValue(v), BasicTypeEnum::IntType(t)) => self.build_int_resize(v, t, signed)?.into(),
+            (BasicValueEnum::IntValue(v), BasicTypeEnum::FloatType(t)) if signed => {
+                self.builder.build_signed_int_to_float(v, t, "sitofp")?.into()
+            }
+            (BasicValueEnum::IntValue(v), BasicTypeEnum::FloatType(t)) => {
+                self.builder.build_unsigned_int_to_float(v, t, "uitofp")?.into()
+            }
+            (BasicValueEnum::FloatValue(v), BasicTypeEnum::IntType(t)) if to.is_signed_integer() => {
+                self.builder.build_float_to_signed_int(v, t, "fptosi")?.into()
+            }
+            (BasicValueEnum::FloatValue(v), BasicTypeEnum::IntType(t)) => {
+                self.builder.build_float_to_unsigned_int(v, t, "fptoui")?.into()
+            }
+            (BasicValueEnum::FloatValue(v), BasicTypeEnum::FloatType(t)) if to == TipyType::F64 => {
+                self.builder.build_float_ext(v, t, "fpext")?.into()
+            }
+            (BasicValueEnum::FloatValue(v), BasicTypeEnum::FloatType(t)) => {
+                self.builder.build_float_trunc(v, t, "fptrunc")?.into()
+            }
+            (BasicValueEnum::PointerValue(v), BasicTypeEnum::IntType(t)) => {
+                self.builder.build_ptr_to_int(v, t, "ptrtoint")?.into()
+            }
+            (BasicValueEnum::IntValue(v), BasicTypeEnum::PointerType(t)) => {
+                self.builder.build_int_to_ptr(v, t, "inttoptr")?.into()
+            }
+            (BasicValueEnum::PointerValue(v), BasicTypeEnum::PointerType(_)) => v.into(),
+            _ => return Err(CodegenError::Message(format!("Unsupported cast from `{}` to `{}`.", from, to))),
+        };
+        Ok(cast)
+    }
+
     /// 编译赋值表达式
+    ///
+    /// 复合赋值 `x op= v` 先求值右侧的 `v`，再通过 `compile_lvalue_expression` 取得 `x` 的地址，
+    /// 从这个地址读出旧值、计算 `旧值 op v` 并写回。左值的地址只计算一次。
     fn compile_assignment_expression(&mut self, assign_expr: &crate::ast::AssignmentExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
-        let compiled_value = self.compile_expression(&assign_expr.value)?;
+        let mut compiled_value = self.compile_expression(&assign_expr.value)?;
 
         // `compile_lvalue` 是一个新的辅助函数，它返回一个指针，而不是值
         let ptr = self.compile_lvalue_expression(&assign_expr.left)?;
+
+        if let Some(op) = assign_expr.op {
+            // 左右两侧类型相同（语义分析保证），所以可以用右侧值的类型读取旧值
+            let old_value = self.builder.build_load(compiled_value.get_type(), ptr, "old")?;
+            let unsigned = self.is_unsigned(&assign_expr.left);
+            compiled_value = self.build_binary_operation(op, old_value, compiled_value, unsigned)?;
+        }
         
         self.builder.build_store(ptr, compiled_value)?;
         // 赋值表达式的值就是被赋的值
@@ -695,24 +1144,28 @@ impl<'ctx> CodeGen<'ctx> {
     /// 编译一个“左值”表达式，返回其内存地址（指针）
     fn compile_lvalue_expression(&mut self, expr: &Expression) -> Result<PointerValue<'ctx>, CodegenError> {
         match expr {
-            Expression::Identifier(name) => {
-                self.lookup_variable(name).map(|(ptr, _)| *ptr).ok_or_else(|| CodegenError::SymbolNotFound(name.clone()))
+            Expression::Identifier(ident) => {
+                self.lookup_variable(&ident.name).map(|(ptr, _)| *ptr).ok_or_else(|| CodegenError::SymbolNotFound(ident.name.clone()))
             }
             // TODO: 支持更复杂的左值，如 `a.b` 或 `*p`
             _ => Err(CodegenError::InvalidLValue),
         }
     }
     
-    /// 编译函数调用
-    fn compile_call_expression(&mut self, call_expr: &crate::ast::CallExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+    /// 编译函数调用。被调用的函数没有返回值时返回 `Ok(None)`。
+    fn compile_call_expression(&mut self, call_expr: &crate::ast::CallExpression) -> Result<Option<BasicValueEnum<'ctx>>, CodegenError> {
         // 我们假设 callee 是一个简单的标识符
-        let callee_name = if let Expression::Identifier(name) = &*call_expr.function {
-            name
+        let callee_name = if let Expression::Identifier(ident) = &*call_expr.function {
+            &ident.name
         } else {
             return Err(CodegenError::Message("Complex function calls are not supported.".to_string()));
         };
         
-        let function = self.module.get_function(callee_name).ok_or_else(|| CodegenError::SymbolNotFound(callee_name.clone()))?;
+        // 没有对应的 LLVM 函数时，被调用的是 prelude 中的内置函数
+        let function = match self.module.get_function(callee_name) {
+            Some(function) => function,
+            None => return self.compile_builtin_call(callee_name, &call_expr.arguments),
+        };
 
         // --- 将参数编译过程拆分为两步，解决类型推断问题 ---
 
@@ -732,16 +1185,186 @@ impl<'ctx> CodeGen<'ctx> {
             .collect();
         
         let call_site = self.builder.build_call(function, &compiled_args, "call_tmp")?;
+        Ok(call_site.try_as_basic_value().left())
+    }
 
-        match call_site.try_as_basic_value().left() {
-            Some(value) => Ok(value),
-            None => Err(CodegenError::Message("Cannot use a void function as an expression.".to_string())),
+    /// 在调用处展开一个内置函数（见 `prelude` 模块）。没有返回值的内置函数返回 `Ok(None)`。
+    fn compile_builtin_call(&mut self, name: &str, arguments: &[Expression]) -> Result<Option<BasicValueEnum<'ctx>>, CodegenError> {
+        let args = arguments
+            .iter()
+            .map(|arg| self.compile_expression(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match (name, args.as_slice()) {
+            ("len", [s]) => Ok(Some(self.builder.build_extract_value(s.into_struct_value(), 1, "len")?)),
+            ("byte_at", [s, index]) => Ok(Some(self.build_str_byte_at(s.into_struct_value(), index.into_int_value())?.into())),
+            ("print" | "println", _) => {
+                // 重载由语义分析选定，这里根据实参的类型决定打印方式
+                let arg_types = arguments
+                    .iter()
+                    .map(|arg| self.expression_types.get(&arg.id()).cloned())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| CodegenError::Message(format!("Missing type information for `{}` arguments.", name)))?;
+                self.build_print(&args, &arg_types, name == "println")?;
+                Ok(None)
+            }
+            _ => Err(CodegenError::SymbolNotFound(name.to_string())),
         }
     }
 
+    /// 通过 C 的 `printf` 打印一个值，`newline` 为真时在末尾追加换行符。
+    ///
+    /// 每种类型对应一个格式：整数统一扩展为 64 位后用 `%lld` / `%llu` 打印，
+    /// 浮点数扩展为 `f64` 后用 `%g` 打印，`str` 和 `char` 用 `%.*s` 按长度打印字节。
+    fn build_print(&mut self, values: &[BasicValueEnum<'ctx>], types: &[TipyType], newline: bool) -> Result<(), CodegenError> {
+        let i64_type = self.context.i64_type();
+        let (format, mut printf_args): (&str, Vec<BasicMetadataValueEnum<'ctx>>) = match (values, types) {
+            ([], []) => ("", Vec::new()),
+            ([value], [TipyType::Str]) => {
+                let s = value.into_struct_value();
+                let ptr = self.builder.build_extract_value(s, 0, "str_ptr")?;
+                let len = self.builder.build_extract_value(s, 1, "str_len")?.into_int_value();
+                // `%.*s` 的精度参数是 `int`
+                let len = self.builder.build_int_truncate(len, self.context.i32_type(), "str_len32")?;
+                ("%.*s", vec![len.into(), ptr.into()])
+            }
+            ([value], [TipyType::Char]) => {
+                let (buffer, len) = self.build_utf8_encode(value.into_int_value())?;
+                ("%.*s", vec![len.into(), buffer.into()])
+            }
+            ([value], [TipyType::Bool]) => {
+                let true_str = self.c_string_constant("true")?;
+                let false_str = self.c_string_constant("false")?;
+                let text = self.builder.build_select(value.into_int_value(), true_str, false_str, "bool_str")?;
+                ("%s", vec![text.into()])
+            }
+            ([value], [t]) if t.is_signed_integer() => {
+                ("%lld", vec![self.build_int_resize(value.into_int_value(), i64_type, true)?.into()])
+            }
+            ([value], [t]) if t.is_unsigned_integer() => {
+                ("%llu", vec![self.build_int_resize(value.into_int_value(), i64_type, false)?.into()])
+            }
+            // C 的可变参数函数只接受 `double`，`f32` 需要先扩展
+            ([value], [TipyType::F32]) => {
+                let value = self.builder.build_float_ext(value.into_float_value(), self.context.f64_type(), "fpext")?;
+                ("%g", vec![value.into()])
+            }
+            ([value], [TipyType::F64]) => ("%g", vec![(*value).into()]),
+            _ => return Err(CodegenError::Message("Unsupported arguments for `print`.".to_string())),
+        };
+
+        let format = self.c_string_constant(&format!("{}{}", format, if newline { "\n" } else { "" }))?;
+        printf_args.insert(0, format.into());
+
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let printf = self.runtime_function("printf", self.context.i32_type().fn_type(&[ptr_type.into()], true))?;
+        self.builder.build_call(printf, &printf_args, "printf")?;
+        Ok(())
+    }
+
+    /// 把一个 Unicode 码点编码为 UTF-8，写入当前函数栈上的一个 4 字节缓冲区。
+    ///
+    /// 返回缓冲区的指针和编码后的字节数（`i32`，可以直接作为 `%.*s` 的精度参数）。
+    /// 编码不使用分支，而是根据码点的范围用 `select` 选出每个字节的值：
+    ///
+    /// | 码点范围            | 字节数 | 第 1 字节          | 后续字节                  |
+    /// |---------------------|--------|--------------------|---------------------------|
+    /// | `..0x80`            | 1      | `c`                |                           |
+    /// | `0x80..0x800`       | 2      | `0xC0 \| c >> 6`   | `0x80 \| (c >> 6k) & 0x3F` |
+    /// | `0x800..0x10000`    | 3      | `0xE0 \| c >> 12`  | 同上                      |
+    /// | `0x10000..`         | 4      | `0xF0 \| c >> 18`  | 同上                      |
+    fn build_utf8_encode(&self, c: IntValue<'ctx>) -> Result<(PointerValue<'ctx>, IntValue<'ctx>), CodegenError> {
+        let i8_type = self.context.i8_type();
+        let i32_type = self.context.i32_type();
+        let b = &self.builder;
+        let constant = |v: u64| i32_type.const_int(v, false);
+
+        let below = |limit: u64, name: &str| b.build_int_compare(IntPredicate::ULT, c, constant(limit), name);
+        let is_1 = below(0x80, "utf8_is_1")?;
+        let is_2 = below(0x800, "utf8_is_2")?;
+        let is_3 = below(0x10000, "utf8_is_3")?;
+
+        let select = |cond, then: IntValue<'ctx>, otherwise: IntValue<'ctx>| -> Result<IntValue<'ctx>, CodegenError> {
+            Ok(b.build_select(cond, then, otherwise, "utf8_select")?.into_int_value())
+        };
+        // `c >> shift` 的低 6 位，加上后续字节的标记 `10xxxxxx`
+        let continuation = |shift: u64| -> Result<IntValue<'ctx>, CodegenError> {
+            let shifted = b.build_right_shift(c, constant(shift), false, "utf8_shr")?;
+            let bits = b.build_and(shifted, constant(0x3F), "utf8_bits")?;
+            Ok(b.build_or(bits, constant(0x80), "utf8_cont")?)
+        };
+        // 首字节：`c >> shift` 加上表示字节数的前缀
+        let lead = |shift: u64, prefix: u64| -> Result<IntValue<'ctx>, CodegenError> {
+            let shifted = b.build_right_shift(c, constant(shift), false, "utf8_shr")?;
+            Ok(b.build_or(shifted, constant(prefix), "utf8_lead")?)
+        };
+
+        let len = select(is_1, constant(1), select(is_2, constant(2), select(is_3, constant(3), constant(4))?)?)?;
+        let bytes = [
+            select(is_1, c, select(is_2, lead(6, 0xC0)?, select(is_3, lead(12, 0xE0)?, lead(18, 0xF0)?)?)?)?,
+            select(is_2, continuation(0)?, select(is_3, continuation(6)?, continuation(12)?)?)?,
+            select(is_3, continuation(0)?, continuation(6)?)?,
+            continuation(0)?,
+        ];
+
+        let buffer = self.create_entry_block_alloca(i8_type.array_type(4).as_basic_type_enum(), "utf8_buf")?;
+        for (i, byte) in bytes.into_iter().enumerate() {
+            let byte = b.build_int_truncate(byte, i8_type, "utf8_byte")?;
+            // SAFETY: 下标 0..4 都在 4 字节缓冲区之内
+            let byte_ptr = unsafe { b.build_gep(i8_type, buffer, &[self.context.i64_type().const_int(i as u64, false)], "utf8_ptr")? };
+            b.build_store(byte_ptr, byte)?;
+        }
+        Ok((buffer, len))
+    }
+
+    /// 返回一个以 `\0` 结尾的全局字符串常量的指针，内容相同的常量只生成一次。
+    fn c_string_constant(&self, content: &str) -> Result<PointerValue<'ctx>, CodegenError> {
+        let name = format!("cstr.{}", content.escape_default());
+        if let Some(global) = self.module.get_global(&name) {
+            return Ok(global.as_pointer_value());
+        }
+        Ok(self.builder.build_global_string_ptr(content, &name)?.as_pointer_value())
+    }
+
+    /// 读取字符串的第 `index` 个字节。下标越界时调用 `abort` 终止程序。
+    fn build_str_byte_at(&mut self, s: StructValue<'ctx>, index: IntValue<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let function = self.current_function.ok_or_else(|| CodegenError::Message("`byte_at` called outside of a function.".to_string()))?;
+        let ptr = self.builder.build_extract_value(s, 0, "str_ptr")?.into_pointer_value();
+        let len = self.builder.build_extract_value(s, 1, "str_len")?.into_int_value();
+
+        let in_bounds_bb = self.context.append_basic_block(function, "index_ok");
+        let out_of_bounds_bb = self.context.append_basic_block(function, "index_out_of_bounds");
+        // 下标是 `usize`，所以一次无符号比较就同时排除了“负数”和超过长度的情况
+        let in_bounds = self.builder.build_int_compare(IntPredicate::ULT, index, len, "in_bounds")?;
+        self.builder.build_conditional_branch(in_bounds, in_bounds_bb, out_of_bounds_bb)?;
+
+        self.builder.position_at_end(out_of_bounds_bb);
+        let abort = self.runtime_function("abort", self.context.void_type().fn_type(&[], false))?;
+        self.builder.build_call(abort, &[], "")?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(in_bounds_bb);
+        let i8_type = self.context.i8_type();
+        // SAFETY: 上面已经检查过 `index < len`，地址在字符串的范围之内
+        let byte_ptr = unsafe { self.builder.build_gep(i8_type, ptr, &[index], "byte_ptr")? };
+        Ok(self.builder.build_load(i8_type, byte_ptr, "byte")?.into_int_value())
+    }
+
     /// 编译 if-else 表达式
-    fn compile_if_expression(&mut self, if_expr: &IfExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
-        let function = self.current_function.unwrap();
+    ///
+    /// 每个分支编译结束后，如果它没有以 `ret` / `break` 等终结，就跳转到 `merge` 块。
+    /// 当语义分析推断出的 `if` 类型不是 `Void` 时，用 PHI 节点合并这些分支的值。
+    ///
+    /// # Returns
+    /// - `Ok(Some(value))` 如果 `if` 作为表达式产生了值。
+    /// - `Ok(None)` 如果 `if` 是语句（没有 `else`，或类型为 `Void`），或所有分支都不会到达 `merge`。
+    fn compile_if_expression(
+        &mut self,
+        if_expr: &IfExpression,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodegenError> {
+        let function = self.current_function.ok_or_else(|| {
+            CodegenError::Message("Cannot compile if: not in a function context.".to_string())
+        })?;
         
         let condition = self.compile_expression(&if_expr.condition)?.into_int_value();
         
@@ -750,30 +1373,47 @@ impl<'ctx> CodeGen<'ctx> {
         let merge_block = self.context.append_basic_block(function, "merge");
         
         self.builder.build_conditional_branch(condition, then_block, else_block)?;
+
+        // 到达 merge 块的各个分支：(分支的值, 分支结束时所在的块)
+        let mut incoming = Vec::new();
         
         // --- 编译 then 分支 ---
         self.builder.position_at_end(then_block);
-        let then_val = self.compile_block_statement(&if_expr.consequence)?.unwrap(); // 假设 if 作为表达式必须有返回值
-        self.builder.build_unconditional_branch(merge_block)?;
-        let then_end_block = self.builder.get_insert_block().unwrap();
+        let then_val = self.compile_block_statement(&if_expr.consequence)?;
+        if !self.current_block_terminated() {
+            self.builder.build_unconditional_branch(merge_block)?;
+            incoming.push((then_val, self.builder.get_insert_block().unwrap()));
+        }
         
         // --- 编译 else 分支 ---
+        // 没有 `else` 时，else 块为空，直接跳到 merge
         self.builder.position_at_end(else_block);
-        let else_val = if let Some(alt) = &if_expr.alternative {
-             self.compile_expression(alt)?
-        } else {
-            // 如果 if-else 表达式要返回值，else 分支必须存在
-            return Err(CodegenError::Message("'if' expression must have an 'else' branch".to_string()));
+        let else_val = match &if_expr.alternative {
+            Some(alt) => self.compile_expression_statement(alt)?,
+            None => None,
         };
-        self.builder.build_unconditional_branch(merge_block)?;
-        let else_end_block = self.builder.get_insert_block().unwrap();
+        if !self.current_block_terminated() {
+            self.builder.build_unconditional_branch(merge_block)?;
+            incoming.push((else_val, self.builder.get_insert_block().unwrap()));
+        }
 
         // --- 编译 merge (PHI) 块 ---
         self.builder.position_at_end(merge_block);
-        let phi = self.builder.build_phi(then_val.get_type(), "iftmp")?;
-        phi.add_incoming(&[(&then_val, then_end_block), (&else_val, else_end_block)]);
+
+        let if_type = self.expression_types.get(&if_expr.id).cloned().unwrap_or(TipyType::Void);
+        if if_type == TipyType::Void || incoming.is_empty() {
+            return Ok(None);
+        }
+
+        let phi = self.builder.build_phi(self.to_llvm_basic_type(&if_type)?, "iftmp")?;
+        for (value, block) in &incoming {
+            let value = value.ok_or_else(|| {
+                CodegenError::Message("Every branch of an `if` expression must produce a value.".to_string())
+            })?;
+            phi.add_incoming(&[(&value, *block)]);
+        }
         
-        Ok(phi.as_basic_value())
+        Ok(Some(phi.as_basic_value()))
     }
 
     /// 编译 `loop` 表达式。
@@ -788,24 +1428,36 @@ impl<'ctx> CodeGen<'ctx> {
     ///
     /// # 关于返回值
     /// `loop` 本身是一个表达式，其类型和值由 `break <value>` 语句决定。
-    /// 一个完整的实现需要使用 PHI 节点来合并所有可能的 `break` 的值。
-    /// 为简化起见，当前版本假定 `loop` 表达式不返回值 (类型为 Void)。
-    fn compile_loop_expression(&mut self, loop_expr: &LoopExpression) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+    /// 语义分析器已经推断出了 `loop` 的类型：如果它不是 `Void`，我们就在栈上为结果
+    /// 分配一块内存，每个 `break <value>` 把值存进去，循环结束后再从中加载。
+    /// 这样不需要手动构造 PHI 节点，`mem2reg` 会把它优化为寄存器。
+    ///
+    /// # Returns
+    /// - `Ok(Some(value))` 如果 `loop` 通过 `break <value>` 产生值。
+    /// - `Ok(None)` 如果 `loop` 的类型为 `Void`。
+    fn compile_loop_expression(
+        &mut self,
+        loop_expr: &LoopExpression,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodegenError> {
         let function = self.current_function.ok_or_else(|| {
             CodegenError::Message("Cannot compile loop: not in a function context.".to_string())
         })?;
 
-        // --- 核心改动：使用 Alloca 模式 ---
-        // TODO: loop 表达式的返回类型应该由语义分析器推断出来。
-        //       这里我们暂时硬编码为 i64 作为示例。
-        let result_type = self.context.i64_type().as_basic_type_enum();
-        let result_alloca = self.create_entry_block_alloca(result_type, "loop_result")?;
+        // --- 使用 Alloca 模式存放 loop 的结果 ---
+        let loop_type = self.expression_types.get(&loop_expr.id).cloned().unwrap_or(TipyType::Void);
+        let result = match loop_type {
+            TipyType::Void => None,
+            ref tipy_type => {
+                let result_type = self.to_llvm_basic_type(tipy_type)?;
+                Some((result_type, self.create_entry_block_alloca(result_type, "loop_result")?))
+            }
+        };
 
         let loop_bb = self.context.append_basic_block(function, "loop.body");
         let after_bb = self.context.append_basic_block(function, "loop.after");
 
         // 将循环上下文（包括结果指针）压入栈中
-        self.loop_context_stack.push((loop_bb, after_bb, Some(result_alloca)));
+        self.loop_context_stack.push((loop_bb, after_bb, result.map(|(_, alloca)| alloca)));
 
         // 从当前块跳转到循环体
         self.builder.build_unconditional_branch(loop_bb)?;
@@ -817,19 +1469,452 @@ impl<'ctx> CodeGen<'ctx> {
 
         // 如果循环体执行完都没有 break 或 return，说明它会无限循环。
         // 我们在这里也需要一个跳转，指回循环开头。
-        if loop_bb.get_terminator().is_none() {
+        // 注意要检查的是循环体编译结束时所在的块，循环体内的 `if` 等会创建新的块。
+        if !self.current_block_terminated() {
             self.builder.build_unconditional_branch(loop_bb)?;
         }
         
         // 离开循环，弹出上下文
         self.loop_context_stack.pop();
 
-        // --- 核心改动：加载最终结果 ---
+        // --- 加载最终结果 ---
         // 将 builder 定位到循环结束后的块
         self.builder.position_at_end(after_bb);
         // 从为 loop 结果预留的内存中加载值，这个值就是整个 loop 表达式的值。
-        let loop_result = self.builder.build_load(result_type, result_alloca, "loop_val")?;
-        
-        Ok(loop_result)
+        match result {
+            Some((result_type, result_alloca)) => {
+                Ok(Some(self.builder.build_load(result_type, result_alloca, "loop_val")?))
+            }
+            None => Ok(None),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// 调用系统链接器，把目标文件链接为可执行文件。
+///
+/// 默认使用 `cc`（它会自动链接 C 运行时并提供 `_start`），
+/// 可以通过环境变量 `CC` 指定其他的编译器驱动，e.g., `CC=clang-18`。
+pub fn link_executable(object_path: &Path, output_path: &Path) -> Result<(), CodegenError> {
+    let linker = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let status = Command::new(&linker)
+        .arg(object_path)
+        .arg("-o")
+        .arg(output_path)
+        .status()
+        .map_err(|e| CodegenError::Linker(format!("failed to run '{}': {}", linker, e)))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(CodegenError::Linker(format!("'{}' exited with {}", linker, status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use inkwell::execution_engine::{ExecutionEngine, JitFunction, UnsafeFunctionPointer};
+
+    use crate::analyzer::SemanticAnalyzer;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    // 编译一段没有错误的源码，返回可以直接调用其中函数的 JIT 引擎
+    fn jit<'ctx>(context: &'ctx Context, source: &str) -> ExecutionEngine<'ctx> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "parse errors: {:?}", parser.errors);
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program);
+        assert!(analyzer.errors.is_empty(), "semantic errors: {:?}", analyzer.errors);
+
+        let mut codegen = CodeGen::new(context, "test").unwrap();
+        codegen.compile(&program, &analyzer).unwrap();
+        codegen.module.verify().unwrap();
+        codegen.module.create_jit_execution_engine(OptimizationLevel::None).unwrap()
+    }
+
+    fn function<'ctx, F: UnsafeFunctionPointer>(engine: &ExecutionEngine<'ctx>, name: &str) -> JitFunction<'ctx, F> {
+        unsafe { engine.get_function(name) }.unwrap()
+    }
+
+    type Unary = unsafe extern "C" fn(i64) -> i64;
+    type Binary = unsafe extern "C" fn(i64, i64) -> i64;
+
+    #[test]
+    fn bool_ordering_treats_true_as_one() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            gt(a: i64, b: i64) -> i64 { if (a != 0) > (b != 0) { 1 } else { 0 } }
+            le(a: i64, b: i64) -> i64 { if (a != 0) <= (b != 0) { 1 } else { 0 } }
+        "#);
+        let gt = function::<Binary>(&engine, "gt");
+        let le = function::<Binary>(&engine, "le");
+        unsafe {
+            // 按有符号比较时 true 是 -1，这两个结果会反过来
+            assert_eq!(gt.call(1, 0), 1);
+            assert_eq!(gt.call(0, 1), 0);
+            assert_eq!(le.call(0, 1), 1);
+            assert_eq!(le.call(1, 0), 0);
+            assert_eq!(le.call(1, 1), 1);
+        }
+    }
+
+    #[test]
+    fn unsigned_operands_use_unsigned_division_and_comparison() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            div(a: u64, b: u64) -> u64 { a / b }
+            less(a: u64, b: u64) -> i64 { if a < b { 1 } else { 0 } }
+            sdiv(a: i64, b: i64) -> i64 { a / b }
+        "#);
+        let div = function::<unsafe extern "C" fn(u64, u64) -> u64>(&engine, "div");
+        let less = function::<unsafe extern "C" fn(u64, u64) -> i64>(&engine, "less");
+        let sdiv = function::<Binary>(&engine, "sdiv");
+        unsafe {
+            assert_eq!(div.call(u64::MAX, 2), u64::MAX / 2);
+            assert_eq!(less.call(1, u64::MAX), 1);
+            assert_eq!(less.call(u64::MAX, 1), 0);
+            assert_eq!(sdiv.call(-7, 2), -3);
+        }
+    }
+
+    #[test]
+    fn integer_casts_extend_and_truncate() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            sext(x: i8) -> i64 { x as i64 }
+            zext(x: u8) -> i64 { x as i64 }
+            trunc(x: i64) -> i64 { (x as i8) as i64 }
+        "#);
+        let sext = function::<unsafe extern "C" fn(i8) -> i64>(&engine, "sext");
+        let zext = function::<unsafe extern "C" fn(u8) -> i64>(&engine, "zext");
+        let trunc = function::<unsafe extern "C" fn(i64) -> i64>(&engine, "trunc");
+        unsafe {
+            assert_eq!(sext.call(-5), -5);
+            assert_eq!(zext.call(250), 250);
+            assert_eq!(trunc.call(200), -56);
+            assert_eq!(trunc.call(0x1234), 0x34);
+        }
+    }
+
+    #[test]
+    fn float_casts() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            to_int(x: f64) -> i64 { x as i64 }
+            to_f32(x: f64) -> f32 { x as f32 }
+            from_int(x: i64) -> f64 { x as f64 }
+        "#);
+        let to_int = function::<unsafe extern "C" fn(f64) -> i64>(&engine, "to_int");
+        let to_f32 = function::<unsafe extern "C" fn(f64) -> f32>(&engine, "to_f32");
+        let from_int = function::<unsafe extern "C" fn(i64) -> f64>(&engine, "from_int");
+        unsafe {
+            // 向零取整
+            assert_eq!(to_int.call(-2.75), -2);
+            assert_eq!(to_int.call(2.75), 2);
+            assert_eq!(to_f32.call(0.1), 0.1f32);
+            assert_eq!(from_int.call(-3), -3.0);
+        }
+    }
+
+    #[test]
+    fn pointers_compare_by_address() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            same(a: i64, b: i64) -> i64 { if (a as ^i64) == (b as ^i64) { 1 } else { 0 } }
+            differ(a: i64, b: i64) -> i64 { if (a as ^i64) != (b as ^i64) { 1 } else { 0 } }
+        "#);
+        let same = function::<Binary>(&engine, "same");
+        let differ = function::<Binary>(&engine, "differ");
+        unsafe {
+            assert_eq!(same.call(0x1000, 0x1000), 1);
+            assert_eq!(same.call(0x1000, 0x2000), 0);
+            assert_eq!(differ.call(0x1000, 0x2000), 1);
+            assert_eq!(differ.call(0x1000, 0x1000), 0);
+        }
+    }
+
+    #[test]
+    fn chars_compare_as_code_points() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            less(a: u32, b: u32) -> i64 { if (a as char) < (b as char) { 1 } else { 0 } }
+            is_digit(a: u32) -> i64 {
+                c: char = a as char;
+                if c >= '0' { if c <= '9' { 1 } else { 0 } } else { 0 }
+            }
+        "#);
+        let less = function::<unsafe extern "C" fn(u32, u32) -> i64>(&engine, "less");
+        let is_digit = function::<unsafe extern "C" fn(u32) -> i64>(&engine, "is_digit");
+        unsafe {
+            assert_eq!(less.call('a' as u32, 'b' as u32), 1);
+            assert_eq!(less.call('a' as u32, '\u{1F600}' as u32), 1);
+            assert_eq!(less.call('\u{1F600}' as u32, 'a' as u32), 0);
+            assert_eq!(is_digit.call('7' as u32), 1);
+            assert_eq!(is_digit.call('a' as u32), 0);
+        }
+    }
+
+    #[test]
+    fn char_casts_round_trip_through_u32() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            next(a: u32) -> u32 { ((a as char) as u32) + 1 }
+            code() -> u32 { '\u{1F600}' as u32 }
+            from_byte(b: u8) -> u32 { (b as char) as u32 }
+        "#);
+        let next = function::<unsafe extern "C" fn(u32) -> u32>(&engine, "next");
+        let code = function::<unsafe extern "C" fn() -> u32>(&engine, "code");
+        let from_byte = function::<unsafe extern "C" fn(u8) -> u32>(&engine, "from_byte");
+        unsafe {
+            assert_eq!(next.call('a' as u32), 'b' as u32);
+            assert_eq!(code.call(), 0x1F600);
+            // `u8` 零扩展，不会把 0xE9 变成负数
+            assert_eq!(from_byte.call(0xE9), 0xE9);
+        }
+    }
+
+    #[test]
+    fn and_evaluates_right_operand_only_when_left_is_true() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            f(a: i64) -> i64 {
+                n: ~i64 = 0;
+                if a != 0 && { n = n + 1; true } {
+                    n = n + 10;
+                }
+                n
+            }
+        "#);
+        let f = function::<Unary>(&engine, "f");
+        unsafe {
+            assert_eq!(f.call(0), 0);
+            assert_eq!(f.call(1), 11);
+        }
+    }
+
+    #[test]
+    fn or_evaluates_right_operand_only_when_left_is_false() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            f(a: i64) -> i64 {
+                n: ~i64 = 0;
+                if a != 0 || { n = n + 1; false } {
+                    n = n + 10;
+                }
+                n
+            }
+        "#);
+        let f = function::<Unary>(&engine, "f");
+        unsafe {
+            assert_eq!(f.call(1), 10);
+            assert_eq!(f.call(0), 1);
+        }
+    }
+
+    #[test]
+    fn short_circuit_guards_division_by_zero() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            guarded(a: i64, b: i64) -> i64 {
+                if b != 0 && a / b > 1 { 1 } else { 0 }
+            }
+        "#);
+        let guarded = function::<Binary>(&engine, "guarded");
+        unsafe {
+            // 不短路的话这里会除以零
+            assert_eq!(guarded.call(10, 0), 0);
+            assert_eq!(guarded.call(10, 2), 1);
+            assert_eq!(guarded.call(1, 2), 0);
+        }
+    }
+
+    #[test]
+    fn logical_operators_as_values() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            and(a: i64, b: i64) -> i64 {
+                x: bool = a != 0 && b != 0;
+                if x { 1 } else { 0 }
+            }
+            or(a: i64, b: i64) -> i64 {
+                x: bool = a != 0 || b != 0;
+                if x { 1 } else { 0 }
+            }
+        "#);
+        let and = function::<Binary>(&engine, "and");
+        let or = function::<Binary>(&engine, "or");
+        for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            unsafe {
+                assert_eq!(and.call(a, b), a & b, "{} && {}", a, b);
+                assert_eq!(or.call(a, b), a | b, "{} || {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn nested_logical_operators() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            f(a: i64, b: i64) -> i64 {
+                n: ~i64 = 0;
+                if (a != 0 || { n = n + 1; b != 0 }) && { n = n + 10; true } {
+                    n = n + 100;
+                }
+                n
+            }
+        "#);
+        let f = function::<Binary>(&engine, "f");
+        unsafe {
+            assert_eq!(f.call(1, 0), 110);
+            assert_eq!(f.call(0, 1), 111);
+            assert_eq!(f.call(0, 0), 1);
+        }
+    }
+
+    #[test]
+    fn right_shift_is_arithmetic_for_signed_and_logical_for_unsigned() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            shr_i32(x: i32, n: i32) -> i32 { x >> n }
+            shr_u32(x: u32, n: u32) -> u32 { x >> n }
+            shr_i8(x: i8, n: i8) -> i8 { x >> n }
+        "#);
+        let shr_i32 = function::<unsafe extern "C" fn(i32, i32) -> i32>(&engine, "shr_i32");
+        let shr_u32 = function::<unsafe extern "C" fn(u32, u32) -> u32>(&engine, "shr_u32");
+        let shr_i8 = function::<unsafe extern "C" fn(i8, i8) -> i8>(&engine, "shr_i8");
+        unsafe {
+            assert_eq!(shr_i32.call(-8, 1), -4);
+            assert_eq!(shr_i32.call(i32::MIN, 31), -1);
+            assert_eq!(shr_u32.call(0x8000_0000, 1), 0x4000_0000);
+            assert_eq!(shr_u32.call(0x8000_0000, 31), 1);
+            assert_eq!(shr_i8.call(-128, 7), -1);
+        }
+    }
+
+    #[test]
+    fn shift_amount_wraps_at_the_bit_width() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            shl_u8(x: u8, n: u8) -> u8 { x << n }
+            shr_i32(x: i32, n: i32) -> i32 { x >> n }
+            shr_u32(x: u32, n: u32) -> u32 { x >> n }
+        "#);
+        let shl_u8 = function::<unsafe extern "C" fn(u8, u8) -> u8>(&engine, "shl_u8");
+        let shr_i32 = function::<unsafe extern "C" fn(i32, i32) -> i32>(&engine, "shr_i32");
+        let shr_u32 = function::<unsafe extern "C" fn(u32, u32) -> u32>(&engine, "shr_u32");
+        unsafe {
+            // 与 Rust 的 wrapping_shl / wrapping_shr 相同
+            assert_eq!(shl_u8.call(1, 9), 1u8.wrapping_shl(9));
+            assert_eq!(shl_u8.call(1, 8), 1);
+            assert_eq!(shr_i32.call(-8, 33), (-8i32).wrapping_shr(33));
+            assert_eq!(shr_u32.call(0x8000_0000, 32), 0x8000_0000);
+            assert_eq!(shr_u32.call(0x8000_0000, 33), 0x8000_0000u32.wrapping_shr(33));
+        }
+    }
+
+    #[test]
+    fn remainder_follows_signedness() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            rem_i64(a: i64, b: i64) -> i64 { a % b }
+            rem_u64(a: u64, b: u64) -> u64 { a % b }
+        "#);
+        let rem_i64 = function::<Binary>(&engine, "rem_i64");
+        let rem_u64 = function::<unsafe extern "C" fn(u64, u64) -> u64>(&engine, "rem_u64");
+        unsafe {
+            assert_eq!(rem_i64.call(7, 3), 1);
+            assert_eq!(rem_i64.call(-7, 3), -1);
+            assert_eq!(rem_u64.call(u64::MAX, 10), u64::MAX % 10);
+        }
+    }
+
+    #[test]
+    fn bitwise_operators_and_precedence() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            mix(a: i64, b: i64) -> i64 { a | b ^ a & b << 1 }
+            not_u8(x: u8) -> u8 { !x }
+            low_bit_clear(x: i64) -> i64 { if x & 1 == 0 { 1 } else { 0 } }
+        "#);
+        let mix = function::<Binary>(&engine, "mix");
+        let not_u8 = function::<unsafe extern "C" fn(u8) -> u8>(&engine, "not_u8");
+        let low_bit_clear = function::<Unary>(&engine, "low_bit_clear");
+        unsafe {
+            for (a, b) in [(0b1100, 0b1010), (-1, 5), (0x0F0F, 0x3333)] {
+                assert_eq!(mix.call(a, b), a | b ^ a & b << 1, "mix({}, {})", a, b);
+            }
+            assert_eq!(not_u8.call(0b1010_0000), 0b0101_1111);
+            assert_eq!(low_bit_clear.call(4), 1);
+            assert_eq!(low_bit_clear.call(5), 0);
+        }
+    }
+
+    #[test]
+    fn compound_assignment_operators() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            add(a: i64, b: i64) -> i64 { x: ~i64 = a; x += b; x }
+            sub(a: i64, b: i64) -> i64 { x: ~i64 = a; x -= b; x }
+            mul(a: i64, b: i64) -> i64 { x: ~i64 = a; x *= b; x }
+            div(a: i64, b: i64) -> i64 { x: ~i64 = a; x /= b; x }
+            rem(a: i64, b: i64) -> i64 { x: ~i64 = a; x %= b; x }
+            and(a: i64, b: i64) -> i64 { x: ~i64 = a; x &= b; x }
+            or(a: i64, b: i64) -> i64 { x: ~i64 = a; x |= b; x }
+            xor(a: i64, b: i64) -> i64 { x: ~i64 = a; x ^= b; x }
+            shl(a: i64, b: i64) -> i64 { x: ~i64 = a; x <<= b; x }
+            shr(a: i64, b: i64) -> i64 { x: ~i64 = a; x >>= b; x }
+        "#);
+        let cases: [(&str, i64, i64, i64); 10] = [
+            ("add", 7, 3, 10),
+            ("sub", 7, 10, -3),
+            ("mul", -7, 3, -21),
+            ("div", -7, 2, -3),
+            ("rem", -7, 3, -1),
+            ("and", 0b1100, 0b1010, 0b1000),
+            ("or", 0b1100, 0b1010, 0b1110),
+            ("xor", 0b1100, 0b1010, 0b0110),
+            ("shl", 3, 4, 48),
+            // 有符号右移保留符号位
+            ("shr", -16, 2, -4),
+        ];
+        for (name, a, b, expected) in cases {
+            let f = function::<Binary>(&engine, name);
+            assert_eq!(unsafe { f.call(a, b) }, expected, "{}({}, {})", name, a, b);
+        }
+    }
+
+    #[test]
+    fn unsigned_compound_division_and_shift() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            div(a: u64, b: u64) -> u64 { x: ~u64 = a; x /= b; x }
+            shr(a: u64, b: u64) -> u64 { x: ~u64 = a; x >>= b; x }
+        "#);
+        let div = function::<unsafe extern "C" fn(u64, u64) -> u64>(&engine, "div");
+        let shr = function::<unsafe extern "C" fn(u64, u64) -> u64>(&engine, "shr");
+        unsafe {
+            assert_eq!(div.call(u64::MAX, 2), u64::MAX / 2);
+            assert_eq!(shr.call(u64::MAX, 60), 0xF);
+        }
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_the_value_before_reading_the_target() {
+        let context = Context::create();
+        let engine = jit(&context, r#"
+            order(a: i64) -> i64 { x: ~i64 = a; x += { x = 10; 1 }; x }
+            value(a: i64) -> i64 { x: ~i64 = a; y: i64 = (x *= 3); y + x }
+        "#);
+        let order = function::<Unary>(&engine, "order");
+        let value = function::<Unary>(&engine, "value");
+        unsafe {
+            // 右侧先求值并把 `x` 改成 10，之后才读取 `x` 的旧值，且只读取一次
+            assert_eq!(order.call(1), 11);
+            // 复合赋值表达式的值是赋值后的新值
+            assert_eq!(value.call(2), 12);
+        }
+    }
+}
@@ -30,6 +30,138 @@ impl fmt::Display for CompilerError {
     }
 }
 
+impl CompilerError {
+    /// 返回该错误的稳定错误码。
+    ///
+    /// 错误码按阶段分段：词法 `E00xx`，语法 `E005x`，语义 `E01xx`，代码生成 `E02xx`。
+    /// 错误码一经发布就不再改变或复用，编辑器和 CI 可以放心地依赖它们。
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilerError::Lexer(e) => e.code(),
+            CompilerError::Parser(e) => e.code(),
+            CompilerError::Semantic(e) => e.code(),
+            CompilerError::Codegen(e) => e.code(),
+        }
+    }
+
+    /// 将任意阶段的错误转换为统一的 `Diagnostic`，交给渲染器 (`emitter`) 打印。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diag = match self {
+            CompilerError::Lexer(e) => e.to_diagnostic(),
+            CompilerError::Parser(e) => e.to_diagnostic(),
+            CompilerError::Semantic(e) => e.to_diagnostic(),
+            CompilerError::Codegen(e) => e.to_diagnostic(),
+        };
+        diag.with_code(self.code())
+    }
+}
+
+// --- 统一的诊断信息 ---
+
+/// 诊断信息的严重程度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// 错误：编译失败。
+    Error,
+    /// 警告：代码可以编译，但很可能有问题。
+    Warning,
+    /// 提示：不代表任何问题，只是附加的信息，e.g., 如何查看错误码的详细说明。
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// 一条与具体编译阶段无关的诊断信息。
+///
+/// 各阶段的错误枚举只负责描述“发生了什么”，`Diagnostic` 则描述“如何展示”：
+/// 一句主消息、若干指向源码的标注 (`Label`)，以及附加的 `note:` / `help:`。
+/// 渲染工作由 `emitter` 模块完成。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 稳定的错误码，e.g., `E0101`。汇总信息等非错误诊断没有错误码。
+    pub code: Option<&'static str>,
+    /// 主消息，e.g., "mismatched types"。
+    pub message: String,
+    /// 指向源码的标注。第一个主标注 (`primary`) 决定了 `-->` 后打印的位置。
+    pub labels: Vec<Label>,
+    /// `= note: ...` 形式的补充说明。
+    pub notes: Vec<String>,
+    /// `= help: ...` 形式的修复建议。
+    pub helps: Vec<String>,
+}
+
+/// 诊断信息中指向源码某一段范围的标注。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    /// 打印在下划线右侧的说明，可以为空。
+    pub message: String,
+    /// 主标注用 `^^^` 标出错误本身，次标注用 `---` 标出相关的位置。
+    pub primary: bool,
+}
+
+impl Diagnostic {
+    /// 创建一个只有主消息的诊断，之后用 `with_*` 方法逐步补充。
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            helps: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn note(message: impl Into<String>) -> Self {
+        Self::new(Severity::Note, message)
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// 添加一个主标注。
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    /// 添加一个次标注。
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.helps.push(help.into());
+        self
+    }
+}
+
 // --- 词法分析阶段的错误 ---
 // UPDATED: 完善了所有 Lexer 可能产生的错误类型
 #[derive(Debug, Clone, PartialEq)]
@@ -42,16 +174,21 @@ pub enum LexerError {
     UnterminatedString { start_span: Span },
 
     /// 数字字面量的格式不正确。
-    /// 例如，"1.2.3" (多个小数点) 或者一个无法解析为 i64/f64 的巨大数字。
+    /// 例如，`0x`（前缀后没有数字）、`0b102`（数字不属于该进制）、`1.5u8`（非法的后缀）
+    /// 或者一个超出 64 位的巨大整数。
     MalformedNumberLiteral { reason: String, span: Span },
     
     /// 字符字面量的格式不正确。
     /// 例如，`'ab'` (包含多个字符) 或者 `'a` (没有找到闭合的单引号)。
     MalformedCharLiteral { span: Span },
 
-    // --- 为未来准备 ---
-    // /// 块注释 /* ... */ 没有找到闭合的 */
-    // UnterminatedBlockComment { start_span: Span },
+    /// 字符串或字符字面量中的转义序列不正确。`span` 覆盖整个转义序列。
+    /// 例如，`"\q"` (未知的转义) 或者 `'\u{110000}'` (不是合法的 Unicode 标量值)。
+    MalformedEscape { reason: String, span: Span },
+
+    /// 块注释 /* ... */ 没有找到闭合的 */，一直到了文件末尾。
+    /// 块注释可以嵌套，所以每一个 `/*` 都需要一个对应的 `*/`。`start_span` 是最外层 `/*` 的位置。
+    UnterminatedBlockComment { start_span: Span },
 }
 /// 为LexerError实现方便的打印trait
 impl fmt::Display for LexerError {
@@ -69,11 +206,62 @@ impl fmt::Display for LexerError {
             LexerError::MalformedCharLiteral { span } => {
                 write!(f, "Lexical Error: Malformed character literal at line {}, column {}.", span.line, span.column)
             }
+            LexerError::UnterminatedBlockComment { start_span } => {
+                write!(f, "Lexical Error: Unterminated block comment starting at line {}, column {}.", start_span.line, start_span.column)
+            }
+            LexerError::MalformedEscape { reason, span } => {
+                write!(f, "Lexical Error: Malformed escape sequence '{}' at line {}, column {}.", reason, span.line, span.column)
+            }
             // ... 未来可以添加更多 ...
         }
     }
 }
 
+impl LexerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerError::UnknownCharacter { .. } => "E0001",
+            LexerError::UnterminatedString { .. } => "E0002",
+            LexerError::MalformedNumberLiteral { .. } => "E0003",
+            LexerError::MalformedCharLiteral { .. } => "E0004",
+            LexerError::MalformedEscape { .. } => "E0005",
+            LexerError::UnterminatedBlockComment { .. } => "E0006",
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexerError::UnknownCharacter { char, span } => {
+                Diagnostic::error(format!("unknown character `{}`", char))
+                    .with_primary(*span, "this character cannot start any token")
+            }
+            LexerError::UnterminatedString { start_span } => {
+                Diagnostic::error("unterminated string literal")
+                    .with_primary(*start_span, "the string starts here but is never closed")
+                    .with_help("add a closing `\"`")
+            }
+            LexerError::MalformedNumberLiteral { reason, span } => {
+                Diagnostic::error("malformed number literal").with_primary(*span, reason.to_lowercase())
+            }
+            LexerError::MalformedCharLiteral { span } => {
+                Diagnostic::error("malformed character literal")
+                    .with_primary(*span, "a character literal must contain exactly one character")
+                    .with_help("if you meant to write a string, use double quotes: `\"...\"`")
+            }
+            LexerError::MalformedEscape { reason, span } => {
+                Diagnostic::error("malformed escape sequence")
+                    .with_primary(*span, reason.to_lowercase())
+                    .with_help("valid escapes are `\\n`, `\\t`, `\\r`, `\\\\`, `\\\"`, `\\'`, `\\0` and `\\u{...}`")
+            }
+            LexerError::UnterminatedBlockComment { start_span } => {
+                Diagnostic::error("unterminated block comment")
+                    .with_primary(*start_span, "the comment starts here but is never closed")
+                    .with_help("add a closing `*/`; block comments nest, so every `/*` needs its own `*/`")
+            }
+        }
+    }
+}
+
 // --- 解析阶段的错误 ---
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParserError {
@@ -89,6 +277,8 @@ pub enum ParserError {
     /// "在解析 `a +` 时，发现代码意外结束了"
     UnexpectedEof {
         expected: String,
+        /// 文件末尾的位置
+        span: Span,
     },
 
     /// 用于赋值表达式，当 `=` 左边不是一个合法的赋值目标时。
@@ -105,10 +295,10 @@ impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParserError::UnexpectedToken { expected, found, span } => {
-                write!(f, "Syntax Error: Expected {}, but found {:?} at line {}, column {}.", expected, found, span.line, span.column)
+                write!(f, "Syntax Error: Expected {}, but found {} at line {}, column {}.", expected, found, span.line, span.column)
             }
-            ParserError::UnexpectedEof { expected } => {
-                write!(f, "Syntax Error: Unexpected end of file. Expected {}.", expected)
+            ParserError::UnexpectedEof { expected, span } => {
+                write!(f, "Syntax Error: Unexpected end of file at line {}, column {}. Expected {}.", span.line, span.column, expected)
             }
             ParserError::InvalidAssignmentTarget { span } => {
                 write!(f, "Syntax Error: Invalid assignment target at line {}, column {}. You can only assign to variables.", span.line, span.column)
@@ -118,11 +308,43 @@ impl fmt::Display for ParserError {
     }
 }
 
+impl ParserError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::UnexpectedToken { .. } => "E0051",
+            ParserError::UnexpectedEof { .. } => "E0052",
+            ParserError::InvalidAssignmentTarget { .. } => "E0053",
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            ParserError::UnexpectedToken { expected, found, span } => {
+                Diagnostic::error(format!("expected {}, found {}", expected, found))
+                    .with_primary(*span, format!("expected {}", expected))
+            }
+            ParserError::UnexpectedEof { expected, span } => {
+                Diagnostic::error("unexpected end of file")
+                    .with_primary(*span, format!("expected {}", expected))
+            }
+            ParserError::InvalidAssignmentTarget { span } => invalid_assignment_target(*span),
+        }
+    }
+}
+
+/// 语法和语义分析阶段共用的“非法赋值目标”诊断。
+fn invalid_assignment_target(span: Span) -> Diagnostic {
+    Diagnostic::error("invalid assignment target")
+        .with_primary(span, "cannot assign to this expression")
+        .with_help("only variables can appear on the left-hand side of `=`")
+}
+
 // --- NEW: 语义分析阶段的错误 ---
 #[derive(Debug, Clone, PartialEq)]
 pub enum SemanticError {
     /// 符号（变量、函数等）在当前作用域已被定义。
-    SymbolAlreadyDefined { name: String, span: Span },
+    /// `previous_span` 指向第一次定义的位置。
+    SymbolAlreadyDefined { name: String, span: Span, previous_span: Span },
 
     /// 尝试使用一个未定义的符号。
     SymbolNotFound { name: String, span: Span },
@@ -173,12 +395,65 @@ pub enum SemanticError {
         the_type: Type, // a more neutral name than 'found'
         span: Span,
     },
+
+    /// 对一个没有用 `~` 声明的变量或参数赋值。
+    /// `span` 指向赋值表达式，`declaration_span` 指向该变量的声明。
+    /// e.g., `x: i32 = 1; x = 2;`
+    CannotAssignToImmutable { name: String, span: Span, declaration_span: Span },
+
+    /// 在 `while` 循环中使用了带值的 `break`。只有 `loop` 表达式可以产生值。
+    /// e.g., `while x > 0 { break x; }`
+    BreakWithValueInWhile { span: Span },
+
+    /// 声明了返回类型的函数并非在所有路径上都返回了值。
+    /// `span` 指向函数体结尾的 `}`，`signature_span` 指向函数签名。
+    /// e.g., `f(x: i64) -> i64 { if x > 0 { ret 1 } }`
+    MissingReturn { name: String, return_type: Type, span: Span, signature_span: Span },
+
+    /// 整数字面量的值超出了它被推断出的类型的取值范围。
+    /// `value` 已经计入了字面量前面的负号。
+    /// e.g., `b: u8 = 300;`
+    LiteralOutOfRange { value: i128, the_type: Type, span: Span },
+
+    /// `as` 不支持在这两个类型之间转换。`span` 指向整个转换表达式。
+    /// e.g., `true as f64`, `1.5 as bool`
+    InvalidCast { from: Type, to: Type, span: Span },
+
+    /// 调用重载的内置函数时，没有任何一个重载接受这些实参的类型。
+    /// `candidates` 是所有重载的函数类型，`span` 指向整个调用表达式。
+    /// e.g., `print(1, 2)`, `print(x)`（`x: i128`）
+    NoMatchingOverload { name: String, arg_types: Vec<Type>, candidates: Vec<Type>, span: Span },
+
+    /// 省略了类型的变量声明无法从初始值推断出类型，因为初始值没有可以存入变量的值。
+    /// `span` 指向初始值。
+    /// e.g., `x := main();`（`void`），`f := println;`（函数）
+    CannotInferType { name: String, found: Type, span: Span },
+
+    /// 读取了一个声明时没有初始值、且并非在所有路径上都已被赋值的变量。
+    /// `span` 指向这次读取，`declaration_span` 指向变量的声明。
+    /// e.g., `x: i32; if c { x = 1 }; println(x);`
+    UseOfUninitialized { name: String, span: Span, declaration_span: Span },
+
+    /// 声明时没有初始值的不可变变量可能被赋值了不止一次。
+    /// `span` 指向多出来的那次赋值，`declaration_span` 指向变量的声明。
+    /// e.g., `x: i32; x = 1; x = 2;`
+    ImmutableAssignedTwice { name: String, span: Span, declaration_span: Span },
+
+    /// 定义了一个与内置函数所用的 C 运行时函数同名的函数（见 `prelude::RUNTIME_FUNCTIONS`）。
+    /// `span` 指向函数签名。
+    /// e.g., `printf(s: str) { ... }`
+    ReservedFunctionName { name: String, span: Span },
+
+    /// 变量的类型标注是一个不能存放在变量中的类型（见 `Type::is_storable`）。
+    /// `span` 指向整个变量声明。
+    /// e.g., `x: void;`
+    InvalidVariableType { name: String, found: Type, span: Span },
 }
 /// 为SemanticError实现方便的打印trait
 impl fmt::Display for SemanticError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            SemanticError::SymbolAlreadyDefined { name, span } => {
+            SemanticError::SymbolAlreadyDefined { name, span, .. } => {
                 write!(f, "Semantic Error: Symbol '{}' is already defined in this scope. (at line {})", name, span.line)
             }
             SemanticError::SymbolNotFound { name, span } => {
@@ -208,6 +483,319 @@ impl fmt::Display for SemanticError {
             SemanticError::InvalidOperatorForType { operator, the_type, span } => {
                 write!(f, "Semantic Error: Operator '{}' cannot be applied to type '{}' at line {}.", operator, the_type, span.line)
             }
+            SemanticError::CannotAssignToImmutable { name, span, .. } => {
+                write!(f, "Semantic Error: Cannot assign to immutable variable '{}' at line {}.", name, span.line)
+            }
+            SemanticError::BreakWithValueInWhile { span } => {
+                write!(f, "Semantic Error: 'break' with a value is only allowed inside 'loop' (at line {}).", span.line)
+            }
+            SemanticError::MissingReturn { name, return_type, span, .. } => {
+                write!(f, "Semantic Error: Function '{}' must return a value of type '{}' on all code paths (at line {}).", name, return_type, span.line)
+            }
+            SemanticError::NoMatchingOverload { name, arg_types, span, .. } => {
+                let args = arg_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, "Semantic Error: No overload of '{}' accepts arguments ({}) at line {}.", name, args, span.line)
+            }
+            SemanticError::InvalidCast { from, to, span } => {
+                write!(f, "Semantic Error: Cannot cast type '{}' to '{}' at line {}.", from, to, span.line)
+            }
+            SemanticError::LiteralOutOfRange { value, the_type, span } => {
+                write!(f, "Semantic Error: Literal {} is out of range for type '{}' at line {}.", value, the_type, span.line)
+            }
+            SemanticError::CannotInferType { name, found, span } => {
+                write!(f, "Semantic Error: Cannot infer the type of '{}' from a value of type '{}' at line {}.", name, found, span.line)
+            }
+            SemanticError::UseOfUninitialized { name, span, .. } => {
+                write!(f, "Semantic Error: Use of possibly-uninitialized variable '{}' at line {}.", name, span.line)
+            }
+            SemanticError::ImmutableAssignedTwice { name, span, .. } => {
+                write!(f, "Semantic Error: Cannot assign twice to immutable variable '{}' at line {}.", name, span.line)
+            }
+            SemanticError::ReservedFunctionName { name, span } => {
+                write!(f, "Semantic Error: The function name '{}' is reserved for the C runtime (at line {}).", name, span.line)
+            }
+            SemanticError::InvalidVariableType { name, found, span } => {
+                write!(f, "Semantic Error: Variable '{}' cannot have type '{}' at line {}.", name, found, span.line)
+            }
+        }
+    }
+}
+
+impl SemanticError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticError::TypeMismatch { .. } => "E0101",
+            SemanticError::SymbolNotFound { .. } => "E0102",
+            SemanticError::SymbolAlreadyDefined { .. } => "E0103",
+            SemanticError::ConditionNotBoolean { .. } => "E0104",
+            SemanticError::IllegalBreak { .. } => "E0105",
+            SemanticError::IllegalContinue { .. } => "E0106",
+            SemanticError::NotAFunction { .. } => "E0107",
+            SemanticError::ArityMismatch { .. } => "E0108",
+            SemanticError::InvalidAssignmentTarget { .. } => "E0109",
+            SemanticError::InvalidOperatorForType { .. } => "E0110",
+            SemanticError::CannotAssignToImmutable { .. } => "E0111",
+            SemanticError::BreakWithValueInWhile { .. } => "E0112",
+            SemanticError::MissingReturn { .. } => "E0113",
+            SemanticError::LiteralOutOfRange { .. } => "E0114",
+            SemanticError::InvalidCast { .. } => "E0115",
+            SemanticError::NoMatchingOverload { .. } => "E0116",
+            SemanticError::CannotInferType { .. } => "E0117",
+            SemanticError::UseOfUninitialized { .. } => "E0118",
+            SemanticError::ImmutableAssignedTwice { .. } => "E0119",
+            SemanticError::ReservedFunctionName { .. } => "E0120",
+            SemanticError::InvalidVariableType { .. } => "E0121",
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            SemanticError::SymbolAlreadyDefined { name, span, previous_span } => {
+                Diagnostic::error(format!("`{}` is defined multiple times", name))
+                    .with_primary(*span, format!("`{}` redefined here", name))
+                    .with_secondary(*previous_span, format!("previous definition of `{}` here", name))
+            }
+            SemanticError::SymbolNotFound { name, span } => {
+                Diagnostic::error(format!("cannot find `{}` in this scope", name))
+                    .with_primary(*span, "not found in this scope")
+            }
+            SemanticError::TypeMismatch { expected, found, span } => {
+                let diag = Diagnostic::error("mismatched types")
+                    .with_primary(*span, format!("expected `{}`, found `{}`", expected, found));
+                // 数字类型之间不会隐式转换，提示用户使用 `as`
+                if expected.is_numeric() && found.is_numeric() {
+                    diag.with_help(format!("you can convert a `{}` to a `{}` with `as {}`", found, expected, expected))
+                } else {
+                    diag
+                }
+            }
+            SemanticError::ConditionNotBoolean { found, span } => {
+                Diagnostic::error("condition is not a boolean")
+                    .with_primary(*span, format!("expected `bool`, found `{}`", found))
+                    .with_note("conditions of `if` and `while` must have type `bool`")
+            }
+            SemanticError::IllegalBreak { span } => {
+                Diagnostic::error("`break` outside of a loop")
+                    .with_primary(*span, "cannot `break` outside of a loop")
+            }
+            SemanticError::IllegalContinue { span } => {
+                Diagnostic::error("`continue` outside of a loop")
+                    .with_primary(*span, "cannot `continue` outside of a loop")
+            }
+            SemanticError::NotAFunction { found, span } => {
+                Diagnostic::error(format!("expected function, found `{}`", found))
+                    .with_primary(*span, "call expression requires a function")
+            }
+            SemanticError::ArityMismatch { expected, found, span } => {
+                Diagnostic::error(format!(
+                    "this function takes {} argument{} but {} {} supplied",
+                    expected,
+                    if *expected == 1 { "" } else { "s" },
+                    found,
+                    if *found == 1 { "was" } else { "were" },
+                ))
+                .with_primary(*span, format!("expected {}, found {}", expected, found))
+            }
+            SemanticError::InvalidAssignmentTarget { span } => invalid_assignment_target(*span),
+            SemanticError::InvalidOperatorForType { operator, the_type, span } => {
+                Diagnostic::error(format!("cannot apply operator `{}` to type `{}`", operator, the_type))
+                    .with_primary(*span, format!("this has type `{}`", the_type))
+            }
+            SemanticError::CannotAssignToImmutable { name, span, declaration_span } => {
+                Diagnostic::error(format!("cannot assign to immutable variable `{}`", name))
+                    .with_primary(*span, "cannot assign to immutable variable")
+                    .with_secondary(*declaration_span, format!("`{}` is declared here without `~`", name))
+                    .with_help(format!("consider making `{}` mutable by writing `~` before its type", name))
+            }
+            SemanticError::BreakWithValueInWhile { span } => {
+                Diagnostic::error("`break` with value from a `while` loop")
+                    .with_primary(*span, "can only break with a value inside `loop`")
+                    .with_help("use `break` on its own inside `while`, or rewrite the loop as `loop { ... }`")
+            }
+            SemanticError::MissingReturn { name, return_type, span, signature_span } => {
+                Diagnostic::error(format!("function `{}` does not return a value on all paths", name))
+                    .with_primary(*span, format!("expected a value of type `{}` before the end of the function", return_type))
+                    .with_secondary(*signature_span, format!("declared to return `{}` here", return_type))
+                    .with_help(format!("add a `ret` statement, or end the body with an expression of type `{}`", return_type))
+            }
+            SemanticError::LiteralOutOfRange { value, the_type, span } => {
+                let mut diag = Diagnostic::error(format!("literal out of range for `{}`", the_type))
+                    .with_primary(*span, format!("`{}` does not fit into `{}`", value, the_type));
+                if let Some((min, max)) = the_type.integer_range() {
+                    diag = diag.with_note(format!("the range of `{}` is `{}..={}`", the_type, min, max));
+                }
+                diag
+            }
+            SemanticError::InvalidCast { from, to, span } => {
+                Diagnostic::error(format!("cannot cast `{}` as `{}`", from, to))
+                    .with_primary(*span, format!("invalid cast from `{}` to `{}`", from, to))
+                    .with_note("`as` converts between numeric types, from `bool` or `char` to an integer, from `u8` or `u32` to `char`, and between pointers and integers")
+            }
+            SemanticError::NoMatchingOverload { name, arg_types, candidates, span } => {
+                let args = arg_types.iter().map(|t| format!("`{}`", t)).collect::<Vec<_>>().join(", ");
+                let found = match arg_types.len() {
+                    0 => "no arguments".to_string(),
+                    1 => format!("an argument of type {}", args),
+                    _ => format!("arguments of type {}", args),
+                };
+                // 列出所有重载的参数列表，e.g., `print(str)`
+                let signatures = candidates
+                    .iter()
+                    .filter_map(|candidate| match candidate {
+                        Type::Function { params, .. } => {
+                            let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+                            Some(format!("`{}({})`", name, params))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Diagnostic::error(format!("no overload of `{}` accepts {}", name, found))
+                    .with_primary(*span, "no matching overload")
+                    .with_note(format!("the available overloads are: {}", signatures))
+            }
+            SemanticError::CannotInferType { name, found, span } => {
+                let diag = Diagnostic::error(format!("cannot infer the type of `{}`", name));
+                match found {
+                    Type::Void => diag
+                        .with_primary(*span, "this expression does not produce a value")
+                        .with_note("a function without `->` in its signature returns nothing"),
+                    Type::Function { .. } | Type::Overloaded(_) => diag
+                        .with_primary(*span, "functions cannot be stored in variables")
+                        .with_help("call the function and store its result instead"),
+                    _ => diag.with_primary(*span, format!("this has type `{}`", found)),
+                }
+            }
+            SemanticError::UseOfUninitialized { name, span, declaration_span } => {
+                Diagnostic::error(format!("use of possibly-uninitialized variable `{}`", name))
+                    .with_primary(*span, format!("`{}` is read here, but it may not have been assigned yet", name))
+                    .with_secondary(*declaration_span, format!("`{}` is declared here without an initial value", name))
+                    .with_help(format!("assign a value to `{}` on every path before reading it, or give it an initial value", name))
+            }
+            SemanticError::ImmutableAssignedTwice { name, span, declaration_span } => {
+                Diagnostic::error(format!("cannot assign twice to immutable variable `{}`", name))
+                    .with_primary(*span, "cannot assign twice to immutable variable")
+                    .with_secondary(*declaration_span, format!("`{}` is declared here without `~`", name))
+                    .with_help(format!("consider making `{}` mutable by writing `~` before its type", name))
+            }
+            SemanticError::ReservedFunctionName { name, span } => {
+                Diagnostic::error(format!("the function name `{}` is reserved", name))
+                    .with_primary(*span, "cannot define a function with this name")
+                    .with_note(format!("built-in functions are implemented by calling `{}` from the C runtime", name))
+                    .with_help("choose a different name for this function")
+            }
+            SemanticError::InvalidVariableType { name, found, span } => {
+                let diag = Diagnostic::error(format!("variable `{}` cannot have type `{}`", name, found))
+                    .with_primary(*span, format!("`{}` has no values that can be stored in a variable", found));
+                if *found == Type::Void {
+                    diag.with_note("`void` is only used as the return type of a function that returns nothing")
+                } else {
+                    diag
+                }
+            }
+        }
+    }
+}
+
+// --- 语义分析阶段的警告 ---
+
+/// 所有 lint 的名称，用于 `-W` / `-A` / `-D` 参数。
+/// `warnings` 是一个特殊的组，代表所有处于警告级别的 lint。
+pub const LINT_NAMES: &[&str] = &["unused_variables", "unreachable_code"];
+
+/// 语义分析发现的、不会导致编译失败的问题。
+///
+/// 每个警告都属于一个 lint，用户可以通过命令行把它关闭 (`-A`)
+/// 或升级为错误 (`-D`)。
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticWarning {
+    /// 声明了一个变量（或参数）但从未读取过它。
+    /// 以下划线开头的名字 (`_x`) 不会触发此警告。
+    UnusedVariable { name: String, span: Span },
+
+    /// `ret` / `break` / `continue` 之后的语句永远不会被执行。
+    /// `span` 指向第一条不可达的语句，`cause_span` 指向导致它不可达的语句。
+    UnreachableCode { span: Span, cause_span: Span },
+}
+
+impl SemanticWarning {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticWarning::UnusedVariable { .. } => "W0001",
+            SemanticWarning::UnreachableCode { .. } => "W0002",
+        }
+    }
+
+    /// 该警告所属的 lint 名称。
+    pub fn lint_name(&self) -> &'static str {
+        match self {
+            SemanticWarning::UnusedVariable { .. } => "unused_variables",
+            SemanticWarning::UnreachableCode { .. } => "unreachable_code",
+        }
+    }
+
+    /// 主标注的位置，用于按源码顺序排列警告。
+    pub fn span(&self) -> Span {
+        match self {
+            SemanticWarning::UnusedVariable { span, .. } | SemanticWarning::UnreachableCode { span, .. } => *span,
+        }
+    }
+
+    /// 转换为 `Diagnostic`。严重程度由调用者根据 lint 级别决定。
+    pub fn to_diagnostic(&self, severity: Severity) -> Diagnostic {
+        let diag = match self {
+            SemanticWarning::UnusedVariable { name, span } => {
+                Diagnostic::new(severity, format!("unused variable: `{}`", name))
+                    .with_primary(*span, "this variable is never read")
+                    .with_help(format!("if this is intentional, prefix it with an underscore: `_{}`", name))
+            }
+            SemanticWarning::UnreachableCode { span, cause_span } => {
+                Diagnostic::new(severity, "unreachable statement")
+                    .with_primary(*span, "unreachable statement")
+                    .with_secondary(*cause_span, "any code following this statement is unreachable")
+            }
+        };
+        diag.with_code(self.code())
+    }
+}
+
+/// 一个 lint 的级别，由 `-A` / `-W` / `-D` 控制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// 不报告。
+    Allow,
+    /// 作为警告报告（默认）。
+    Warn,
+    /// 作为错误报告，并使编译失败。
+    Deny,
+}
+
+/// 命令行中给出的所有 lint 级别设置，按出现顺序保存。
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    rules: Vec<(String, LintLevel)>,
+}
+
+impl LintLevels {
+    /// 记录一条设置。`name` 必须是 `LINT_NAMES` 之一或 `warnings`。
+    pub fn set(&mut self, name: String, level: LintLevel) {
+        self.rules.push((name, level));
+    }
+
+    /// 计算某个 lint 最终的级别，以及决定该级别的命令行参数名。
+    ///
+    /// 与 rustc 一致：先看针对该 lint 本身的设置（后出现的覆盖先出现的，默认 `Warn`），
+    /// 如果结果仍是 `Warn`，再应用 `warnings` 组的设置。因此 `-A unused_variables -D warnings`
+    /// 不会把已关闭的 lint 重新打开。
+    pub fn level<'a>(&'a self, lint: &'a str) -> (LintLevel, &'a str) {
+        let last_rule = |name: &str| self.rules.iter().rev().find(|(n, _)| n == name);
+
+        match last_rule(lint) {
+            Some((name, level)) if *level != LintLevel::Warn => (*level, name.as_str()),
+            _ => match last_rule("warnings") {
+                Some((name, level)) => (*level, name.as_str()),
+                None => (LintLevel::Warn, lint),
+            },
         }
     }
 }
@@ -226,6 +814,12 @@ pub enum CodegenError {
     /// 例如，`5 = x;`
     InvalidLValue,
 
+    /// 初始化本机目标、创建 `TargetMachine` 或写出目标文件时失败。
+    Target(String),
+
+    /// 调用系统链接器生成可执行文件时失败。
+    Linker(String),
+
     /// 用于包装一个简单的、基于字符串的错误信息。
     /// 在某些不值得为其创建一个专属错误类型的场景下非常有用。
     Message(String),
@@ -240,10 +834,40 @@ impl fmt::Display for CodegenError {
             CodegenError::Backend(e) => write!(f, "LLVM Backend Error: {}", e),
             CodegenError::SymbolNotFound(name) => write!(f, "Codegen Error: Symbol '{}' not found.", name),
             CodegenError::InvalidLValue => write!(f, "Codegen Error: Expression is not a valid L-Value for assignment."),
+            CodegenError::Target(msg) => write!(f, "Target Error: {}", msg),
+            CodegenError::Linker(msg) => write!(f, "Linker Error: {}", msg),
             CodegenError::Message(msg) => write!(f, "Codegen Error: {}", msg),
         }
     }
 }
+impl CodegenError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CodegenError::Backend(_) => "E0201",
+            CodegenError::SymbolNotFound(_) => "E0202",
+            CodegenError::InvalidLValue => "E0203",
+            CodegenError::Target(_) => "E0204",
+            CodegenError::Linker(_) => "E0205",
+            CodegenError::Message(_) => "E0206",
+        }
+    }
+
+    /// 代码生成阶段的错误没有源码位置，因此诊断中只有主消息。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            CodegenError::Backend(e) => Diagnostic::error(format!("LLVM backend error: {}", e)),
+            CodegenError::SymbolNotFound(name) => {
+                Diagnostic::error(format!("symbol `{}` not found during code generation", name))
+                    .with_note("this is a compiler bug: semantic analysis should have rejected the program")
+            }
+            CodegenError::InvalidLValue => Diagnostic::error("expression is not a valid assignment target"),
+            CodegenError::Target(msg) => Diagnostic::error(format!("target error: {}", msg)),
+            CodegenError::Linker(msg) => Diagnostic::error(format!("linking failed: {}", msg)),
+            CodegenError::Message(msg) => Diagnostic::error(msg.clone()),
+        }
+    }
+}
+
 /// 允许 `BuilderError` 自动转换为 `CodegenError`。
 ///
 /// 这样，在返回 `Result<_, CodegenError>` 的函数中，
@@ -271,10 +895,76 @@ impl From<CodegenError> for CompilerError {
 
 // --- 位置信息 ---
 // Span 代表了源代码中的一个范围，(Copy trait 让它在函数间传递更方便)
-#[derive(Debug, Clone, PartialEq, Copy, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy, Default)]
 pub struct Span {
     pub line: u32,
     pub column: u32,
     pub start_byte: usize, // 在源文件中的起始字节位置
     pub end_byte: usize,   // 在源文件中的结束字节位置
-}
\ No newline at end of file
+}
+
+impl Span {
+    /// 合并两个 Span，得到从 `self` 开头一直延伸到 `end` 结尾的范围。
+    /// e.g., `a.to(b)` 用于从 `a + b` 的左右操作数得到整个表达式的范围。
+    pub fn to(self, end: Span) -> Span {
+        Span { end_byte: end.end_byte, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(rules: &[(&str, LintLevel)]) -> LintLevels {
+        let mut levels = LintLevels::default();
+        for (name, level) in rules {
+            levels.set(name.to_string(), *level);
+        }
+        levels
+    }
+
+    #[test]
+    fn lints_warn_by_default() {
+        assert_eq!(levels(&[]).level("unused_variables"), (LintLevel::Warn, "unused_variables"));
+    }
+
+    #[test]
+    fn later_flag_wins() {
+        let lints = levels(&[("unused_variables", LintLevel::Deny), ("unused_variables", LintLevel::Allow)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Allow, "unused_variables"));
+        let lints = levels(&[("warnings", LintLevel::Allow), ("warnings", LintLevel::Deny)]);
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Deny, "warnings"));
+    }
+
+    #[test]
+    fn rules_only_affect_their_own_lint() {
+        let lints = levels(&[("unused_variables", LintLevel::Allow)]);
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Warn, "unreachable_code"));
+    }
+
+    #[test]
+    fn deny_warnings_applies_to_every_lint() {
+        let lints = levels(&[("warnings", LintLevel::Deny)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Deny, "warnings"));
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Deny, "warnings"));
+    }
+
+    #[test]
+    fn specific_lint_overrides_warnings_regardless_of_order() {
+        // 与 rustc 相同：`-A unused_variables -D warnings` 不会重新打开已关闭的 lint
+        let lints = levels(&[("unused_variables", LintLevel::Allow), ("warnings", LintLevel::Deny)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Allow, "unused_variables"));
+        assert_eq!(lints.level("unreachable_code"), (LintLevel::Deny, "warnings"));
+
+        let lints = levels(&[("warnings", LintLevel::Deny), ("unused_variables", LintLevel::Allow)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Allow, "unused_variables"));
+    }
+
+    #[test]
+    fn explicit_warn_still_follows_the_warnings_group() {
+        let lints = levels(&[("unused_variables", LintLevel::Warn), ("warnings", LintLevel::Deny)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Deny, "warnings"));
+        let lints = levels(&[("warnings", LintLevel::Allow), ("unused_variables", LintLevel::Deny)]);
+        assert_eq!(lints.level("unused_variables"), (LintLevel::Deny, "unused_variables"));
+    }
+}
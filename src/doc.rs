@@ -0,0 +1,272 @@
+// file: src/doc.rs
+
+//! 文档生成器，实现 `tipyc doc`。
+//!
+//! 它从 AST 中读取 `///` 和 `//!` 文档注释，从语义分析器的符号表中读取
+//! 每个函数解析后的 `Type::Function`，生成一份静态的 HTML 或 Markdown 文档。
+
+use std::fmt::Write;
+
+use crate::ast::{Program, TopLevelStatement};
+use crate::scope::SymbolTable;
+use crate::types::Type;
+
+/// `--doc-format` 选项：`tipyc doc` 的输出格式。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocFormat {
+    /// 一个独立的 HTML 页面（默认）。
+    Html,
+    /// 一个 Markdown 文件，适合直接放进仓库或 wiki。
+    Markdown,
+}
+
+impl DocFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "html" => Some(DocFormat::Html),
+            "markdown" | "md" => Some(DocFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    /// 该格式默认使用的文件扩展名。
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Html => "html",
+            DocFormat::Markdown => "md",
+        }
+    }
+}
+
+/// 一个函数需要写进文档的全部信息。
+struct FunctionDoc<'a> {
+    name: &'a str,
+    /// 参数名与它解析后的类型
+    params: Vec<(&'a str, Type)>,
+    ret: Type,
+    /// 去掉了注释标记的文档文本，可能为空
+    docs: String,
+}
+
+impl FunctionDoc<'_> {
+    /// 与源码写法相同的函数签名，e.g., `add(a: i32, b: i32) -> i32`。返回 `void` 时省略箭头。
+    fn signature(&self) -> String {
+        let params = self.params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect::<Vec<_>>().join(", ");
+        match self.ret {
+            Type::Void => format!("{}({})", self.name, params),
+            _ => format!("{}({}) -> {}", self.name, params, self.ret),
+        }
+    }
+}
+
+/// 为一个已经通过语义分析的程序生成文档。
+///
+/// `symbols` 是分析结束后的符号表，其中的全局作用域保存着每个函数的签名。
+/// `title` 是文档的标题，通常是源文件名（不含扩展名）。
+pub fn render(program: &Program, symbols: &SymbolTable, title: &str, format: DocFormat) -> String {
+    let functions = collect_functions(program, symbols);
+    let module_docs = join_doc_lines(&program.docs);
+    match format {
+        DocFormat::Html => render_html(title, &module_docs, &functions),
+        DocFormat::Markdown => render_markdown(title, &module_docs, &functions),
+    }
+}
+
+/// 按源码顺序收集所有函数的文档信息。
+fn collect_functions<'a>(program: &'a Program, symbols: &SymbolTable) -> Vec<FunctionDoc<'a>> {
+    let mut functions = Vec::new();
+    for toplevel_stmt in &program.body {
+        let TopLevelStatement::Function(func_decl) = toplevel_stmt;
+        // 分析成功后每个函数都已注册到全局作用域，这里只是防御性的检查
+        let Some(Type::Function { params, ret }) = symbols.lookup(&func_decl.name).map(|s| &s.symbol_type) else {
+            continue;
+        };
+        functions.push(FunctionDoc {
+            name: &func_decl.name,
+            params: func_decl.params.iter().map(|p| p.name.as_str()).zip(params.iter().cloned()).collect(),
+            ret: (**ret).clone(),
+            docs: join_doc_lines(&func_decl.docs),
+        });
+    }
+    functions
+}
+
+/// 把文档注释的各行拼成一段文本。与 rustdoc 相同，每行开头的一个空格被视为注释标记的一部分。
+fn join_doc_lines(lines: &[String]) -> String {
+    let lines: Vec<&str> = lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect();
+    lines.join("\n").trim().to_string()
+}
+
+// --- Markdown ---
+
+fn render_markdown(title: &str, module_docs: &str, functions: &[FunctionDoc]) -> String {
+    let mut out = String::new();
+    // 向 String 写入不会失败，所以忽略 `writeln!` 的返回值
+    let _ = writeln!(out, "# {}\n", title);
+    if !module_docs.is_empty() {
+        let _ = writeln!(out, "{}\n", module_docs);
+    }
+    let _ = writeln!(out, "## Functions\n");
+    if functions.is_empty() {
+        let _ = writeln!(out, "This file does not declare any functions.");
+    }
+
+    for func in functions {
+        let _ = writeln!(out, "### `{}`\n", func.name);
+        let _ = writeln!(out, "```tipy\n{}\n```\n", func.signature());
+        if !func.docs.is_empty() {
+            let _ = writeln!(out, "{}\n", func.docs);
+        }
+        if !func.params.is_empty() {
+            let _ = writeln!(out, "| Parameter | Type |\n| --- | --- |");
+            for (name, ty) in &func.params {
+                let _ = writeln!(out, "| `{}` | `{}` |", name, ty);
+            }
+            out.push('\n');
+        }
+        let _ = writeln!(out, "**Returns:** `{}`\n", func.ret);
+    }
+
+    // 只保留一个结尾换行
+    let len = out.trim_end().len();
+    out.truncate(len);
+    out.push('\n');
+    out
+}
+
+// --- HTML ---
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+code, pre { font-family: monospace; background: #f4f4f4; border-radius: 3px; }
+code { padding: 0 0.2em; }
+pre { padding: 0.6em; overflow-x: auto; }
+section { border-top: 1px solid #ddd; margin-top: 1.5em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ddd; padding: 0.2em 0.6em; text-align: left; }";
+
+fn render_html(title: &str, module_docs: &str, functions: &[FunctionDoc]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>");
+    let _ = writeln!(out, "<meta charset=\"utf-8\">\n<title>{} - Tipy documentation</title>", escape_html(title));
+    let _ = writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(out, "<h1>{}</h1>", escape_html(title));
+    if !module_docs.is_empty() {
+        out.push_str(&doc_text_to_html(module_docs));
+    }
+
+    let _ = writeln!(out, "<h2>Functions</h2>");
+    if functions.is_empty() {
+        let _ = writeln!(out, "<p>This file does not declare any functions.</p>");
+    } else {
+        // 目录
+        let _ = writeln!(out, "<ul>");
+        for func in functions {
+            let _ = writeln!(out, "<li><a href=\"#fn.{0}\"><code>{0}</code></a></li>", escape_html(func.name));
+        }
+        let _ = writeln!(out, "</ul>");
+    }
+
+    for func in functions {
+        let _ = writeln!(out, "<section id=\"fn.{}\">", escape_html(func.name));
+        let _ = writeln!(out, "<h3><code>{}</code></h3>", escape_html(&func.signature()));
+        if !func.docs.is_empty() {
+            out.push_str(&doc_text_to_html(&func.docs));
+        }
+        if !func.params.is_empty() {
+            let _ = writeln!(out, "<table>\n<tr><th>Parameter</th><th>Type</th></tr>");
+            for (name, ty) in &func.params {
+                let _ = writeln!(
+                    out,
+                    "<tr><td><code>{}</code></td><td><code>{}</code></td></tr>",
+                    escape_html(name),
+                    escape_html(&ty.to_string())
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+        let _ = writeln!(out, "<p><strong>Returns:</strong> <code>{}</code></p>", escape_html(&func.ret.to_string()));
+        let _ = writeln!(out, "</section>");
+    }
+
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+/// 将文档文本转换为 HTML。
+///
+/// 这里只支持文档注释中最常用的几种 Markdown 写法：空行分隔的段落、
+/// 用 ``` 围起来的代码块，以及用反引号包裹的行内代码。其余文本按原样转义输出。
+fn doc_text_to_html(text: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        if let Some(code) = code_block.as_mut() {
+            if line.trim_start().starts_with("```") {
+                let _ = writeln!(out, "<pre><code>{}</code></pre>", escape_html(&code.join("\n")));
+                code_block = None;
+            } else {
+                code.push(line);
+            }
+        } else if line.trim_start().starts_with("```") {
+            flush_paragraph(&mut out, &mut paragraph);
+            code_block = Some(Vec::new());
+        } else if line.trim().is_empty() {
+            flush_paragraph(&mut out, &mut paragraph);
+        } else {
+            paragraph.push(line.trim());
+        }
+    }
+
+    // 没有闭合的代码块一直延续到文档末尾
+    if let Some(code) = code_block {
+        let _ = writeln!(out, "<pre><code>{}</code></pre>", escape_html(&code.join("\n")));
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    out
+}
+
+/// 把收集到的段落行输出为一个 `<p>`，并清空 `paragraph`。
+fn flush_paragraph(out: &mut String, paragraph: &mut Vec<&str>) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let text = paragraph.join(" ");
+    paragraph.clear();
+
+    // 反引号把文本切成交替的两种片段：奇数位置的片段是行内代码。
+    // 反引号数量为奇数时，最后一个反引号之后的文本按普通文本处理
+    let pieces: Vec<&str> = text.split('`').collect();
+    let unmatched = text.matches('`').count() % 2 == 1;
+    let closed = if unmatched { pieces.len() - 1 } else { pieces.len() };
+    let mut html = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        if i >= closed {
+            html.push('`');
+            html.push_str(&escape_html(piece));
+        } else if i % 2 == 1 {
+            let _ = write!(html, "<code>{}</code>", escape_html(piece));
+        } else {
+            html.push_str(&escape_html(piece));
+        }
+    }
+    let _ = writeln!(out, "<p>{}</p>", html);
+}
+
+/// 转义 HTML 中有特殊含义的字符。
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
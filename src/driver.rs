@@ -0,0 +1,280 @@
+// file: src/driver.rs
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{self, ExitCode};
+
+use inkwell::context::Context;
+
+use crate::analyzer::SemanticAnalyzer;
+use crate::ast::Program;
+use crate::cli::{Command, EmitKind, Options};
+use crate::codegen::{link_executable, CodeGen};
+use crate::diagnostics::{CodegenError, CompilerError, Diagnostic, LintLevel, LintLevels, SemanticWarning, Severity};
+use crate::doc;
+use crate::emitter::{Emitter, ErrorFormat};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::Token;
+
+/// 按照 `options` 运行一次完整的编译流程。
+///
+/// 各个阶段依次执行：词法 -> 语法 -> 语义 -> 代码生成。
+/// 任何一个阶段收集到错误，都会打印所有错误并以非零退出码结束，
+/// 后续阶段不会再运行。警告会被打印，但只有被 `-D` 升级为错误时才会使编译失败。
+pub fn run(options: &Options) -> ExitCode {
+    let source = match fs::read_to_string(&options.input) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("error: cannot read `{}`: {}", options.input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let file_name = options.input.display().to_string();
+    let mut reporter = Reporter::new(options, &file_name, &source);
+
+    if let Err(errors) = compile(options, &source, &mut reporter) {
+        for err in &errors {
+            reporter.emit(&err.to_diagnostic());
+        }
+    }
+    reporter.finish()
+}
+
+/// 编译流程的主体。成功时返回 `Ok(())`，否则返回第一个失败阶段收集到的所有错误。
+///
+/// 警告在产生它们的阶段结束时直接交给 `reporter` 打印。
+fn compile(options: &Options, source: &str, reporter: &mut Reporter) -> Result<(), Vec<CompilerError>> {
+    // --- 1. 词法分析 (仅在 `--emit=tokens` 时单独运行) ---
+    if options.emit.contains(&EmitKind::Tokens) {
+        dump_tokens(source)?;
+    }
+
+    // --- 2. 语法分析 ---
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if !parser.errors.is_empty() {
+        return Err(parser.errors);
+    }
+    if options.emit.contains(&EmitKind::Ast) {
+        println!("{:#?}", program);
+    }
+
+    // --- 3. 语义分析 ---
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze(&program);
+    reporter.report_warnings(&analyzer.warnings, &options.lints);
+    if !analyzer.errors.is_empty() {
+        return Err(analyzer.errors);
+    }
+    if reporter.error_count > 0 {
+        // 被 `-D` 升级为错误的警告已经打印过了，这里只需停止编译
+        return Err(Vec::new());
+    }
+
+    if options.command == Command::Doc {
+        return write_docs(options, &program, &analyzer).map_err(|e| vec![e.into()]);
+    }
+
+    if options.command == Command::Check || !options.emit.iter().any(|kind| kind.needs_codegen()) {
+        return Ok(());
+    }
+
+    // --- 4. 代码生成 ---
+    let context = Context::create();
+    let module_name = options.input.file_stem().and_then(|s| s.to_str()).unwrap_or("tipy_module");
+    let mut codegen = CodeGen::new(&context, module_name).map_err(|e| vec![e.into()])?;
+    codegen.compile(&program, &analyzer).map_err(|e| vec![e.into()])?;
+
+    for &kind in options.emit.iter().filter(|kind| kind.needs_codegen()) {
+        let path = options.output_path(kind);
+        let result = match kind {
+            // `-o -` 表示把 IR 打印到标准输出
+            EmitKind::LlvmIr if path.as_os_str() == "-" => {
+                print!("{}", codegen.ir_to_string());
+                Ok(())
+            }
+            EmitKind::LlvmIr => codegen.save_ir_to_file(&path),
+            EmitKind::Asm => codegen.write_assembly_file(&path),
+            EmitKind::Obj => codegen.write_object_file(&path),
+            EmitKind::Exe => build_executable(&codegen, options, &path),
+            EmitKind::Tokens | EmitKind::Ast => Ok(()),
+        };
+        result.map_err(|e| vec![e.into()])?;
+    }
+
+    Ok(())
+}
+
+/// 生成目标文件并调用系统链接器，得到最终的可执行文件。
+///
+/// 如果用户同时请求了 `--emit=obj`，就直接链接那个目标文件；
+/// 否则在临时目录中生成一个中间目标文件，链接完成后删除它。
+fn build_executable(codegen: &CodeGen, options: &Options, exe_path: &Path) -> Result<(), CodegenError> {
+    if options.emit.contains(&EmitKind::Obj) {
+        let obj_path = options.output_path(EmitKind::Obj);
+        codegen.write_object_file(&obj_path)?;
+        return link_executable(&obj_path, exe_path);
+    }
+
+    let stem = options.input.file_stem().and_then(|s| s.to_str()).unwrap_or("tipy");
+    let obj_path = env::temp_dir().join(format!("tipyc-{}-{}.o", process::id(), stem));
+    codegen.write_object_file(&obj_path)?;
+    let result = link_executable(&obj_path, exe_path);
+    // 临时文件删除失败不影响编译结果
+    let _ = fs::remove_file(&obj_path);
+    result
+}
+
+/// 生成 `tipyc doc` 的文档，写入 `-o` 指定的文件（`-o -` 表示标准输出）或输入文件旁边。
+fn write_docs(options: &Options, program: &Program, analyzer: &SemanticAnalyzer) -> Result<(), CodegenError> {
+    let title = options.input.file_stem().and_then(|s| s.to_str()).unwrap_or("tipy");
+    let text = doc::render(program, &analyzer.symbol_table, title, options.doc_format);
+
+    let path = options.doc_output_path();
+    if path.as_os_str() == "-" {
+        print!("{}", text);
+        return Ok(());
+    }
+    fs::write(&path, text)
+        .map_err(|e| CodegenError::Message(format!("Error writing documentation to `{}`: {}", path.display(), e)))
+}
+
+/// 运行词法分析器并把每个 Token 打印到标准输出，用于 `--emit=tokens`。
+fn dump_tokens(source: &str) -> Result<(), Vec<CompilerError>> {
+    let mut lexer = Lexer::new(source);
+    loop {
+        let spanned = lexer.next_token().map_err(|e| vec![e.into()])?;
+        println!("{}:{}\t{:?}", spanned.span.line, spanned.span.column, spanned.token);
+        if spanned.token == Token::Eof {
+            return Ok(());
+        }
+    }
+}
+
+/// 负责打印诊断信息，并统计错误和警告的数量。
+///
+/// - `human` 格式：每条诊断带源码片段，最后附上一行汇总。
+/// - `json` 格式：每条诊断一行 JSON，不打印汇总，方便工具逐行解析。
+struct Reporter<'a> {
+    emitter: Emitter<'a>,
+    format: ErrorFormat,
+    error_count: usize,
+    warning_count: usize,
+    /// 所有报告过的错误的错误码，用于最后的 `--explain` 提示。
+    error_codes: Vec<&'static str>,
+}
+
+impl<'a> Reporter<'a> {
+    fn new(options: &Options, file_name: &'a str, source: &'a str) -> Self {
+        Reporter {
+            emitter: Emitter::new(file_name, source, options.color.should_color()),
+            format: options.error_format,
+            error_count: 0,
+            warning_count: 0,
+            error_codes: Vec::new(),
+        }
+    }
+
+    /// 打印一条诊断并计数。
+    fn emit(&mut self, diag: &Diagnostic) {
+        match diag.severity {
+            Severity::Error => {
+                self.error_count += 1;
+                self.error_codes.extend(diag.code);
+            }
+            Severity::Warning => self.warning_count += 1,
+            // 提示不影响编译结果，也不计入汇总
+            Severity::Note => {}
+        }
+
+        match self.format {
+            ErrorFormat::Json => eprintln!("{}", self.emitter.render_json(diag)),
+            // 每条诊断之后空一行
+            ErrorFormat::Human => eprintln!("{}", self.emitter.render(diag)),
+        }
+    }
+
+    /// 按照 lint 级别打印语义分析产生的警告：`Allow` 的被忽略，`Deny` 的作为错误打印。
+    fn report_warnings(&mut self, warnings: &[SemanticWarning], lints: &LintLevels) {
+        let mut warnings: Vec<&SemanticWarning> = warnings.iter().collect();
+        warnings.sort_by_key(|w| w.span().start_byte);
+
+        for warning in warnings {
+            let lint = warning.lint_name();
+            let diag = match lints.level(lint) {
+                (LintLevel::Allow, _) => continue,
+                (LintLevel::Warn, _) => warning.to_diagnostic(Severity::Warning),
+                (LintLevel::Deny, source) if source == lint => warning
+                    .to_diagnostic(Severity::Error)
+                    .with_note(format!("requested on the command line with `-D {}`", lint)),
+                (LintLevel::Deny, source) => warning
+                    .to_diagnostic(Severity::Error)
+                    .with_note(format!("`-D {}` implied by `-D {}`", lint, source)),
+            };
+            self.emit(&diag);
+        }
+    }
+
+    /// 打印汇总信息，并根据是否出现过错误给出进程的退出码。
+    fn finish(&self) -> ExitCode {
+        let failed = self.error_count > 0;
+
+        let mut codes = self.error_codes.clone();
+        codes.sort_unstable();
+        codes.dedup();
+
+        if self.format == ErrorFormat::Json {
+            // JSON 格式没有汇总行，`--explain` 的提示作为一条独立的 note 输出，供编辑器等工具展示
+            let hint = match codes.as_slice() {
+                [] => None,
+                [code] => Some(format!("for more information about this error, try `tipyc --explain {}`", code)),
+                [first, ..] => Some(format!(
+                    "some errors have detailed explanations: {}; for more information about an error, try `tipyc --explain {}`",
+                    codes.join(", "),
+                    first
+                )),
+            };
+            if let Some(hint) = hint {
+                eprintln!("{}", self.emitter.render_json(&Diagnostic::note(hint)));
+            }
+            return if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+        }
+
+        let warnings = plural(self.warning_count, "warning");
+        if !failed {
+            if self.warning_count > 0 {
+                eprint!("{}", self.emitter.render(&Diagnostic::warning(format!("{} emitted", warnings))));
+            }
+            return ExitCode::SUCCESS;
+        }
+
+        let mut summary = format!("aborting due to {}", plural(self.error_count, "previous error"));
+        if self.warning_count > 0 {
+            summary.push_str(&format!("; {} emitted", warnings));
+        }
+        eprint!("{}", self.emitter.render(&Diagnostic::error(summary)));
+
+        // 与 rustc 一样，提示用户可以用 `--explain` 查看错误码的详细说明
+        match codes.as_slice() {
+            [] => {}
+            [code] => eprintln!("For more information about this error, try `tipyc --explain {}`.", code),
+            [first, ..] => {
+                eprintln!("Some errors have detailed explanations: {}.", codes.join(", "));
+                eprintln!("For more information about an error, try `tipyc --explain {}`.", first);
+            }
+        }
+        ExitCode::FAILURE
+    }
+}
+
+/// e.g., `plural(1, "warning")` -> "1 warning"，`plural(2, "warning")` -> "2 warnings"。
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
@@ -0,0 +1,396 @@
+// file: src/emitter.rs
+
+use std::env;
+use std::io::IsTerminal;
+
+use crate::diagnostics::{Diagnostic, Label, Severity, Span};
+
+// --- ANSI 颜色 ---
+const BOLD: &str = "1";
+const BOLD_RED: &str = "1;31";
+const BOLD_BLUE: &str = "1;34";
+const BOLD_YELLOW: &str = "1;33";
+const BOLD_GREEN: &str = "1;32";
+
+/// 制表符在源码片段中展开成的空格数，保证下划线与源码对齐。
+const TAB_WIDTH: usize = 4;
+
+/// `--color` 选项：何时在诊断信息中使用 ANSI 颜色。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// 仅当标准错误输出是终端、且没有设置 `NO_COLOR` 时着色（默认）。
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// 结合当前环境，决定最终是否着色。
+    pub fn should_color(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+/// `--error-format` 选项：诊断信息的输出格式。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    /// 面向人类的、带源码片段的文本（默认）。
+    Human,
+    /// 每条诊断一行 JSON，供编辑器和 CI 解析。
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// 诊断渲染器，把 `Diagnostic` 渲染成 rustc 风格的文本：
+///
+/// ```text
+/// error[E0101]: mismatched types
+///  --> example.tp:5:14
+///   |
+/// 5 |     x: i32 = true;
+///   |              ^^^^ expected `i32`, found `bool`
+///   |
+///   = help: ...
+/// ```
+pub struct Emitter<'a> {
+    /// 显示在 `-->` 之后的文件名。
+    file_name: &'a str,
+    /// 完整的源代码，所有 `Span` 的字节偏移都相对于它。
+    source: &'a str,
+    color: bool,
+}
+
+/// 一个标注在源码中的具体位置，由 `Span` 的字节偏移换算而来。
+struct Annotation<'s, 'l> {
+    /// 1 起始的行号。
+    line_number: usize,
+    /// 该行的源码文本（不含换行符）。
+    line_text: &'s str,
+    /// 1 起始的列号（按字符计），用于 `-->` 一行。
+    column: usize,
+    /// 下划线之前需要留出的显示宽度。
+    offset: usize,
+    /// 下划线的显示宽度，至少为 1。
+    width: usize,
+    label: &'l Label,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(file_name: &'a str, source: &'a str, color: bool) -> Self {
+        Emitter { file_name, source, color }
+    }
+
+    /// 将一条诊断渲染为多行文本，每一行都以换行符结尾。
+    pub fn render(&self, diag: &Diagnostic) -> String {
+        let mut out = String::new();
+
+        // --- 1. 标题行: `error[E0101]: message` / `warning[W0001]: message` ---
+        let header = match diag.code {
+            Some(code) => format!("{}[{}]", diag.severity, code),
+            None => diag.severity.to_string(),
+        };
+        let severity_style = match diag.severity {
+            Severity::Error => BOLD_RED,
+            Severity::Warning => BOLD_YELLOW,
+            Severity::Note => BOLD_GREEN,
+        };
+        out.push_str(&self.paint(&header, severity_style));
+        out.push_str(&self.paint(&format!(": {}", diag.message), BOLD));
+        out.push('\n');
+
+        let mut annotations: Vec<Annotation> = diag.labels.iter().map(|label| self.annotate(label)).collect();
+
+        // 行号栏的宽度由最大的行号决定
+        let gutter_width = annotations.iter().map(|a| a.line_number.to_string().len()).max().unwrap_or(1);
+        let blank_gutter = " ".repeat(gutter_width);
+        let bar = self.paint("|", BOLD_BLUE);
+
+        if !annotations.is_empty() {
+            // --- 2. 位置行: ` --> file:line:col`，取第一个主标注 ---
+            let main = annotations.iter().find(|a| a.label.primary).unwrap_or(&annotations[0]);
+            out.push_str(&format!(
+                "{}{} {}:{}:{}\n",
+                blank_gutter,
+                self.paint("-->", BOLD_BLUE),
+                self.file_name,
+                main.line_number,
+                main.column
+            ));
+            out.push_str(&format!("{} {}\n", blank_gutter, bar));
+
+            // --- 3. 源码片段与下划线 ---
+            annotations.sort_by_key(|a| (a.line_number, a.offset));
+            let mut last_line: Option<usize> = None;
+            for annotation in &annotations {
+                if last_line != Some(annotation.line_number) {
+                    // 两个标注之间隔了不止一行时，用 `...` 表示省略
+                    if last_line.is_some_and(|last| annotation.line_number > last + 1) {
+                        out.push_str(&format!("{}\n", self.paint("...", BOLD_BLUE)));
+                    }
+                    let number = format!("{:>width$}", annotation.line_number, width = gutter_width);
+                    out.push_str(&format!(
+                        "{} {} {}\n",
+                        self.paint(&number, BOLD_BLUE),
+                        bar,
+                        expand_tabs(annotation.line_text)
+                    ));
+                    last_line = Some(annotation.line_number);
+                }
+
+                // 主标注的颜色与严重程度一致（错误为红色，警告为黄色）
+                let (marker, style) = if annotation.label.primary { ('^', severity_style) } else { ('-', BOLD_BLUE) };
+                let mut underline: String = std::iter::repeat_n(marker, annotation.width).collect();
+                if !annotation.label.message.is_empty() {
+                    underline.push(' ');
+                    underline.push_str(&annotation.label.message);
+                }
+                out.push_str(&format!(
+                    "{} {} {}{}\n",
+                    blank_gutter,
+                    bar,
+                    " ".repeat(annotation.offset),
+                    self.paint(&underline, style)
+                ));
+            }
+        }
+
+        // --- 4. `= note:` / `= help:` ---
+        if !diag.notes.is_empty() || !diag.helps.is_empty() {
+            if !annotations.is_empty() {
+                out.push_str(&format!("{} {}\n", blank_gutter, bar));
+            }
+            let footers = diag.notes.iter().map(|n| ("note", n)).chain(diag.helps.iter().map(|h| ("help", h)));
+            for (kind, text) in footers {
+                out.push_str(&format!(
+                    "{} {} {}: {}\n",
+                    blank_gutter,
+                    self.paint("=", BOLD_BLUE),
+                    self.paint(kind, BOLD),
+                    text
+                ));
+            }
+        }
+
+        out
+    }
+
+    /// 将一条诊断序列化为单行 JSON（不含换行符）。
+    ///
+    /// 格式如下，所有行号、列号都从 1 开始，列号按字符计，字节偏移从 0 开始：
+    ///
+    /// ```text
+    /// {"code":"E0101","severity":"error","message":"mismatched types","file":"a.tp",
+    ///  "spans":[{"byte_start":60,"byte_end":64,"line_start":5,"column_start":14,
+    ///            "line_end":5,"column_end":18,"is_primary":true,"label":"expected `i32`, found `bool`"}],
+    ///  "notes":[],"helps":[]}
+    /// ```
+    pub fn render_json(&self, diag: &Diagnostic) -> String {
+        let spans: Vec<String> = diag
+            .labels
+            .iter()
+            .map(|label| {
+                let (line_start, column_start) = self.line_column(label.span.start_byte);
+                let (line_end, column_end) = self.line_column(label.span.end_byte);
+                format!(
+                    "{{\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{},\"is_primary\":{},\"label\":{}}}",
+                    label.span.start_byte,
+                    label.span.end_byte,
+                    line_start,
+                    column_start,
+                    line_end,
+                    column_end,
+                    label.primary,
+                    json_string(&label.message)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"code\":{},\"severity\":\"{}\",\"message\":{},\"file\":{},\"spans\":[{}],\"notes\":[{}],\"helps\":[{}]}}",
+            diag.code.map_or("null".to_string(), json_string),
+            diag.severity,
+            json_string(&diag.message),
+            json_string(self.file_name),
+            spans.join(","),
+            diag.notes.iter().map(|n| json_string(n)).collect::<Vec<_>>().join(","),
+            diag.helps.iter().map(|h| json_string(h)).collect::<Vec<_>>().join(",")
+        )
+    }
+
+    /// 将字节偏移换算为 1 起始的 (行号, 列号)，列号按字符计。
+    fn line_column(&self, byte: usize) -> (usize, usize) {
+        let byte = floor_char_boundary(self.source, byte.min(self.source.len()));
+        let before = &self.source[..byte];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
+
+    /// 把标注的字节范围换算成行号、列号和下划线的位置。
+    ///
+    /// 跨越多行的范围只在第一行画下划线，一直画到行尾。
+    fn annotate<'l>(&self, label: &'l Label) -> Annotation<'a, 'l> {
+        let Span { start_byte, end_byte, .. } = label.span;
+        let source = self.source;
+
+        let mut start = floor_char_boundary(source, start_byte.min(source.len()));
+        // 指向文件末尾（例如缺少 `}`）时，若文件以换行结尾，则改为指向最后一行的行尾
+        if start == source.len() && source.ends_with('\n') && start > 0 {
+            start -= 1;
+        }
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+        let end = floor_char_boundary(source, end_byte.clamp(start, line_end));
+
+        let line_text = source[line_start..line_end].trim_end_matches('\r');
+        let prefix = &source[line_start..start];
+
+        Annotation {
+            line_number: source[..start].matches('\n').count() + 1,
+            line_text,
+            column: prefix.chars().count() + 1,
+            offset: display_width(prefix),
+            width: display_width(&source[start..end]).max(1),
+            label,
+        }
+    }
+
+    /// 在启用颜色时用 ANSI 转义序列包裹文本。
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// 计算一段文本在终端中的显示宽度（制表符按 `TAB_WIDTH` 计）。
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// 向前找到最近的 UTF-8 字符边界，防止在多字节字符中间切片。
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// 将文本编码为 JSON 字符串字面量（包括两侧的引号）。
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::SemanticAnalyzer;
+    use crate::diagnostics::CompilerError;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::token::Token;
+
+    // 返回源码中第一个词法错误的诊断
+    fn lexer_diagnostic(source: &str) -> Diagnostic {
+        let mut lexer = Lexer::new(source);
+        loop {
+            match lexer.next_token() {
+                Err(e) => return CompilerError::Lexer(e).to_diagnostic(),
+                Ok(spanned) if spanned.token == Token::Eof => panic!("no lexer error in `{}`", source),
+                Ok(_) => {}
+            }
+        }
+    }
+
+    // 返回源码中的所有语义错误的诊断
+    fn semantic_diagnostics(source: &str) -> Vec<Diagnostic> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "parse errors: {:?}", parser.errors);
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program);
+        analyzer.errors.iter().map(CompilerError::to_diagnostic).collect()
+    }
+
+    #[test]
+    fn json_for_a_lexer_error() {
+        let source = "main() {\n    x: i64 = 1 $ 2;\n}\n";
+        let json = Emitter::new("demo.tp", source, false).render_json(&lexer_diagnostic(source));
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"code":"E0001","severity":"error","message":"unknown character `$`","file":"demo.tp","spans":["#,
+                r#"{"byte_start":24,"byte_end":25,"line_start":2,"column_start":16,"line_end":2,"column_end":17,"#,
+                r#""is_primary":true,"label":"this character cannot start any token"}],"notes":[],"helps":[]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn json_for_a_semantic_error_with_a_secondary_span() {
+        let source = "main() {\n    x: i64 = 1;\n    x: bool = true;\n}\n";
+        let diagnostics = semantic_diagnostics(source);
+        assert_eq!(diagnostics.len(), 1);
+        let json = Emitter::new("demo.tp", source, false).render_json(&diagnostics[0]);
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"code":"E0103","severity":"error","message":"`x` is defined multiple times","file":"demo.tp","spans":["#,
+                r#"{"byte_start":29,"byte_end":44,"line_start":3,"column_start":5,"line_end":3,"column_end":20,"#,
+                r#""is_primary":true,"label":"`x` redefined here"},"#,
+                r#"{"byte_start":13,"byte_end":24,"line_start":2,"column_start":5,"line_end":2,"column_end":16,"#,
+                r#""is_primary":false,"label":"previous definition of `x` here"}],"notes":[],"helps":[]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn json_escapes_strings_and_lists_helps() {
+        let source = "s: str = \"abc\n";
+        let json = Emitter::new("dir\\demo.tp", source, false).render_json(&lexer_diagnostic(source));
+        assert!(json.starts_with(r#"{"code":"E0002","severity":"error","message":"unterminated string literal","file":"dir\\demo.tp","#), "{}", json);
+        assert!(json.ends_with(r#""notes":[],"helps":["add a closing `\"`"]}"#), "{}", json);
+    }
+}
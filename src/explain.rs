@@ -0,0 +1,770 @@
+// file: src/explain.rs
+
+//! `tipyc --explain <CODE>` 使用的错误码说明。
+//!
+//! 每个错误码对应一段长说明：错误的含义、一个会触发该错误的 Tipy 示例，
+//! 以及修正后的写法。新增错误变体时，请同时在 `diagnostics.rs` 中分配错误码，
+//! 并在这里补上说明。
+
+/// 所有错误码及其长说明，按错误码排序。警告码 (Wxxxx) 排在最后。
+const EXPLANATIONS: &[(&str, &str)] = &[
+    // --- 词法分析 (E00xx) ---
+    ("E0001", r#"An unknown character was found in the source.
+
+The lexer only accepts characters that can start a Tipy token: letters, digits,
+`_`, quotes, whitespace and the operators of the language. Anything else, such
+as `@`, `$` or `#`, is rejected.
+
+Erroneous code example:
+
+    main() -> i64 {
+        x: i64 = 1 @ 2;
+        ret x
+    }
+
+Remove the character or replace it with a valid operator:
+
+    main() -> i64 {
+        x: i64 = 1 + 2;
+        ret x
+    }
+"#),
+    ("E0002", r#"A string literal was never closed.
+
+A string starts with `"` and must end with another `"` before the end of the
+file. The error points at the opening quote.
+
+Erroneous code example:
+
+    main() {
+        s: str = "hello;
+    }
+
+Add the closing quote:
+
+    main() {
+        s: str = "hello";
+    }
+"#),
+    ("E0003", r#"A number literal is malformed.
+
+Number literals are written in decimal (`42`, `3.14`, `1e-9`), or as integers
+with a `0x` (hexadecimal), `0o` (octal) or `0b` (binary) prefix. Digits may be
+separated with `_`, and a literal may end with a type suffix such as `10u8` or
+`2.5f32`. This error is reported when the literal does not follow these rules:
+a prefix without digits, a digit that is not valid in the given base, an
+unknown suffix, a float with an integer suffix, or an integer that does not fit
+into 64 bits.
+
+Erroneous code example:
+
+    main() {
+        x: i64 = 99999999999999999999;
+        y: u8 = 0b102;
+        z: f64 = 1.5u8;
+    }
+
+Write a number that fits into the type, and use digits and suffixes that match
+the kind of literal:
+
+    main() {
+        x: i64 = 9_999_999_999;
+        y: u8 = 0b101;
+        z: f64 = 1.5f64;
+    }
+"#),
+    ("E0004", r#"A character literal is malformed.
+
+A character literal holds exactly one character between single quotes. Empty
+literals, literals with several characters and a missing closing quote are all
+errors.
+
+Erroneous code example:
+
+    main() {
+        c: char = 'ab';
+    }
+
+Use a single character, or a string literal for text:
+
+    main() {
+        c: char = 'a';
+        s: str = "ab";
+    }
+"#),
+    ("E0005", r#"An escape sequence in a string or character literal is malformed.
+
+Inside string and character literals a backslash starts an escape sequence.
+The supported escapes are `\n` (newline), `\t` (tab), `\r` (carriage return),
+`\\` (backslash), `\"` (double quote), `\'` (single quote), `\0` (null) and
+`\u{...}`, which takes 1 to 6 hexadecimal digits naming a Unicode scalar value.
+
+Erroneous code example:
+
+    main() {
+        s: str = "C:\path";
+        c: char = '\u{D800}';
+    }
+
+`\p` is not a known escape, and `D800` is a surrogate code point, which is not
+a valid `char`. Escape the backslash itself, and use a valid code point:
+
+    main() {
+        s: str = "C:\\path";
+        c: char = '\u{1F600}';
+    }
+"#),
+    ("E0006", r#"A block comment was never closed.
+
+A block comment starts with `/*` and ends with `*/`. Block comments nest: every
+`/*` inside a comment opens another level, and each level needs its own `*/`.
+Reaching the end of the file while a comment is still open is an error.
+
+Erroneous code example:
+
+    /* the outer comment
+       /* a nested comment */
+    main() {}
+
+The nested comment is closed, but the outer one is not, so the whole rest of the
+file is part of the comment. Close every level:
+
+    /* the outer comment
+       /* a nested comment */
+    */
+    main() {}
+"#),
+    // --- 语法分析 (E005x) ---
+    ("E0051", r#"The parser found a token it did not expect.
+
+The message says what the parser was expecting at this point (for example an
+expression, an identifier or a closing `}`) and which token it found instead.
+
+Erroneous code example:
+
+    main() -> i64 {
+        x: i64 = 1 +;
+        ret x
+    }
+
+Complete the expression:
+
+    main() -> i64 {
+        x: i64 = 1 + 2;
+        ret x
+    }
+"#),
+    ("E0052", r#"The file ended in the middle of a construct.
+
+The parser reached the end of the file while it was still expecting more input,
+usually because a block or a parameter list was not closed.
+
+Erroneous code example:
+
+    main() {
+        x: i64 = 1;
+
+Close every block that was opened:
+
+    main() {
+        x: i64 = 1;
+    }
+"#),
+    ("E0053", r#"The left-hand side of `=` is not something that can be assigned to.
+
+Only variables can be assigned. Literals, calls and arithmetic expressions do
+not name a storage location.
+
+The parser currently accepts any expression on the left of `=` and leaves this
+check to semantic analysis, which reports it as E0109.
+
+Erroneous code example:
+
+    main() {
+        x: ~i64 = 1;
+        x + 1 = 2;
+    }
+
+Assign to the variable itself:
+
+    main() {
+        x: ~i64 = 1;
+        x = x + 1;
+    }
+"#),
+    // --- 语义分析 (E01xx) ---
+    ("E0101", r#"An expression has a different type than the one its context requires.
+
+Tipy never converts between types implicitly. The type of an initializer must
+match the declared type of the variable, a returned value must match the
+function's return type, arguments must match the parameter types, and both
+operands of a binary operator must have the same type.
+
+Erroneous code example:
+
+    main() -> i64 {
+        x: i64 = true;   // expected `i64`, found `bool`
+        ret x
+    }
+
+Use a value of the expected type, or change the declared type:
+
+    main() -> i64 {
+        x: i64 = 1;
+        ret x
+    }
+"#),
+    ("E0102", r#"A name was used that is not defined in the current scope.
+
+Variables must be declared before they are used, and a variable declared inside
+a block is not visible outside of it. Unknown type names produce this error as
+well.
+
+Erroneous code example:
+
+    main() -> i64 {
+        ret count
+    }
+
+Declare the name before using it:
+
+    main() -> i64 {
+        count: i64 = 0;
+        ret count
+    }
+"#),
+    ("E0103", r#"A name was defined more than once in the same scope.
+
+Two functions may not share a name, and two variables may not share a name in
+the same block. The error points at the second definition and also shows the
+first one.
+
+Erroneous code example:
+
+    main() {
+        x: i64 = 1;
+        x: i64 = 2;
+    }
+
+Pick a different name, or assign to the existing mutable variable:
+
+    main() {
+        x: ~i64 = 1;
+        x = 2;
+    }
+"#),
+    ("E0104", r#"The condition of an `if`, `elif` or `while` is not a `bool`.
+
+Tipy has no notion of "truthy" values: integers, characters and strings are not
+accepted as conditions. Compare the value explicitly instead.
+
+Erroneous code example:
+
+    main() {
+        n: ~i64 = 10;
+        while n {
+            n = n - 1;
+        }
+    }
+
+Write a comparison that produces a `bool`:
+
+    main() {
+        n: ~i64 = 10;
+        while n > 0 {
+            n = n - 1;
+        }
+    }
+"#),
+    ("E0105", r#"`break` was used outside of a loop.
+
+`break` leaves the innermost enclosing `loop` or `while`. Outside of a loop
+there is nothing to leave; use `ret` to leave a function early instead.
+
+Erroneous code example:
+
+    check(x: i64) -> i64 {
+        if x > 10 {
+            break;
+        }
+        ret x
+    }
+
+Return from the function instead:
+
+    check(x: i64) -> i64 {
+        if x > 10 {
+            ret 10
+        }
+        ret x
+    }
+"#),
+    ("E0106", r#"`continue` was used outside of a loop.
+
+`continue` jumps to the next iteration of the innermost enclosing `loop` or
+`while`, so it is only meaningful inside a loop body.
+
+Erroneous code example:
+
+    main() {
+        continue;
+    }
+
+Only use `continue` inside a loop:
+
+    main() {
+        i: ~i64 = 0;
+        while i < 10 {
+            i = i + 1;
+            continue;
+        }
+    }
+"#),
+    ("E0107", r#"Something that is not a function was called.
+
+Only functions can be called with `(...)`. Calling a variable of type `i64` or
+`bool` is an error.
+
+Erroneous code example:
+
+    main() {
+        x: i64 = 10;
+        x();
+    }
+
+Call a function instead:
+
+    ten() -> i64 {
+        ret 10
+    }
+
+    main() {
+        x: i64 = ten();
+    }
+"#),
+    ("E0108", r#"A function was called with the wrong number of arguments.
+
+The number of arguments in a call must match the number of parameters in the
+function's declaration exactly. Tipy has no default or variadic parameters.
+
+Erroneous code example:
+
+    add(a: i64, b: i64) -> i64 {
+        ret a + b
+    }
+
+    main() -> i64 {
+        ret add(1, 2, 3)
+    }
+
+Pass exactly one argument per parameter:
+
+    main() -> i64 {
+        ret add(1, 2)
+    }
+"#),
+    ("E0109", r#"The left-hand side of `=` is not something that can be assigned to.
+
+This is the semantic counterpart of E0053: only variables can be assigned.
+
+Erroneous code example:
+
+    main() {
+        5 = 10;
+    }
+
+Assign to a mutable variable:
+
+    main() {
+        x: ~i64 = 5;
+        x = 10;
+    }
+"#),
+    ("E0110", r#"An operator was applied to a type that does not support it.
+
+Unary `-` only works on signed integers and floats (an unsigned integer such as
+`u8` cannot be negated), `!` works on `bool` (logical not) and on integers
+(bitwise not), `&&` and `||` only work on `bool`, the arithmetic operators `+`,
+`-`, `*` and `/` only work on numbers, and `%`, `&`, `|`, `^`, `<<` and `>>`
+only work on integers. Strings can be compared
+with `==` and `!=`, but not ordered with `<`, `<=`, `>` or `>=`.
+
+Erroneous code example:
+
+    main() {
+        flag: bool = -true;
+    }
+
+Use the operator that matches the type:
+
+    main() {
+        flag: bool = !true;
+    }
+"#),
+    ("E0111", r#"A variable or parameter that was not declared with `~` was assigned to.
+
+Bindings in Tipy are immutable by default. Only a binding whose type is marked
+with `~` may be assigned after its declaration. This applies to function
+parameters as well. The error points at the assignment and at the declaration.
+A binding declared without an initial value, as in `x: i64;`, may still be
+assigned once (see E0119).
+
+Erroneous code example:
+
+    count_down(n: i64) -> i64 {
+        n = n - 1;
+        ret n
+    }
+
+Mark the binding as mutable with `~`:
+
+    count_down(n: ~i64) -> i64 {
+        n = n - 1;
+        ret n
+    }
+"#),
+    ("E0112", r#"`break` with a value was used inside a `while` loop.
+
+Only `loop` is an expression: the value given to `break` becomes the value of
+the whole `loop`. A `while` loop is a statement and has no value, so its
+`break` must not carry one.
+
+Erroneous code example:
+
+    main() -> i64 {
+        n: ~i64 = 0;
+        while n < 10 {
+            n = n + 1;
+            break n;
+        }
+        ret n
+    }
+
+Use `loop` if the loop should produce a value:
+
+    main() -> i64 {
+        n: ~i64 = 0;
+        result: i64 = loop {
+            n = n + 1;
+            if n >= 10 {
+                break n;
+            }
+        };
+        ret result
+    }
+"#),
+    ("E0113", r#"A function with a return type does not return a value on every path.
+
+A function declared with `-> T` must produce a `T` no matter which path
+execution takes. A path ends correctly if it reaches a `ret`, if it runs into
+an infinite `loop`, or if the function body ends with an expression of type
+`T`, which is returned implicitly. An `if` without `else`, or an `if` whose
+branches do not all return, leaves a path that falls off the end of the body.
+
+The error points at the closing brace of the function body.
+
+Erroneous code example:
+
+    sign(x: i64) -> i64 {
+        if x > 0 {
+            ret 1
+        } elif x < 0 {
+            ret -1
+        }
+    }
+
+Make sure every branch returns, or end the body with a value:
+
+    sign(x: i64) -> i64 {
+        if x > 0 {
+            ret 1
+        } elif x < 0 {
+            ret -1
+        }
+        0
+    }
+"#),
+    ("E0114", r#"An integer literal does not fit into the type it is used as.
+
+Integer and float literals have no fixed type of their own. They take the type
+the surrounding code expects: the declared type of a variable, the type of a
+parameter, the return type of the function, or the type of the other operand
+of an operator. Only when nothing is expected does an integer literal default
+to `i64` and a float literal to `f64`.
+
+Once the type is known, the value of the literal must lie within its range. A
+leading `-` counts as part of the literal, so `-128` is a valid `i8`.
+
+Erroneous code example:
+
+    b: u8 = 300;
+    c: i8 = -129;
+
+Use a wider type, or a value that fits:
+
+    b: u16 = 300;
+    c: i8 = -128;
+"#),
+    ("E0115", r#"An `as` cast between two types that cannot be converted.
+
+Tipy never converts between types implicitly; `as` is the way to do it
+explicitly. The following casts are allowed:
+
+* between any two numeric types (integers and floats). Integers are
+  truncated or extended, floats are rounded towards zero when cast to an
+  integer.
+* from `bool` to an integer (`false` is 0, `true` is 1).
+* from `char` to an integer, which yields its Unicode code point, and from
+  `u8` or `u32` to `char`.
+* between two pointer types, and between pointers and integers.
+
+Erroneous code example:
+
+    main() {
+        x: f64 = true as f64;
+    }
+
+Go through an integer, or compare instead of casting:
+
+    main() {
+        x: f64 = true as i64 as f64;
+        b: bool = x != 0.0;
+    }
+"#),
+    ("E0116", r#"No overload of a built-in function accepts the given arguments.
+
+Some built-in functions are overloaded: `print` and `println` accept exactly one
+value of type `str`, `bool`, `char`, `f32`, `f64` or any integer type up to 64
+bits, and `println` may also be called without arguments to print an empty
+line. The overload is chosen by the types of the arguments, so a call with
+several arguments, or with a value of another type, has no matching overload.
+
+Erroneous code example:
+
+    main() {
+        x: i64 = 42;
+        print("x = ", x);
+    }
+
+Print each value separately:
+
+    main() {
+        x: i64 = 42;
+        print("x = ");
+        println(x);
+    }
+"#),
+    ("E0117", r#"The type of a variable could not be inferred from its initializer.
+
+A variable declaration may leave out its type when it has an initial value, as
+in `x := 10;` or `y: ~ = f();`. The variable then takes the type of that value.
+This only works if the value is something that can be stored in a variable: a
+call to a function without a return type produces no value, and functions
+themselves cannot be stored.
+
+Erroneous code example:
+
+    greet() {
+        println("hello");
+    }
+
+    main() {
+        x := greet();
+    }
+
+Call the function as a statement, or have it return a value:
+
+    greet() {
+        println("hello");
+    }
+
+    main() {
+        greet();
+    }
+"#),
+    ("E0118", r#"A variable was read before it was assigned on every path.
+
+A variable may be declared without an initial value, as in `x: i32;`. Every
+path through the function must then assign it before it is read. A value
+assigned in only one branch of an `if`, inside a `while` loop that may run zero
+times, or on the right side of `&&` or `||` does not count. The error points at
+the read and at the declaration.
+
+Erroneous code example:
+
+    main() {
+        x: i64;
+        if true {
+            x = 1;
+        }
+        println(x);
+    }
+
+Assign the variable on every path, or give it an initial value:
+
+    main() {
+        x: i64;
+        if true {
+            x = 1;
+        } else {
+            x = 2;
+        }
+        println(x);
+    }
+"#),
+    ("E0119", r#"An immutable variable declared without a value may be assigned twice.
+
+A variable that is declared without `~` and without an initial value, as in
+`x: i64;`, may be assigned exactly once. The compiler rejects any assignment
+that could happen after an earlier one, including an assignment inside a loop
+body that runs more than once.
+
+Erroneous code example:
+
+    main() {
+        x: i64;
+        x = 1;
+        x = 2;
+    }
+
+Mark the variable as mutable with `~`:
+
+    main() {
+        x: ~i64;
+        x = 1;
+        x = 2;
+    }
+"#),
+    ("E0120", r#"A function was given a name that the compiler reserves for the C runtime.
+
+Some built-in functions are implemented by calling functions from the C
+runtime library: `print` and `println` call `printf`, string comparison calls
+`memcmp`, and `byte_at` calls `abort` when the index is out of bounds. A Tipy
+function is emitted under its own name, so a function with one of these names
+would replace the C function that the built-ins rely on.
+
+The reserved names are `printf`, `memcmp` and `abort`.
+
+Erroneous code example:
+
+    abort() {
+        println("giving up");
+    }
+
+Choose a different name:
+
+    give_up() {
+        println("giving up");
+    }
+"#),
+    ("E0121", r#"A variable was declared with a type that cannot hold a value.
+
+`void` is the return type of a function that returns nothing. It has no
+values, so a variable of type `void` could never be read or assigned. This
+applies whether or not the declaration has an initial value. When the type is
+left out and inferred, the same mistake is reported as E0117.
+
+Erroneous code example:
+
+    main() {
+        x: void;
+    }
+
+Give the variable a type that has values, or remove it:
+
+    main() {
+        x: i64;
+        x = 1;
+        println(x);
+    }
+"#),
+    // --- 代码生成 (E02xx) ---
+    ("E0201", r#"LLVM reported an error while building instructions.
+
+This error comes from the LLVM backend rather than from your program. If the
+program passes `tipyc check`, please report it as a compiler bug together with
+the source file that triggers it.
+"#),
+    ("E0202", r#"Code generation could not find a symbol.
+
+Semantic analysis is supposed to reject every use of an undefined name (E0102)
+before code generation starts, so this error indicates a compiler bug. Please
+report it together with the source file that triggers it.
+"#),
+    ("E0203", r#"Code generation was asked to assign to an expression that has no address.
+
+Like E0202, this should have been rejected earlier (see E0053 and E0109) and
+indicates a compiler bug.
+"#),
+    ("E0204", r#"The native target could not be initialized or the output file could not be written.
+
+This happens when LLVM does not support the host machine, or when the object or
+assembly file cannot be written, e.g. because the output directory does not
+exist or is not writable. Check the path given with `-o`.
+"#),
+    ("E0205", r#"Linking the executable failed.
+
+`tipyc build` links the generated object file by invoking `cc`, or the program
+named by the `CC` environment variable. Make sure a C compiler driver is
+installed and on your `PATH`, or produce an object file with `--emit=obj` and
+link it yourself.
+"#),
+    ("E0206", r#"Code generation does not support this construct yet.
+
+The program is valid Tipy, but the backend cannot compile it yet. The message
+names the unsupported construct.
+"#),
+    // --- 警告 (W0xxx) ---
+    ("W0001", r#"A variable or parameter is declared but its value is never read.
+
+This is a warning (lint `unused_variables`). It usually points at a leftover
+from an earlier version of the code or at a typo in a later use of the name.
+Assigning to a variable does not count as reading it.
+
+Example:
+
+    main() -> i64 {
+        x: i64 = 1;
+        y: i64 = 2;
+        ret y
+    }
+
+Remove the variable, or prefix its name with `_` if it is intentionally unused:
+
+    main() -> i64 {
+        _x: i64 = 1;
+        y: i64 = 2;
+        ret y
+    }
+
+Use `-A unused_variables` to silence this lint, or `-D unused_variables` to
+turn it into an error.
+"#),
+    ("W0002", r#"A statement can never be executed.
+
+This is a warning (lint `unreachable_code`). A statement that directly follows
+`ret`, `break` or `continue` in the same block is never reached.
+
+Example:
+
+    main() -> i64 {
+        ret 0
+        x: i64 = 1;
+    }
+
+Remove the unreachable statement, or move it before the `ret`:
+
+    main() -> i64 {
+        ret 0
+    }
+
+Use `-A unreachable_code` to silence this lint, or `-D unreachable_code` to
+turn it into an error.
+"#),
+];
+
+/// 查找错误码的长说明。错误码不区分大小写，e.g., `e0101` 与 `E0101` 等价。
+pub fn lookup(code: &str) -> Option<&'static str> {
+    let code = code.to_ascii_uppercase();
+    EXPLANATIONS.iter().find(|(c, _)| *c == code).map(|(_, text)| *text)
+}
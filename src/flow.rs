@@ -0,0 +1,165 @@
+// file: src/flow.rs
+
+//! 基于 AST 的控制流分析。
+//!
+//! 这里的函数只读取 AST，不依赖符号表或类型信息，供语义分析器判断
+//! “某段代码执行完之后，控制流是否还会继续往下走”。
+
+use crate::ast::{BlockStatement, Expression, Operator, Statement};
+
+/// 判断一个代码块是否“发散”：即控制流永远不会正常地从块的末尾离开。
+///
+/// 只要块中有任何一条语句发散，它之后的语句都不可达，整个块也就发散了。
+///
+/// 发散的来源有：
+/// - `ret`、`break`、`continue`。
+/// - 所有分支都发散的 `if-elif-else` 链（必须有 `else`）。
+/// - 不包含任何跳出自身的 `break` 的 `loop`（无限循环）。
+///
+/// 注意 `break` 和 `continue` 只会跳出最近的循环，而包含 `break` 的循环本身
+/// 不被视为发散，所以在函数体这一层，“发散”就等价于“所有路径都以 `ret` 结束”。
+pub fn block_diverges(block: &BlockStatement) -> bool {
+    block.statements.iter().any(statement_diverges)
+}
+
+/// 判断一条语句是否发散，规则见 `block_diverges`。
+pub fn statement_diverges(statement: &Statement) -> bool {
+    match statement {
+        Statement::Return(_) | Statement::Break(_) | Statement::Continue(_) => true,
+        Statement::Expression(expr) => expression_diverges(expr),
+        Statement::VarDeclaration(var_decl) => var_decl.value.as_ref().is_some_and(expression_diverges),
+        Statement::Block(block) => block_diverges(block),
+        // 条件第一次就可能为假，循环体可能一次都不执行，所以只看条件本身
+        Statement::While(while_stmt) => expression_diverges(&while_stmt.condition),
+    }
+}
+
+/// 判断一个表达式是否发散，规则见 `block_diverges`。
+pub fn expression_diverges(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(_) | Expression::Identifier(_) => false,
+        Expression::Prefix(prefix) => expression_diverges(&prefix.right),
+        Expression::Cast(cast) => expression_diverges(&cast.value),
+        // `&&` 和 `||` 的右操作数可能因为短路而不被求值
+        Expression::Infix(infix) if matches!(infix.op, Operator::LogicalAnd | Operator::LogicalOr) => {
+            expression_diverges(&infix.left)
+        }
+        Expression::Infix(infix) => expression_diverges(&infix.left) || expression_diverges(&infix.right),
+        Expression::Assignment(assign) => expression_diverges(&assign.value),
+        Expression::Call(call) => {
+            expression_diverges(&call.function) || call.arguments.iter().any(expression_diverges)
+        }
+        Expression::If(if_expr) => {
+            expression_diverges(&if_expr.condition)
+                || match &if_expr.alternative {
+                    // 没有 `else` 时，条件为假的路径会直接跳过整个 `if`
+                    Some(alternative) => block_diverges(&if_expr.consequence) && expression_diverges(alternative),
+                    None => false,
+                }
+        }
+        Expression::Loop(loop_expr) => !block_contains_break(&loop_expr.body),
+        Expression::Block(block) => block_diverges(block),
+    }
+}
+
+/// 判断代码块中是否有跳出 **当前** 循环的 `break`。
+///
+/// 嵌套的 `loop` / `while` 循环体中的 `break` 跳出的是内层循环，不会被计入。
+fn block_contains_break(block: &BlockStatement) -> bool {
+    block.statements.iter().any(statement_contains_break)
+}
+
+fn statement_contains_break(statement: &Statement) -> bool {
+    match statement {
+        Statement::Break(_) => true,
+        Statement::Return(ret_stmt) => ret_stmt.value.as_ref().is_some_and(expression_contains_break),
+        Statement::Continue(_) => false,
+        Statement::Expression(expr) => expression_contains_break(expr),
+        Statement::VarDeclaration(var_decl) => var_decl.value.as_ref().is_some_and(expression_contains_break),
+        Statement::Block(block) => block_contains_break(block),
+        // `while` 的条件仍属于外层循环，循环体则不是
+        Statement::While(while_stmt) => expression_contains_break(&while_stmt.condition),
+    }
+}
+
+fn expression_contains_break(expr: &Expression) -> bool {
+    match expr {
+        Expression::Literal(_) | Expression::Identifier(_) => false,
+        Expression::Prefix(prefix) => expression_contains_break(&prefix.right),
+        Expression::Cast(cast) => expression_contains_break(&cast.value),
+        Expression::Infix(infix) => expression_contains_break(&infix.left) || expression_contains_break(&infix.right),
+        Expression::Assignment(assign) => expression_contains_break(&assign.value),
+        Expression::Call(call) => {
+            expression_contains_break(&call.function) || call.arguments.iter().any(expression_contains_break)
+        }
+        Expression::If(if_expr) => {
+            expression_contains_break(&if_expr.condition)
+                || block_contains_break(&if_expr.consequence)
+                || if_expr.alternative.as_deref().is_some_and(expression_contains_break)
+        }
+        Expression::Loop(_) => false,
+        Expression::Block(block) => block_contains_break(block),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TopLevelStatement;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    // 解析只有一个函数的源码，判断它的函数体是否发散
+    fn body_diverges(source: &str) -> bool {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "parse errors: {:?}", parser.errors);
+        match &program.body[..] {
+            [TopLevelStatement::Function(func_decl)] => block_diverges(&func_decl.body),
+            _ => panic!("expected exactly one function"),
+        }
+    }
+
+    #[test]
+    fn return_diverges() {
+        assert!(body_diverges("f() -> i64 { ret 1; }"));
+        assert!(!body_diverges("f() -> i64 { 1 }"));
+        assert!(!body_diverges("f() { }"));
+    }
+
+    #[test]
+    fn if_without_else_does_not_diverge() {
+        assert!(!body_diverges("f(c: bool) -> i64 { if c { ret 1; } }"));
+    }
+
+    #[test]
+    fn if_chain_diverges_only_when_every_branch_does() {
+        assert!(body_diverges("f(a: bool, b: bool) -> i64 { if a { ret 1; } elif b { ret 2; } else { ret 3; } }"));
+        assert!(!body_diverges("f(a: bool, b: bool) -> i64 { if a { ret 1; } elif b { 2; } else { ret 3; } }"));
+        assert!(!body_diverges("f(a: bool, b: bool) -> i64 { if a { ret 1; } elif b { ret 2; } }"));
+    }
+
+    #[test]
+    fn loop_without_break_diverges() {
+        assert!(body_diverges("f() -> i64 { loop { } }"));
+        // 内层循环的 `break` 不会离开外层的 `loop`
+        assert!(body_diverges("f(c: bool) -> i64 { loop { while c { break; } loop { break; } } }"));
+    }
+
+    #[test]
+    fn loop_with_break_does_not_diverge() {
+        assert!(!body_diverges("f() -> i64 { loop { break; } }"));
+        assert!(!body_diverges("f(c: bool) -> i64 { loop { if c { break; } } }"));
+        assert!(!body_diverges("f() -> i64 { loop { { break; } } }"));
+    }
+
+    #[test]
+    fn while_body_may_never_run() {
+        assert!(!body_diverges("f(c: bool) -> i64 { while c { ret 1; } }"));
+    }
+
+    #[test]
+    fn diverging_initializer_diverges() {
+        assert!(body_diverges("f() -> i64 { x: i64 = { ret 1; }; }"));
+    }
+}
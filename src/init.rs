@@ -0,0 +1,473 @@
+// file: src/init.rs
+
+//! 确定初始化分析 (definite initialization)。
+//!
+//! 声明时没有初始值的变量（e.g., `x: i32;`）必须在每一条执行路径上都先被赋值，
+//! 之后才能被读取，否则代码生成会读到未初始化的栈内存。没有 `~` 的这类变量
+//! 只能被赋值一次。
+//!
+//! 与 `flow` 模块一样，这里只读取 AST。语义分析器在一个函数体通过类型检查之后
+//! 调用 `check_function`，此时函数体中的每个名字都已确认可以解析。
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use crate::ast::{
+    BlockStatement, Expression, FunctionDeclaration, LoopExpression, Operator, PrefixOperator, Statement,
+    WhileStatement,
+};
+use crate::diagnostics::{SemanticError, Span};
+
+/// 检查一个函数体中没有初始值的变量是否在读取前被赋值，以及不可变的变量是否只被赋值一次。
+///
+/// 返回发现的所有错误，没有错误时返回空列表。
+pub fn check_function(func_decl: &FunctionDeclaration) -> Vec<SemanticError> {
+    let mut checker = InitChecker {
+        vars: Vec::new(),
+        scopes: vec![HashMap::new()],
+        loops: Vec::new(),
+        state: InitState::entry(),
+        errors: Vec::new(),
+    };
+    // 参数总是已初始化的，记录它们只是为了正确处理遮蔽
+    for p in &func_decl.params {
+        checker.declare(&p.name, None);
+    }
+    checker.block(&func_decl.body);
+    checker.errors
+}
+
+/// 一个声明时没有初始值、需要跟踪的变量。
+struct TrackedVar {
+    is_mutable: bool,
+    /// 变量声明的位置
+    span: Span,
+}
+
+/// 程序中某一点的初始化状态。集合中保存的是 `InitChecker::vars` 的下标。
+#[derive(Clone)]
+struct InitState {
+    /// 这一点是否可达。`ret`、`break`、`continue` 之后的代码不可达，
+    /// 在那里不报告错误，合并控制流时也不考虑它
+    reachable: bool,
+    /// 在所有到达这里的路径上都已被赋值的变量
+    definitely: HashSet<usize>,
+    /// 至少在一条到达这里的路径上被赋值过的变量，用于检查不可变变量是否被赋值了两次
+    maybe: HashSet<usize>,
+}
+
+impl InitState {
+    fn entry() -> Self {
+        InitState { reachable: true, definitely: HashSet::new(), maybe: HashSet::new() }
+    }
+
+    fn unreachable() -> Self {
+        InitState { reachable: false, definitely: HashSet::new(), maybe: HashSet::new() }
+    }
+
+    /// 合并两条控制流路径在汇合点的状态，e.g., `if` 的两个分支。
+    fn join(self, other: InitState) -> InitState {
+        if !self.reachable {
+            return other;
+        }
+        if !other.reachable {
+            return self;
+        }
+        InitState {
+            reachable: true,
+            definitely: self.definitely.intersection(&other.definitely).copied().collect(),
+            maybe: self.maybe.union(&other.maybe).copied().collect(),
+        }
+    }
+}
+
+/// 正在分析的循环，收集从循环体中跳出的路径的状态。
+#[derive(Default)]
+struct LoopFrame {
+    /// 每个 `break` 处的状态，它们在循环之后汇合
+    breaks: Vec<InitState>,
+    /// 每个 `continue` 处的状态，它们回到循环开头
+    continues: Vec<InitState>,
+}
+
+struct InitChecker {
+    vars: Vec<TrackedVar>,
+    /// 作用域栈，把名字映射到被跟踪变量的下标。
+    /// 参数和带初始值的变量映射为 `None`：它们不需要跟踪，但可能遮蔽外层同名的变量
+    scopes: Vec<HashMap<String, Option<usize>>>,
+    /// 循环栈，栈顶是最内层的循环
+    loops: Vec<LoopFrame>,
+    /// 当前程序点的状态
+    state: InitState,
+    errors: Vec<SemanticError>,
+}
+
+impl InitChecker {
+    fn declare(&mut self, name: &str, id: Option<usize>) {
+        self.scopes.last_mut().expect("there is always a function scope").insert(name.to_string(), id);
+    }
+
+    /// 查找名字对应的被跟踪变量。函数名、内置函数以及不需要跟踪的变量都返回 `None`。
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).copied().flatten()
+    }
+
+    fn read(&mut self, name: &str, span: Span) {
+        let Some(id) = self.lookup(name) else { return };
+        if self.state.reachable && !self.state.definitely.contains(&id) {
+            self.errors.push(SemanticError::UseOfUninitialized {
+                name: name.to_string(),
+                span,
+                declaration_span: self.vars[id].span,
+            });
+            // 每条路径只报告一次，之后的读取不再重复报错
+            self.state.definitely.insert(id);
+        }
+    }
+
+    fn assign(&mut self, name: &str, span: Span) {
+        let Some(id) = self.lookup(name) else { return };
+        if !self.state.reachable {
+            return;
+        }
+        if !self.vars[id].is_mutable && self.state.maybe.contains(&id) {
+            self.errors.push(SemanticError::ImmutableAssignedTwice {
+                name: name.to_string(),
+                span,
+                declaration_span: self.vars[id].span,
+            });
+        }
+        self.state.definitely.insert(id);
+        self.state.maybe.insert(id);
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        self.scopes.push(HashMap::new());
+        for stmt in &block.statements {
+            self.statement(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VarDeclaration(var_decl) => {
+                let id = match &var_decl.value {
+                    Some(value) => {
+                        self.expression(value);
+                        None
+                    }
+                    None => {
+                        // 每次经过声明都会得到一个新的编号，所以循环体中的声明在每次迭代都是一个新的变量
+                        self.vars.push(TrackedVar { is_mutable: var_decl.is_mutable, span: var_decl.span });
+                        Some(self.vars.len() - 1)
+                    }
+                };
+                self.declare(&var_decl.name, id);
+            }
+            Statement::Expression(expr) => self.expression(expr),
+            Statement::Return(ret_stmt) => {
+                if let Some(value) = &ret_stmt.value {
+                    self.expression(value);
+                }
+                self.state = InitState::unreachable();
+            }
+            Statement::Block(block) => self.block(block),
+            Statement::While(while_stmt) => self.while_statement(while_stmt),
+            Statement::Break(break_stmt) => {
+                if let Some(value) = &break_stmt.value {
+                    self.expression(value);
+                }
+                let state = mem::replace(&mut self.state, InitState::unreachable());
+                if let Some(frame) = self.loops.last_mut() {
+                    frame.breaks.push(state);
+                }
+            }
+            Statement::Continue(_) => {
+                let state = mem::replace(&mut self.state, InitState::unreachable());
+                if let Some(frame) = self.loops.last_mut() {
+                    frame.continues.push(state);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Literal(_) => {}
+            Expression::Identifier(ident) => self.read(&ident.name, ident.span),
+            Expression::Prefix(prefix) => self.expression(&prefix.right),
+            Expression::Cast(cast) => self.expression(&cast.value),
+            // 右操作数可能因为短路而不被求值，所以其中的赋值不一定发生
+            Expression::Infix(infix) if matches!(infix.op, Operator::LogicalAnd | Operator::LogicalOr) => {
+                let (when_true, when_false) = self.condition(expr);
+                self.state = when_true.join(when_false);
+            }
+            Expression::Infix(infix) => {
+                self.expression(&infix.left);
+                self.expression(&infix.right);
+            }
+            Expression::Assignment(assign) => {
+                // 与代码生成的顺序一致：先对右侧求值，复合赋值再读取旧值，最后写入
+                self.expression(&assign.value);
+                if let Expression::Identifier(ident) = &*assign.left {
+                    if assign.op.is_some() {
+                        self.read(&ident.name, ident.span);
+                    }
+                    self.assign(&ident.name, assign.span);
+                }
+            }
+            Expression::Call(call) => {
+                self.expression(&call.function);
+                for arg in &call.arguments {
+                    self.expression(arg);
+                }
+            }
+            Expression::If(if_expr) => {
+                let (when_true, when_false) = self.condition(&if_expr.condition);
+                self.state = when_true;
+                self.block(&if_expr.consequence);
+                let after_consequence = mem::replace(&mut self.state, when_false);
+                // 没有 `else` 时，条件为假的路径直接离开 `if`
+                if let Some(alternative) = &if_expr.alternative {
+                    self.expression(alternative);
+                }
+                self.state = mem::replace(&mut self.state, InitState::unreachable()).join(after_consequence);
+            }
+            Expression::Loop(loop_expr) => self.loop_expression(loop_expr),
+            Expression::Block(block) => self.block(block),
+        }
+    }
+
+    /// 分析一个布尔条件，分别返回条件为真和为假时的状态。
+    ///
+    /// 区分两者是为了 `&&` 和 `||`：`if c && { x = 1; true } { ... }` 的条件为真时，
+    /// 右操作数一定被求值过，所以 `x` 在 `if` 的分支中已经被赋值。
+    fn condition(&mut self, expr: &Expression) -> (InitState, InitState) {
+        match expr {
+            Expression::Infix(infix) if infix.op == Operator::LogicalAnd => {
+                let (left_true, left_false) = self.condition(&infix.left);
+                self.state = left_true;
+                let (right_true, right_false) = self.condition(&infix.right);
+                (right_true, left_false.join(right_false))
+            }
+            Expression::Infix(infix) if infix.op == Operator::LogicalOr => {
+                let (left_true, left_false) = self.condition(&infix.left);
+                self.state = left_false;
+                let (right_true, right_false) = self.condition(&infix.right);
+                (left_true.join(right_true), right_false)
+            }
+            // 条件的类型是 `bool`，所以这里的 `!` 一定是逻辑非
+            Expression::Prefix(prefix) if prefix.op == PrefixOperator::Not => {
+                let (when_true, when_false) = self.condition(&prefix.right);
+                (when_false, when_true)
+            }
+            _ => {
+                self.expression(expr);
+                (self.state.clone(), self.state.clone())
+            }
+        }
+    }
+
+    /// 分析 `loop`。
+    ///
+    /// 循环体被分析两遍。第一遍从进入循环时的状态出发，只为了得到回到循环开头的
+    /// 状态（循环体末尾和每个 `continue`），这一遍发现的错误会被丢弃。第二遍从
+    /// “进入循环”与“回到开头”合并后的状态出发，报告错误：在这个状态下，前一次
+    /// 迭代中的赋值不算作已初始化，但算作可能已赋值。
+    fn loop_expression(&mut self, loop_expr: &LoopExpression) {
+        let entry = self.state.clone();
+        let error_count = self.errors.len();
+        let back_edge = self.loop_body(&loop_expr.body).1;
+        self.errors.truncate(error_count);
+
+        self.state = entry.join(back_edge);
+        let (breaks, _) = self.loop_body(&loop_expr.body);
+        // `loop` 只能通过 `break` 结束，没有 `break` 时循环之后不可达
+        self.state = breaks.into_iter().fold(InitState::unreachable(), InitState::join);
+    }
+
+    /// 分析 `while`，方法与 `loop_expression` 相同，只是每次迭代都先对条件求值，
+    /// 并且条件为假时也会离开循环。
+    fn while_statement(&mut self, while_stmt: &WhileStatement) {
+        let entry = self.state.clone();
+        let error_count = self.errors.len();
+        self.state = self.condition(&while_stmt.condition).0;
+        let back_edge = self.loop_body(&while_stmt.body).1;
+        self.errors.truncate(error_count);
+
+        self.state = entry.join(back_edge);
+        let (when_true, when_false) = self.condition(&while_stmt.condition);
+        self.state = when_true;
+        let (breaks, _) = self.loop_body(&while_stmt.body);
+        self.state = breaks.into_iter().fold(when_false, InitState::join);
+    }
+
+    /// 从当前状态出发分析一遍循环体，返回所有 `break` 处的状态，以及回到循环开头的状态。
+    fn loop_body(&mut self, body: &BlockStatement) -> (Vec<InitState>, InitState) {
+        self.loops.push(LoopFrame::default());
+        self.block(body);
+        let frame = self.loops.pop().expect("loop frame was just pushed");
+        let end_of_body = mem::replace(&mut self.state, InitState::unreachable());
+        let back_edge = frame.continues.into_iter().fold(end_of_body, InitState::join);
+        (frame.breaks, back_edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::TopLevelStatement;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    // 检查源码中的每个函数，把错误简化为 (错误码, 变量名)
+    fn check(source: &str) -> Vec<(&'static str, String)> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "parse errors: {:?}", parser.errors);
+        program
+            .body
+            .iter()
+            .flat_map(|stmt| match stmt {
+                TopLevelStatement::Function(func_decl) => check_function(func_decl),
+            })
+            .map(|e| match &e {
+                SemanticError::UseOfUninitialized { name, .. } | SemanticError::ImmutableAssignedTwice { name, .. } => {
+                    (e.code(), name.clone())
+                }
+                other => panic!("unexpected error: {:?}", other),
+            })
+            .collect()
+    }
+
+    fn uninitialized(name: &str) -> (&'static str, String) {
+        ("E0118", name.to_string())
+    }
+
+    fn assigned_twice(name: &str) -> (&'static str, String) {
+        ("E0119", name.to_string())
+    }
+
+    #[test]
+    fn read_before_assignment() {
+        assert_eq!(check("f() -> i64 { x: i64; x }"), vec![uninitialized("x")]);
+        assert_eq!(check("f() -> i64 { x: i64; x = 1; x }"), vec![]);
+    }
+
+    #[test]
+    fn each_path_reports_once() {
+        assert_eq!(check("f() -> i64 { x: i64; a: i64 = x; x + a }"), vec![uninitialized("x")]);
+    }
+
+    #[test]
+    fn compound_assignment_reads_the_old_value() {
+        assert_eq!(check("f() { x: ~i64; x += 1; }"), vec![uninitialized("x")]);
+    }
+
+    #[test]
+    fn initialized_variables_and_parameters_are_not_tracked() {
+        assert_eq!(check("f(p: i64) -> i64 { x: i64 = p; x + p }"), vec![]);
+    }
+
+    #[test]
+    fn if_else_requires_both_branches() {
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c { x = 1; } else { x = 2; } x }"), vec![]);
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c { x = 1; } else { } x }"), vec![uninitialized("x")]);
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c { x = 1; } x }"), vec![uninitialized("x")]);
+        assert_eq!(
+            check("f(a: bool, b: bool) -> i64 { x: i64; if a { x = 1; } elif b { x = 2; } else { x = 3; } x }"),
+            vec![],
+        );
+    }
+
+    #[test]
+    fn diverging_branch_does_not_join() {
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c { x = 1; } else { ret 0; } x }"), vec![]);
+    }
+
+    #[test]
+    fn unreachable_code_is_not_reported() {
+        assert_eq!(check("f() -> i64 { x: i64; ret 0; x }"), vec![]);
+    }
+
+    #[test]
+    fn assignment_on_the_right_of_and_in_a_condition() {
+        // 条件为真时 `&&` 的右操作数一定被求值过
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c && { x = 1; true } { x } else { 0 } }"), vec![]);
+        // 条件为假时可能是 `c` 为假导致的短路
+        assert_eq!(
+            check("f(c: bool) -> i64 { x: i64; if c && { x = 1; true } { 0 } else { x } }"),
+            vec![uninitialized("x")],
+        );
+        // 作为普通表达式时，`&&` 之后的赋值不一定发生
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; b: bool = c && { x = 1; true }; x }"), vec![uninitialized("x")]);
+    }
+
+    #[test]
+    fn assignment_on_the_right_of_or_in_a_condition() {
+        // 条件为假时两个操作数都被求值过
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if c || { x = 1; false } { 0 } else { x } }"), vec![]);
+        assert_eq!(
+            check("f(c: bool) -> i64 { x: i64; if c || { x = 1; false } { x } else { 0 } }"),
+            vec![uninitialized("x")],
+        );
+        // `!` 交换两个分支
+        assert_eq!(check("f(c: bool) -> i64 { x: i64; if !(c || { x = 1; false }) { x } else { 0 } }"), vec![]);
+    }
+
+    #[test]
+    fn immutable_assigned_twice() {
+        assert_eq!(check("f() { x: i64; x = 1; x = 2; }"), vec![assigned_twice("x")]);
+        assert_eq!(check("f() { x: ~i64; x = 1; x = 2; }"), vec![]);
+        // 两个分支各赋值一次是允许的
+        assert_eq!(check("f(c: bool) { x: i64; if c { x = 1; } else { x = 2; } }"), vec![]);
+        assert_eq!(check("f(c: bool) { x: i64; if c { x = 1; } x = 2; }"), vec![assigned_twice("x")]);
+    }
+
+    #[test]
+    fn immutable_assigned_in_a_loop() {
+        assert_eq!(check("f(c: bool) { x: i64; while c { x = 1; } }"), vec![assigned_twice("x")]);
+        assert_eq!(check("f() { x: i64; loop { x = 1; } }"), vec![assigned_twice("x")]);
+        assert_eq!(check("f(c: bool) { x: ~i64; while c { x = 1; } }"), vec![]);
+        // 赋值之后立即离开循环，不会被执行第二次
+        assert_eq!(check("f() { x: i64; loop { x = 1; break; } }"), vec![]);
+        // 循环体中的声明在每次迭代都是一个新的变量
+        assert_eq!(check("f(c: bool) { while c { y: i64; y = 1; } }"), vec![]);
+    }
+
+    #[test]
+    fn continue_returns_to_the_loop_head() {
+        assert_eq!(
+            check("f(c: bool) { x: i64; loop { if c { x = 1; continue; } break; } }"),
+            vec![assigned_twice("x")],
+        );
+    }
+
+    #[test]
+    fn assignments_in_loops_after_the_loop() {
+        // `while` 的循环体可能一次也不执行
+        assert_eq!(check("f(c: bool) -> i64 { x: ~i64; while c { x = 1; } x }"), vec![uninitialized("x")]);
+        // `loop` 只能通过 `break` 离开
+        assert_eq!(check("f() -> i64 { x: i64; loop { x = 1; break; } x }"), vec![]);
+        assert_eq!(
+            check("f(c: bool) -> i64 { x: i64; loop { if c { break; } x = 1; break; } x }"),
+            vec![uninitialized("x")],
+        );
+    }
+
+    #[test]
+    fn read_in_loop_before_assignment_in_previous_iteration() {
+        // 前一次迭代中的赋值不算作已初始化
+        assert_eq!(
+            check("f(c: bool) { x: ~i64; while c { y: i64 = x; x = 1; } }"),
+            vec![uninitialized("x")],
+        );
+    }
+
+    #[test]
+    fn inner_declaration_shadows_outer() {
+        assert_eq!(check("f() -> i64 { x: i64; { x: i64 = 1; x; } x = 2; x }"), vec![]);
+        assert_eq!(check("f() -> i64 { x: i64 = 1; { x: i64; x } }"), vec![uninitialized("x")]);
+    }
+}
@@ -1,8 +1,16 @@
 // file: src/lexer.rs
 
-use crate::token::{Token, Keyword, Literal};
+use crate::token::{Token, Keyword, Literal, SpannedToken};
 use crate::diagnostics::{LexerError, Span}; 
 
+use std::num::IntErrorKind;
+
+/// 整数字面量可以使用的类型后缀，e.g., `10u8`、`1_000i64`。
+const INTEGER_SUFFIXES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize",
+    "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
 /// 词法分析器
 pub struct Lexer<'a> {
     // 源代码字符串
@@ -21,22 +29,33 @@ impl<'a> Lexer<'a> {
 
     // 创建一个新的词法分析器
     pub fn new(source: &'a str) -> Self {
-        let mut lexer = Lexer {
+        Lexer {
             source,
             position: 0,
             line: 1,
-            column: 0, // 将在 read_char 中首次变为 1
-            ch: '\0',
-        };
-        lexer.read_char(); // 初始化第一个字符
-        lexer
+            column: 1,
+            // 直接读入第一个字符，position 此时正指向它
+            ch: source.chars().next().unwrap_or('\0'),
+        }
     }
 
     // 核心接口，会返回 Result，需要后续解包
-    pub fn next_token(&mut self) -> Result<Token, LexerError> {
+    // 每个 Token 都附带它在源码中的 Span
+    pub fn next_token(&mut self) -> Result<SpannedToken, LexerError> {
         // 跳过空白和注释
-        self.skip_whitespace_and_comments();
-        
+        self.skip_whitespace_and_comments()?;
+
+        let start_pos = self.position;
+        let start_line = self.line;
+        let start_col = self.column;
+
+        let token = self.read_token()?;
+        let span = self.span_from(start_pos, start_line, start_col);
+        Ok(SpannedToken { token, span })
+    }
+
+    // 读取一个不带位置信息的 Token，由 next_token 负责补上 Span
+    fn read_token(&mut self) -> Result<Token, LexerError> {
         // 在处理 token 前记录起始位置，方便报错
         let start_pos = self.position; 
         let start_line = self.line;
@@ -66,6 +85,9 @@ impl<'a> Lexer<'a> {
                 if self.peek_char() == '=' { 
                     self.read_char(); 
                     Ok(Token::LessEqual) 
+                } else if self.peek_char() == '<' {
+                    self.read_char();
+                    Ok(self.with_assign(Token::ShiftLeft, Token::ShiftLeftAssign))
                 } else { 
                     Ok(Token::LessThan) 
                 }
@@ -74,23 +96,45 @@ impl<'a> Lexer<'a> {
                 if self.peek_char() == '=' { 
                     self.read_char(); 
                     Ok(Token::GreaterEqual) 
+                } else if self.peek_char() == '>' {
+                    self.read_char();
+                    Ok(self.with_assign(Token::ShiftRight, Token::ShiftRightAssign))
                 } else { 
                     Ok(Token::GreaterThan) 
                 }
             }
+            '&' => {
+                if self.peek_char() == '&' {
+                    self.read_char();
+                    Ok(Token::LogicalAnd)
+                } else {
+                    Ok(self.with_assign(Token::Ampersand, Token::AmpersandAssign))
+                }
+            }
+            '|' => {
+                if self.peek_char() == '|' {
+                    self.read_char();
+                    Ok(Token::LogicalOr)
+                } else {
+                    Ok(self.with_assign(Token::Pipe, Token::PipeAssign))
+                }
+            }
             '-' => {
                 if self.peek_char() == '>' {
                     self.read_char();
                     Ok(Token::Arrow)
                 } else {
-                    Ok(Token::Minus)
+                    Ok(self.with_assign(Token::Minus, Token::MinusAssign))
                 }
             }
             
             // 单字符
-            '+' => Ok(Token::Plus),
-            '*' => Ok(Token::Star),
-            '/' => Ok(Token::Slash), // 注释已在 skip 中处理
+            '+' => Ok(self.with_assign(Token::Plus, Token::PlusAssign)),
+            '*' => Ok(self.with_assign(Token::Star, Token::StarAssign)),
+            // 普通注释已在 skip 中处理，剩下的 `//` 只可能是文档注释
+            '/' if self.peek_char() == '/' => return Ok(self.read_doc_comment()),
+            '/' => Ok(self.with_assign(Token::Slash, Token::SlashAssign)),
+            '%' => Ok(self.with_assign(Token::Percent, Token::PercentAssign)),
             '~' => Ok(Token::Tilde),
             ':' => Ok(Token::Colon),
             ';' => Ok(Token::Semicolon),
@@ -99,8 +143,7 @@ impl<'a> Lexer<'a> {
             ')' => Ok(Token::RParen),
             '{' => Ok(Token::LBrace),
             '}' => Ok(Token::RBrace),
-            '^' => Ok(Token::Caret),
-            '|' => Ok(Token::Pipe),
+            '^' => Ok(self.with_assign(Token::Caret, Token::CaretAssign)),
 
             // 处理字符串字面量("hello")
             '"' => self.read_string(), 
@@ -127,7 +170,12 @@ impl<'a> Lexer<'a> {
                 // 处理未知错误
                 } else {
                     // 处理未知字符，返回结构化错误
-                    let span = Span { line: start_line, column: start_col, start_byte: start_pos, end_byte: self.position };
+                    let span = Span {
+                        line: start_line,
+                        column: start_col,
+                        start_byte: start_pos,
+                        end_byte: start_pos + self.ch.len_utf8(),
+                    };
                     Err(LexerError::UnknownCharacter { char: self.ch, span })
                 }
             }
@@ -145,23 +193,44 @@ impl<'a> Lexer<'a> {
 
     // --- 辅助函数 ---
 
+    // 运算符之后紧跟 '=' 时是复合赋值（如 `+=`），消耗 '=' 并返回 `compound`，否则返回 `plain`。
+    // 进入时 self.ch 是运算符的最后一个字符
+    fn with_assign(&mut self, plain: Token, compound: Token) -> Token {
+        if self.peek_char() == '=' {
+            self.read_char();
+            compound
+        } else {
+            plain
+        }
+    }
+
     fn read_char(&mut self) {
-        let current_len = self.ch.len_utf8();
-        self.position += current_len;
-        
+        // 已经到达文件末尾，不再前进
         if self.position >= self.source.len() {
             self.ch = '\0';
             return;
         }
 
-        self.ch = self.source[self.position..].chars().next().unwrap_or('\0');
-
+        // 行列号描述的是 self.ch 的位置，所以越过换行符时换到下一行
         if self.ch == '\n' {
             self.line += 1;
             self.column = 1;
         } else {
             self.column += 1;
         }
+
+        self.position += self.ch.len_utf8();
+        self.ch = self.source[self.position..].chars().next().unwrap_or('\0');
+    }
+
+    // 根据记录下的起始位置，构造一个覆盖到当前位置（不含）的 Span
+    fn span_from(&self, start_pos: usize, start_line: u32, start_col: u32) -> Span {
+        Span {
+            line: start_line,
+            column: start_col,
+            start_byte: start_pos,
+            end_byte: self.position,
+        }
     }
 
     fn peek_char(&self) -> char {
@@ -173,26 +242,104 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    // 跳过所有的空白和单行注释
-    fn skip_whitespace_and_comments(&mut self) {
+    // 向前查看第 n 个字符（`peek_nth_char(1)` 等价于 `peek_char()`），超出文件末尾时返回 '\0'
+    fn peek_nth_char(&self, n: usize) -> char {
+        self.source[self.position..].chars().nth(n).unwrap_or('\0')
+    }
+
+    // 跳过所有的空白、单行注释和块注释
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), LexerError> {
         // 主循环开始
         loop {
             // 如果是空白就一直跳过
             if self.ch.is_whitespace() {
                 self.read_char();
 
-            // 如果检测到连续的两个'/'，说明是单行注释
-            } else if self.ch == '/' && self.peek_char() == '/' {
+            // 如果检测到连续的两个'/'，说明是单行注释（文档注释除外，它们会成为 Token）
+            } else if self.ch == '/' && self.peek_char() == '/' && !self.at_doc_comment() {
                 // 只要没有遇到换行和文件末尾，一直跳过
                 while self.ch != '\n' && self.ch != '\0' {
                     self.read_char();
                 }
+
+            // `/*` 开始一个块注释
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                self.skip_block_comment()?;
             
-            // 这里说明上面俩种情况都不是，逻辑走完了，loop结束
+            // 这里说明上面几种情况都不是，逻辑走完了，loop结束
             } else {
                 break;
             }
         }
+        Ok(())
+    }
+
+    // 当前是否位于一个文档注释的开头：`///` 或 `//!`。
+    // 与 Rust 相同，`////...` 这样四个及以上的斜杠被看作普通注释，方便用作分隔线
+    fn at_doc_comment(&self) -> bool {
+        if self.ch != '/' || self.peek_char() != '/' {
+            return false;
+        }
+        match self.peek_nth_char(2) {
+            '/' => self.peek_nth_char(3) != '/',
+            '!' => true,
+            _ => false,
+        }
+    }
+
+    // 读取一行文档注释，返回 DocComment 或 InnerDocComment。
+    // 结束时 self.ch 是行尾的换行符（或文件末尾），它会被 skip 正常跳过
+    fn read_doc_comment(&mut self) -> Token {
+        let is_inner = self.peek_nth_char(2) == '!';
+        // 消耗 `///` 或 `//!`
+        for _ in 0..3 {
+            self.read_char();
+        }
+
+        let content_start = self.position;
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
+        }
+        // 去掉 Windows 换行符中的 '\r'
+        let content = self.source[content_start..self.position].trim_end_matches('\r').to_string();
+
+        if is_inner {
+            Token::InnerDocComment(content)
+        } else {
+            Token::DocComment(content)
+        }
+    }
+
+    // 跳过一个块注释，进入时 self.ch 是开头 `/*` 的 '/'。
+    // 块注释可以嵌套：`/* a /* b */ c */` 是一个完整的注释，所以需要记录当前的嵌套深度
+    fn skip_block_comment(&mut self) -> Result<(), LexerError> {
+        let start_pos = self.position;
+        let start_line = self.line;
+        let start_col = self.column;
+
+        self.read_char(); // 消耗 '/'
+        self.read_char(); // 消耗 '*'
+        // 报错时只标出最外层的 `/*`
+        let start_span = self.span_from(start_pos, start_line, start_col);
+
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.ch, self.peek_char()) {
+                ('\0', _) => return Err(LexerError::UnterminatedBlockComment { start_span }),
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    self.read_char();
+                }
+                _ => self.read_char(),
+            }
+        }
+        Ok(())
     }
     
     // 在处理标识符中使用，读取一个标识符并转换成String
@@ -212,25 +359,23 @@ impl<'a> Lexer<'a> {
         let start_col = self.column;
         
         self.read_char(); // 消耗起始的 "
-        let content_start = self.position;
+        let mut content = String::new();
         
+        // 逐个字符读取内容，同时把转义序列替换为它所代表的字符
         while self.ch != '"' && self.ch != '\0' {
-            self.read_char();
+            if self.ch == '\\' {
+                content.push(self.read_escape()?);
+            } else {
+                content.push(self.ch);
+                self.read_char();
+            }
         }
         
         // 直接到结尾说明字符串未关闭
         if self.ch == '\0' {
-            let span = Span { 
-                line: start_line, 
-                column: start_col, 
-                start_byte: start_pos, 
-                end_byte: self.position 
-            };
+            let span = self.span_from(start_pos, start_line, start_col);
             return Err(LexerError::UnterminatedString { start_span: span });
         }
-        
-        // 截取字符串并转化为String
-        let content = self.source[content_start..self.position].to_string();
 
         // 能到这里就可以直接返回字面量了
         Ok(Token::Literal(Literal::String(content)))
@@ -243,17 +388,22 @@ impl<'a> Lexer<'a> {
         let start_col = self.column;
 
         self.read_char(); // 消耗起始的 '
-        let char_val = self.ch;
-        self.read_char(); // 消耗字符本身
+        let char_val = match self.ch {
+            '\\' => self.read_escape()?,
+            // `''` 中没有字符；遇到文件末尾说明字面量没有闭合
+            '\'' | '\0' => {
+                let span = self.span_from(start_pos, start_line, start_col);
+                return Err(LexerError::MalformedCharLiteral { span });
+            }
+            ch => {
+                self.read_char(); // 消耗字符本身
+                ch
+            }
+        };
 
         // 如果不是以'结尾，则说明出错了，需要记录
         if self.ch != '\'' {
-            let span = Span { 
-                line: start_line, 
-                column: start_col, 
-                start_byte: start_pos, 
-                end_byte: self.position 
-            };
+            let span = self.span_from(start_pos, start_line, start_col);
             return Err(LexerError::MalformedCharLiteral { span }); 
         }
         
@@ -261,59 +411,192 @@ impl<'a> Lexer<'a> {
         Ok(Token::Literal(Literal::Char(char_val)))
     }
 
+    // 读取字符串或字符字面量中的一个转义序列，返回它所代表的字符。
+    // 进入时 self.ch 是 '\\'，返回时 self.ch 是转义序列之后的第一个字符。
+    //
+    // 支持的转义：`\n` `\t` `\r` `\\` `\"` `\'` `\0`，以及 `\u{1F600}` 形式的 Unicode 转义（1 到 6 位十六进制数字）
+    fn read_escape(&mut self) -> Result<char, LexerError> {
+        let start_pos = self.position;
+        let start_line = self.line;
+        let start_col = self.column;
+
+        self.read_char(); // 消耗 '\\'
+        let escaped = match self.ch {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            '0' => '\0',
+            'u' => return self.read_unicode_escape(start_pos, start_line, start_col),
+            '\0' => {
+                let span = self.span_from(start_pos, start_line, start_col);
+                return Err(LexerError::MalformedEscape { reason: "Missing escape character".to_string(), span });
+            }
+            other => {
+                self.read_char(); // 让 Span 覆盖整个转义序列
+                let span = self.span_from(start_pos, start_line, start_col);
+                return Err(LexerError::MalformedEscape { reason: format!("Unknown character escape `\\{}`", other), span });
+            }
+        };
+        self.read_char(); // 消耗被转义的字符
+        Ok(escaped)
+    }
+
+    // 读取 `\u{...}` 的剩余部分。进入时 self.ch 是 'u'，start_* 是 '\\' 的位置
+    fn read_unicode_escape(&mut self, start_pos: usize, start_line: u32, start_col: u32) -> Result<char, LexerError> {
+        let malformed = |lexer: &Self, reason: &str| LexerError::MalformedEscape {
+            reason: reason.to_string(),
+            span: lexer.span_from(start_pos, start_line, start_col),
+        };
+
+        self.read_char(); // 消耗 'u'
+        if self.ch != '{' {
+            return Err(malformed(self, "Unicode escape must be written as `\\u{...}`"));
+        }
+        self.read_char(); // 消耗 '{'
+
+        let digits_start = self.position;
+        while self.ch.is_ascii_hexdigit() {
+            self.read_char();
+        }
+        let digits = &self.source[digits_start..self.position];
+
+        if self.ch != '}' {
+            return Err(malformed(self, "Unterminated unicode escape, expected `}`"));
+        }
+        self.read_char(); // 消耗 '}'
+
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(malformed(self, "Unicode escape must have 1 to 6 hex digits"));
+        }
+        // 代理码点 (0xD800..0xE000) 和大于 0x10FFFF 的值都不是合法的 Unicode 标量值
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| malformed(self, "Invalid unicode scalar value"))
+    }
+
     // 处理数字字面量，包含整数和浮点数
+    //
+    // 支持的写法：
+    // - 十进制整数和浮点数：`42`、`3.14`、`1e-9`、`2.5E3`
+    // - 带前缀的整数：`0x1F`（十六进制）、`0o17`（八进制）、`0b1010`（二进制）
+    // - 数字之间的 `_` 分隔符：`1_000_000`、`0xFF_FF`
+    // - 类型后缀：`10u8`、`3.0f32`、`1f64`。带 `f32` / `f64` 后缀的十进制整数是浮点数字面量
     fn read_number(&mut self) -> Result<Token, LexerError> {
         let start_pos = self.position;
         let start_line = self.line;
         let start_col = self.column;
 
-        while self.ch.is_ascii_digit() {
+        // 1. 进制前缀
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x') => 16,
+            ('0', 'o') => 8,
+            ('0', 'b') => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.read_char(); // 消耗 '0'
+            self.read_char(); // 消耗 'x' / 'o' / 'b'
+        }
+
+        // 2. 整数部分。二进制和八进制也先读入所有十进制数字，
+        // 这样 `0b102` 会作为一个整体报错，而不是被拆成 `0b10` 和 `2`。
+        let mut digits = String::new();
+        let is_radix_digit = |ch: char| if radix == 16 { ch.is_ascii_hexdigit() } else { ch.is_ascii_digit() };
+        while is_radix_digit(self.ch) || self.ch == '_' {
+            if self.ch != '_' {
+                digits.push(self.ch);
+            }
             self.read_char();
         }
 
-        // 处理浮点数
-        if self.ch == '.' && self.peek_char().is_ascii_digit() {
-            self.read_char(); // 消耗 '.'
-            while self.ch.is_ascii_digit() {
-                self.read_char();
+        // 3. 十进制数的小数部分和指数部分
+        let mut is_float = false;
+        if radix == 10 {
+            if self.ch == '.' && self.peek_char().is_ascii_digit() {
+                is_float = true;
+                digits.push('.');
+                self.read_char(); // 消耗 '.'
+                while self.ch.is_ascii_digit() || self.ch == '_' {
+                    if self.ch != '_' {
+                        digits.push(self.ch);
+                    }
+                    self.read_char();
+                }
             }
-            let num_str = &self.source[start_pos..self.position];
-            return match num_str.parse::<f64>() {
-                Ok(val) => Ok(Token::Literal(Literal::Float(val))),
-                Err(_) => {
-                    let span = Span { 
-                        line: start_line, 
-                        column: start_col, 
-                        start_byte: start_pos, 
-                        end_byte: self.position 
-                    };
-                    Err(
-                        LexerError::MalformedNumberLiteral { 
-                            reason: "Invalid float".to_string(), 
-                            span 
-                        }
-                    )
+
+            let has_exponent = matches!(self.ch, 'e' | 'E')
+                && (self.peek_char().is_ascii_digit()
+                    || (matches!(self.peek_char(), '+' | '-') && self.peek_nth_char(2).is_ascii_digit()));
+            if has_exponent {
+                is_float = true;
+                digits.push('e');
+                self.read_char(); // 消耗 'e'
+                if matches!(self.ch, '+' | '-') {
+                    digits.push(self.ch);
+                    self.read_char();
                 }
+                while self.ch.is_ascii_digit() || self.ch == '_' {
+                    if self.ch != '_' {
+                        digits.push(self.ch);
+                    }
+                    self.read_char();
+                }
+            }
+        }
+
+        // 4. 类型后缀，紧跟在数字后面的标识符字符都属于后缀
+        let suffix_start = self.position;
+        while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        let suffix = &self.source[suffix_start..self.position];
+        let suffix = if suffix.is_empty() { None } else { Some(suffix.to_string()) };
+
+        let malformed = |lexer: &Self, reason: String| LexerError::MalformedNumberLiteral {
+            reason,
+            span: lexer.span_from(start_pos, start_line, start_col),
+        };
+
+        if digits.is_empty() {
+            let prefix = &self.source[start_pos..start_pos + 2];
+            return Err(malformed(self, format!("Missing digits after `{}`", prefix)));
+        }
+
+        let is_float_suffix = matches!(suffix.as_deref(), Some("f32" | "f64"));
+        if let Some(suffix) = &suffix {
+            // 浮点后缀只能用于十进制数；整数后缀只能用于整数
+            let valid = if is_float_suffix { radix == 10 } else { !is_float && INTEGER_SUFFIXES.contains(&suffix.as_str()) };
+            if !valid {
+                let kind = if is_float { "float" } else { "integer" };
+                return Err(malformed(self, format!("Invalid suffix `{}` for {} literal", suffix, kind)));
+            }
+        }
+
+        // 处理浮点数
+        if is_float || is_float_suffix {
+            return match digits.parse::<f64>() {
+                Ok(val) => Ok(Token::Literal(Literal::Float(val, suffix))),
+                Err(_) => Err(malformed(self, "Invalid float".to_string())),
             };
         }
-        
+
         // 处理整数
-        let num_str = &self.source[start_pos..self.position];
-        match num_str.parse::<i64>() {
-            Ok(val) => Ok(Token::Literal(Literal::Integer(val))),
+        match u64::from_str_radix(&digits, radix) {
+            Ok(val) => Ok(Token::Literal(Literal::Integer(val, suffix))),
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                Err(malformed(self, "Integer literal is too large to fit into 64 bits".to_string()))
+            }
             Err(_) => {
-                 let span = Span { 
-                    line: start_line, 
-                    column: start_col, 
-                    start_byte: start_pos, 
-                    end_byte: self.position 
+                let kind = match radix {
+                    2 => "a binary",
+                    8 => "an octal",
+                    _ => "a decimal",
                 };
-                Err(
-                    LexerError::MalformedNumberLiteral { 
-                        reason: "Invalid integer".to_string(), 
-                        span 
-                    }
-                )
+                Err(malformed(self, format!("Invalid digit for {} literal", kind)))
             }
         }
     }
@@ -354,6 +637,7 @@ fn lookup_ident(ident: &str) -> Token {
         "new" => Keyword::New,
         "free" => Keyword::Free,
         "None" => Keyword::None,
+        "as" => Keyword::As,
 
         // 如果不是以上任何关键字，它就是一个普通的标识符，提前返回
         _ => return Token::Identifier(ident.to_string()),
@@ -363,3 +647,220 @@ fn lookup_ident(ident: &str) -> Token {
     Token::Keyword(keyword)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 读取全部 Token（不含 Eof），遇到第一个错误时返回它
+    fn lex(source: &str) -> Result<Vec<Token>, LexerError> {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token()?.token {
+                Token::Eof => return Ok(tokens),
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    fn int(value: u64, suffix: Option<&str>) -> Token {
+        Token::Literal(Literal::Integer(value, suffix.map(str::to_string)))
+    }
+
+    fn float(value: f64, suffix: Option<&str>) -> Token {
+        Token::Literal(Literal::Float(value, suffix.map(str::to_string)))
+    }
+
+    // 断言源码是一个格式错误的数字字面量，并返回错误原因
+    fn malformed_number(source: &str) -> String {
+        match lex(source) {
+            Err(LexerError::MalformedNumberLiteral { reason, .. }) => reason,
+            other => panic!("expected a malformed number literal for `{}`, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn integer_literals_in_every_radix() {
+        assert_eq!(lex("42").unwrap(), vec![int(42, None)]);
+        assert_eq!(lex("0x1F").unwrap(), vec![int(0x1F, None)]);
+        assert_eq!(lex("0o17").unwrap(), vec![int(0o17, None)]);
+        assert_eq!(lex("0b1010").unwrap(), vec![int(0b1010, None)]);
+        assert_eq!(lex("0").unwrap(), vec![int(0, None)]);
+    }
+
+    #[test]
+    fn underscores_separate_digits() {
+        assert_eq!(lex("1_000_000").unwrap(), vec![int(1_000_000, None)]);
+        assert_eq!(lex("0xFF_FF").unwrap(), vec![int(0xFFFF, None)]);
+        assert_eq!(lex("0b_1_0").unwrap(), vec![int(2, None)]);
+        assert_eq!(lex("1_0.2_5").unwrap(), vec![float(10.25, None)]);
+    }
+
+    #[test]
+    fn float_literals_with_fraction_and_exponent() {
+        assert_eq!(lex("2.75").unwrap(), vec![float(2.75, None)]);
+        assert_eq!(lex("1e-9").unwrap(), vec![float(1e-9, None)]);
+        assert_eq!(lex("2.5E3").unwrap(), vec![float(2500.0, None)]);
+        assert_eq!(lex("1e+2").unwrap(), vec![float(100.0, None)]);
+    }
+
+    #[test]
+    fn type_suffixes() {
+        assert_eq!(lex("10u8").unwrap(), vec![int(10, Some("u8"))]);
+        assert_eq!(lex("1_000i64").unwrap(), vec![int(1000, Some("i64"))]);
+        assert_eq!(lex("0xFFusize").unwrap(), vec![int(0xFF, Some("usize"))]);
+        assert_eq!(lex("3.0f32").unwrap(), vec![float(3.0, Some("f32"))]);
+        // 带浮点后缀的十进制整数是浮点数
+        assert_eq!(lex("1f64").unwrap(), vec![float(1.0, Some("f64"))]);
+        // 十六进制中 `f` 是数字，所以 `0x1f32` 是一个没有后缀的整数
+        assert_eq!(lex("0x1f32").unwrap(), vec![int(0x1f32, None)]);
+    }
+
+    #[test]
+    fn invalid_suffixes_are_rejected() {
+        assert_eq!(malformed_number("10u7"), "Invalid suffix `u7` for integer literal");
+        assert_eq!(malformed_number("1.5u8"), "Invalid suffix `u8` for float literal");
+        assert_eq!(malformed_number("0b1f32"), "Invalid suffix `f32` for integer literal");
+    }
+
+    #[test]
+    fn exponent_without_digits_is_an_invalid_suffix() {
+        assert_eq!(malformed_number("1e"), "Invalid suffix `e` for integer literal");
+        assert_eq!(malformed_number("1e+"), "Invalid suffix `e` for integer literal");
+    }
+
+    #[test]
+    fn radix_prefix_without_digits() {
+        assert_eq!(malformed_number("0x"), "Missing digits after `0x`");
+        assert_eq!(malformed_number("0x_"), "Missing digits after `0x`");
+        assert_eq!(malformed_number("0b"), "Missing digits after `0b`");
+    }
+
+    #[test]
+    fn invalid_digits_for_radix() {
+        assert_eq!(malformed_number("0b102"), "Invalid digit for a binary literal");
+        assert_eq!(malformed_number("0o78"), "Invalid digit for an octal literal");
+    }
+
+    #[test]
+    fn integer_overflow_past_u64() {
+        assert_eq!(lex("18446744073709551615").unwrap(), vec![int(u64::MAX, None)]);
+        assert_eq!(lex("0xFFFF_FFFF_FFFF_FFFF").unwrap(), vec![int(u64::MAX, None)]);
+        assert_eq!(malformed_number("18446744073709551616"), "Integer literal is too large to fit into 64 bits");
+        assert_eq!(malformed_number("0x1_0000_0000_0000_0000"), "Integer literal is too large to fit into 64 bits");
+    }
+
+    #[test]
+    fn malformed_number_span_covers_the_whole_literal() {
+        let Err(LexerError::MalformedNumberLiteral { span, .. }) = lex("x := 0b102u8;") else {
+            panic!("expected a malformed number literal");
+        };
+        assert_eq!((span.line, span.column, span.start_byte, span.end_byte), (1, 6, 5, 12));
+    }
+
+    fn string(value: &str) -> Token {
+        Token::Literal(Literal::String(value.to_string()))
+    }
+
+    fn char_literal(value: char) -> Token {
+        Token::Literal(Literal::Char(value))
+    }
+
+    // 断言源码中有一个格式错误的转义序列，返回错误原因和它覆盖的字节范围
+    fn malformed_escape(source: &str) -> (String, (usize, usize)) {
+        match lex(source) {
+            Err(LexerError::MalformedEscape { reason, span }) => (reason, (span.start_byte, span.end_byte)),
+            other => panic!("expected a malformed escape for `{}`, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn escapes_in_strings() {
+        assert_eq!(lex(r#""a\n\t\r\\\"\'\0b""#).unwrap(), vec![string("a\n\t\r\\\"'\0b")]);
+        assert_eq!(lex(r#""\u{41}\u{1F600}""#).unwrap(), vec![string("A\u{1F600}")]);
+    }
+
+    #[test]
+    fn escapes_in_char_literals() {
+        let escapes = [
+            (r"'\n'", '\n'),
+            (r"'\t'", '\t'),
+            (r"'\r'", '\r'),
+            (r"'\\'", '\\'),
+            (r#"'\"'"#, '"'),
+            (r"'\''", '\''),
+            (r"'\0'", '\0'),
+            (r"'\u{1F600}'", '\u{1F600}'),
+        ];
+        for (source, value) in escapes {
+            assert_eq!(lex(source).unwrap(), vec![char_literal(value)], "{}", source);
+        }
+    }
+
+    #[test]
+    fn malformed_escapes() {
+        // 错误覆盖从 `\` 开始的整个转义序列
+        assert_eq!(malformed_escape(r#""a\qb""#), ("Unknown character escape `\\q`".to_string(), (2, 4)));
+        assert_eq!(malformed_escape(r"'\u{}'").0, "Unicode escape must have 1 to 6 hex digits");
+        assert_eq!(malformed_escape(r"'\u{1234567}'").0, "Unicode escape must have 1 to 6 hex digits");
+        assert_eq!(malformed_escape(r"'\u{D800}'"), ("Invalid unicode scalar value".to_string(), (1, 9)));
+        assert_eq!(malformed_escape(r"'\u{110000}'").0, "Invalid unicode scalar value");
+        assert_eq!(malformed_escape(r"'\u41'").0, "Unicode escape must be written as `\\u{...}`");
+        assert_eq!(malformed_escape(r"'\u{41'").0, "Unterminated unicode escape, expected `}`");
+    }
+
+    #[test]
+    fn empty_or_unterminated_char_literal() {
+        assert!(matches!(lex("''"), Err(LexerError::MalformedCharLiteral { .. })));
+        let Err(LexerError::MalformedCharLiteral { span }) = lex("'a") else {
+            panic!("expected a malformed char literal");
+        };
+        assert_eq!((span.start_byte, span.end_byte), (0, 2));
+        assert!(matches!(lex("'ab'"), Err(LexerError::MalformedCharLiteral { .. })));
+    }
+
+    // 断言源码中有一个没有闭合的块注释，并返回它开头的 Span
+    fn unterminated_comment(source: &str) -> Span {
+        match lex(source) {
+            Err(LexerError::UnterminatedBlockComment { start_span }) => start_span,
+            other => panic!("expected an unterminated block comment for `{}`, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn block_comments_are_skipped() {
+        assert_eq!(lex("/* comment */ x").unwrap(), vec![Token::Identifier("x".to_string())]);
+        assert_eq!(lex("a /* one\ntwo */ b").unwrap(), vec![
+            Token::Identifier("a".to_string()),
+            Token::Identifier("b".to_string()),
+        ]);
+        assert_eq!(lex("/**/").unwrap(), vec![]);
+        assert_eq!(lex("/* **/ 1").unwrap(), vec![int(1, None)]);
+    }
+
+    #[test]
+    fn block_comments_nest() {
+        assert_eq!(lex("/* outer /* inner */ still outer */ x").unwrap(), vec![Token::Identifier("x".to_string())]);
+        assert_eq!(lex("/* /* /* */ */ */ 1").unwrap(), vec![int(1, None)]);
+    }
+
+    #[test]
+    fn line_comment_inside_block_comment_does_not_hide_the_end() {
+        assert_eq!(lex("/* // */ x").unwrap(), vec![Token::Identifier("x".to_string())]);
+    }
+
+    #[test]
+    fn unterminated_block_comments() {
+        assert_eq!(unterminated_comment("/*").column, 1);
+        assert_eq!(unterminated_comment("/* never closed").column, 1);
+        // `/*/` 中的 `*` 属于开头的 `/*`，不能再用作结尾
+        assert_eq!(unterminated_comment("/*/").column, 1);
+    }
+
+    #[test]
+    fn unclosed_inner_comment_leaves_the_outer_one_open() {
+        // 内层的 `*/` 只闭合了内层注释，报错指向最外层的 `/*`
+        let span = unterminated_comment("x\n  /* /* */");
+        assert_eq!((span.line, span.column, span.start_byte, span.end_byte), (2, 3, 4, 6));
+    }
+}
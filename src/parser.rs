@@ -4,7 +4,7 @@
 // 每个 `use` 块都解释了其引入的模块的职责。
 
 // 引入诊断模块，用于创建和收集结构化的错误信息。
-use crate::diagnostics::{CompilerError, ParserError, Span}; 
+use crate::diagnostics::{CompilerError, LexerError, ParserError, Span};
 
 // 引入抽象语法树 (AST) 模块。
 // 解析器的最终目标就是将 Token 流转换成这些结构化的 AST 节点。
@@ -12,6 +12,7 @@ use crate::ast::{
     // --- 顶层结构 ---
     Program,
     TopLevelStatement,
+    NodeId,
 
     // --- 语句 (Statements) ---
     Statement,
@@ -28,8 +29,11 @@ use crate::ast::{
     InfixExpression,
     AssignmentExpression,
     CallExpression,
+    CastExpression,
     IfExpression,
     LoopExpression,
+    IdentifierExpression,
+    LiteralExpression,
     
     // --- 运算符 ---
     Operator,
@@ -44,7 +48,7 @@ use crate::ast::{
 use crate::lexer::Lexer;
 
 // 引入 Token 定义，这是 Parser 直接消费的基本单元。
-use crate::token::{Token, Keyword, Literal}; 
+use crate::token::{Token, Keyword, Literal, SpannedToken};
 
 /// 定义了 Tipy 语言中运算符的优先级。
 ///
@@ -59,12 +63,28 @@ pub enum Precedence {
     Lowest,
     /// 赋值表达式的优先级, e.g., `x = y`
     Assign,
+    /// 逻辑或表达式的优先级, e.g., `x || y`
+    LogicalOr,
+    /// 逻辑与表达式的优先级, e.g., `x && y`。高于 `||`：`a || b && c` 等价于 `a || (b && c)`。
+    LogicalAnd,
     /// 比较表达式的优先级, e.g., `x == y`, `x > y`
     Comparison,
+    /// 按位或表达式的优先级, e.g., `x | y`
+    BitOr,
+    /// 按位异或表达式的优先级, e.g., `x ^ y`
+    BitXor,
+    /// 按位与表达式的优先级, e.g., `x & y`。
+    /// 与 Rust 相同，位运算的优先级高于比较：`x & 1 == 0` 等价于 `(x & 1) == 0`。
+    BitAnd,
+    /// 移位表达式的优先级, e.g., `x << 2`
+    Shift,
     /// 加减法表达式的优先级, e.g., `x + y`
     Sum,
-    /// 乘除法表达式的优先级, e.g., `x * y`
+    /// 乘除法与取余表达式的优先级, e.g., `x * y`, `x % y`
     Product,
+    /// 类型转换表达式的优先级, e.g., `x as f64`。
+    /// 高于乘除法，低于前缀运算：`-x as u8` 等价于 `(-x) as u8`。
+    Cast,
     /// 前缀表达式的优先级, e.g., `-x`, `!y`
     Prefix,
     /// 函数调用表达式的优先级, e.g., `my_func(x)`
@@ -83,9 +103,34 @@ pub struct Parser<'a> {
     /// 当前正在处理的 Token。解析逻辑的判断依据。
     current_token: Token,
 
+    /// `current_token` 在源码中的位置。
+    current_span: Span,
+
     /// 下一个即将被处理的 Token。Pratt 解析器和许多其他解析策略
     /// 都需要它来决定当前的操作（例如，一个 `+` 后面是数字还是括号）。
     peek_token: Token,
+
+    /// `peek_token` 在源码中的位置。
+    peek_span: Span,
+
+    /// 紧贴在 `current_token` 之前的 `///` 文档注释。
+    ///
+    /// 文档注释不参与语法，`next_token` 会把它们从 Token 流中取出，
+    /// 挂在其后的第一个 Token 上，由声明的解析函数按需取走。
+    current_docs: Vec<String>,
+
+    /// 紧贴在 `peek_token` 之前的 `///` 文档注释。
+    peek_docs: Vec<String>,
+
+    /// 目前为止遇到的所有 `//!` 内部文档注释，最终成为 `Program::docs`。
+    inner_docs: Vec<String>,
+
+    /// 词法分析器是否已经报告过错误。
+    /// 一旦出错，Token 流就不再可信，之后 `peek_token` 将一直是 `Eof`。
+    lexer_failed: bool,
+
+    /// 下一个 AST 节点将得到的编号，见 `NodeId`。
+    next_node_id: u32,
     
     /// 错误收集器。
     ///
@@ -106,13 +151,20 @@ impl<'a> Parser<'a> {
     /// # Arguments
     ///
     /// * `lexer` - 一个已经初始化好的 `Lexer` 实例。
-    pub fn new(mut lexer: Lexer<'a>) -> Self {
+    pub fn new(lexer: Lexer<'a>) -> Self {
         // 先创建一个包含 lexer 和空错误列表的 "半成品" Parser
         let mut p = Parser {
             lexer,
             // 暂时用 Eof 占位，将立即调用 next_token 来填充它们
             current_token: Token::Eof,
+            current_span: Span::default(),
             peek_token: Token::Eof,
+            peek_span: Span::default(),
+            current_docs: Vec::new(),
+            peek_docs: Vec::new(),
+            inner_docs: Vec::new(),
+            lexer_failed: false,
+            next_node_id: 0,
             errors: Vec::new(),
         };
 
@@ -133,8 +185,8 @@ impl<'a> Parser<'a> {
     /// - 当 `parse_top_level_statement` 成功时，它会将结果添加到程序体中。
     /// - 当遇到一个解析错误 (`Err`) 时，它不会立即停止，而是：
     ///   1. 将错误记录到 `self.errors` 向量中。
-    ///   2. 调用 `self.synchronize()` 来尝试跳过出错的 Token，找到下一个
-    ///      可能安全的同步点（如一个新的函数声明），然后继续解析。
+    ///   2. 调用 `self.synchronize_top_level()` 来跳过出错的 Token，找到下一个
+    ///      函数声明的开头，然后继续解析。
     ///
     /// 这种机制允许我们一次性报告多个解析错误，极大地提升了用户体验，
     /// 并且从根本上解决了旧代码中的无限循环问题。
@@ -143,52 +195,83 @@ impl<'a> Parser<'a> {
 
         while !self.current_token_is(&Token::Eof) {
             match self.parse_top_level_statement() {
-                Ok(stmt) => program.body.push(stmt),
+                Ok(stmt) => {
+                    program.body.push(stmt);
+                    // 顶层声明结束时停在它的最后一个 Token（函数体的 `}`）上，跳过它
+                    self.next_token();
+                }
                 Err(err) => {
                     // NEW: 集成新的诊断系统
-                    self.errors.push(CompilerError::Parser(err));
+                    self.record_error(err);
                     // NEW: 调用错误恢复机制，防止无限循环
-                    self.synchronize();
+                    self.synchronize_top_level();
                 }
             }
         }
+        program.docs = std::mem::take(&mut self.inner_docs);
         program
     }
 
     // --- 内部辅助与错误处理 (Internal Helpers & Error Handling) ---
 
-    /// 错误恢复函数，用于在解析失败后寻找下一个安全的同步点。
+    /// 语句级的错误恢复函数，用于在代码块内某条语句解析失败后寻找下一个安全的同步点。
     ///
-    /// 这是防止无限循环并能一次性报告多个错误的关键。当一个解析函数
-    /// 返回 `Err` 后，主循环会调用此函数。
+    /// 这是防止无限循环并能一次性报告多个错误的关键。
     ///
     /// 它的策略是：
-    /// 1. 至少消耗掉引发错误的当前 Token。
-    /// 2. 不断向前移动，直到找到一个被认为是新语句开始的标志
-    ///    （如分号 `;`，或 `ret`, `if` 等关键字）。
+    /// 1. 除非出错的 Token 本身就是 `}` 或 `Eof`，至少消耗掉它。
+    /// 2. 不断向前移动，直到找到一个被认为是新语句开始的标志：
+    ///    分号 `;` 之后的 Token，或 `ret`, `if` 等关键字。
+    /// 3. 绝不越过 `}`，它属于外层代码块，由 `parse_block_statement` 处理。
+    ///
+    /// 返回时 `current_token` 停在下一条语句的第一个 Token（或 `}` / `Eof`）上。
     fn synchronize(&mut self) {
-        self.next_token(); // 至少消耗掉引发错误的 token
+        loop {
+            match self.current_token {
+                Token::Eof | Token::RBrace => return,
+                Token::Semicolon => {
+                    self.next_token();
+                    return;
+                }
+                _ => {}
+            }
 
-        while !self.current_token_is(&Token::Eof) {
-            // 如果上一个 token 是分号，那么我们很可能在一个新语句的开头，可以安全退出。
-            if self.current_token_is(&Token::Semicolon) {
-                self.next_token();
+            self.next_token();
+
+            // 如果当前 token 是一个常见的语句起始关键字，我们也可以认为找到了同步点。
+            if let Token::Keyword(
+                Keyword::Ret      |
+                Keyword::If       |
+                Keyword::Loop     |
+                Keyword::While    |
+                Keyword::Break    |
+                Keyword::Continue
+            ) = self.current_token {
                 return;
             }
+        }
+    }
 
-            // 如果下一个 token 是一个常见的语句起始关键字，我们也可以认为找到了同步点。
-            match self.peek_token {
-                Token::Keyword(
-                    Keyword::Class | 
-                    Keyword::Ret   | 
-                    Keyword::If    | 
-                    Keyword::Loop  | 
-                    Keyword::While
-                ) => return,
+    /// 顶层的错误恢复函数：跳过出错的函数声明剩余的部分，
+    /// 停在下一个函数声明的开头（`name(`）或文件末尾。
+    ///
+    /// 通过记录花括号的嵌套深度，函数体内部形如 `foo(` 的调用不会被误认为新的函数声明。
+    fn synchronize_top_level(&mut self) {
+        let mut depth = 0usize;
+        while !self.current_token_is(&Token::Eof) {
+            match self.current_token {
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth = depth.saturating_sub(1),
                 _ => {}
             }
-            
             self.next_token();
+
+            if depth == 0
+                && self.current_token_is(&Token::Identifier("".into()))
+                && self.peek_token_is(&Token::LParen)
+            {
+                return;
+            }
         }
     }
 
@@ -206,10 +289,17 @@ impl<'a> Parser<'a> {
             self.next_token();
             Ok(())
         } else {
-            Err(self.peek_error(format!("Expected next token to be {:?}", expected)))
+            Err(self.peek_error(expected.to_string()))
         }
     }
 
+    /// 为一个新创建的 AST 节点分配编号。
+    fn new_node_id(&mut self) -> NodeId {
+        let id = NodeId(self.next_node_id);
+        self.next_node_id += 1;
+        id
+    }
+
     /// 检查当前 Token (`current_token`) 是否为指定的类型。
     fn current_token_is(&self, token_type: &Token) -> bool {
         // 使用 std::mem::discriminant 来比较 enum 的变体，而不关心其内部的值。
@@ -237,36 +327,56 @@ impl<'a> Parser<'a> {
     /// 注意：只有作为中缀运算符的 Token 才有高于 `Lowest` 的优先级。
     fn token_to_precedence(token: &Token) -> Precedence {
         match token {
-            Token::Assign => Precedence::Assign,
+            Token::Assign | Token::PlusAssign | Token::MinusAssign | Token::StarAssign | Token::SlashAssign |
+            Token::PercentAssign | Token::AmpersandAssign | Token::PipeAssign | Token::CaretAssign |
+            Token::ShiftLeftAssign | Token::ShiftRightAssign => Precedence::Assign,
+            Token::LogicalOr => Precedence::LogicalOr,
+            Token::LogicalAnd => Precedence::LogicalAnd,
             Token::Equal | Token::NotEqual | Token::LessThan | Token::GreaterThan |
             Token::LessEqual | Token::GreaterEqual => Precedence::Comparison,
+            // `|` 和 `^` 在类型中另有含义（枚举变体、指针），但类型由 `parse_type_string` 单独解析，
+            // 出现在这里时一定处于中缀位置，只可能是位运算符
+            Token::Pipe => Precedence::BitOr,
+            Token::Caret => Precedence::BitXor,
+            Token::Ampersand => Precedence::BitAnd,
+            Token::ShiftLeft | Token::ShiftRight => Precedence::Shift,
             Token::Plus | Token::Minus => Precedence::Sum,
-            Token::Star | Token::Slash => Precedence::Product,
+            Token::Star | Token::Slash | Token::Percent => Precedence::Product,
+            Token::Keyword(Keyword::As) => Precedence::Cast,
             Token::LParen => Precedence::Call,
             _ => Precedence::Lowest,
         }
     }
     
     // --- 错误创建辅助函数 ---
+
+    /// 记录一个语法错误。
+    ///
+    /// 词法错误之后的 `Eof` 是人为补上的，因 "遇到了 Eof" 而产生的语法错误
+    /// 只是词法错误的连锁反应，不再重复报告。
+    fn record_error(&mut self, err: ParserError) {
+        if self.lexer_failed && matches!(err, ParserError::UnexpectedEof { .. }) {
+            return;
+        }
+        self.errors.push(CompilerError::Parser(err));
+    }
+
+    /// 根据找到的 Token 创建错误：遇到文件末尾时报告 `UnexpectedEof`，否则报告 `UnexpectedToken`。
+    fn unexpected(expected: String, found: &Token, span: Span) -> ParserError {
+        match found {
+            Token::Eof => ParserError::UnexpectedEof { expected, span },
+            _ => ParserError::UnexpectedToken { expected, found: found.clone(), span },
+        }
+    }
     
     /// 根据当前 Token (`current_token`) 创建一个 `ParserError`。
     fn current_error(&self, message: String) -> ParserError {
-        ParserError::UnexpectedToken {
-            expected: message,
-            found: self.current_token.clone(),
-            // TODO: 当 Token 携带 Span 信息后，在这里传递真实的 Span。
-            span: Span::default(), 
-        }
+        Self::unexpected(message, &self.current_token, self.current_span)
     }
     
     /// 根据下一个 Token (`peek_token`) 创建一个 `ParserError`。
     fn peek_error(&self, message: String) -> ParserError {
-        ParserError::UnexpectedToken {
-            expected: message,
-            found: self.peek_token.clone(),
-            // TODO: 当 Token 携带 Span 信息后，在这里传递真实的 Span。
-            span: Span::default(),
-        }
+        Self::unexpected(message, &self.peek_token, self.peek_span)
     }
 
     // --- 顶层与声明解析 (Top-Level & Declaration Parsing) ---
@@ -288,13 +398,17 @@ impl<'a> Parser<'a> {
         }
         
         // 如果不满足以上条件，则报告一个错误。
-        Err(self.current_error("Expected a function declaration".to_string()))
+        Err(self.current_error("a function declaration".to_string()))
     }
 
     /// 解析一个完整的函数声明。
     ///
     /// e.g., `my_func(a: i32, b: i32) -> i32 { ... }`
     fn parse_function_declaration(&mut self) -> Result<FunctionDeclaration, ParserError> {
+        let start = self.current_span;
+        // 函数名之前的文档注释属于这个函数
+        let docs = std::mem::take(&mut self.current_docs);
+
         // 1. 解析函数名
         let name = self.parse_identifier_string()?;
         
@@ -307,23 +421,21 @@ impl<'a> Parser<'a> {
         let return_type = if self.peek_token_is(&Token::Arrow) {
             self.next_token(); // 消耗 '->'
             self.next_token(); // 前进到类型标识符
-            self.parse_identifier_string()?
+            self.parse_type_string()?
         } else {
             // 如果没有 '->'，则为隐式 void 返回
             "void".to_string()
         };
+        // 函数签名的范围止于 `)` 或返回类型
+        let span = start.to(self.current_span);
 
         // 4. 解析函数体
         self.expect_peek(&Token::LBrace)?;
-        let body = self.parse_block_statement()?; // 我们将在下一步实现此函数
-        
-        // `parse_block_statement` 结束时，`current_token` 应该是 '}'
-        // 注意：我们在这里不消耗最后的 '}'，因为 Tipy 的块表达式特性
-        // 意味着 `{...}` 本身可以是一个表达式，其调用者需要 '}' 作为结束标志。
-        // 但对于函数声明，它的主体是一个语句块，通常需要消耗掉。这是一个需要仔细考虑的设计点。
-        // 为保持一致性，我们暂定由 `parse_block_statement` 的调用者负责处理 `{` 和 `}`。
-        
-        Ok(FunctionDeclaration { name, params, return_type, body })
+        let body = self.parse_block_statement()?;
+        // `parse_block_statement` 结束时，`current_token` 是函数体的 '}'，
+        // 由 `parse_program` 负责越过它。
+
+        Ok(FunctionDeclaration { name, params, return_type, body, span, docs })
     }
 
     /// 解析函数声明中的参数列表 `(p1: T1, p2: T2, ...)`
@@ -340,12 +452,26 @@ impl<'a> Parser<'a> {
 
         // 循环解析每个参数
         loop {
+            let start = self.current_span;
             let param_name = self.parse_identifier_string()?;
             self.expect_peek(&Token::Colon)?;
-            self.next_token(); // 消耗 ':'，前进到类型名
-            let param_type = self.parse_identifier_string()?;
+            self.next_token(); // 消耗 ':'，前进到类型名或 '~'
+
+            // 与变量声明一样，参数默认不可变，`~` 将其标记为可变
+            let is_mutable = if self.current_token_is(&Token::Tilde) {
+                self.next_token(); // 消耗 '~'
+                true
+            } else {
+                false
+            };
+            let param_type = self.parse_type_string()?;
             
-            params.push(FunctionParameter { name: param_name, param_type });
+            params.push(FunctionParameter {
+                name: param_name,
+                is_mutable,
+                param_type,
+                span: start.to(self.current_span),
+            });
             
             // 检查下一个 Token，决定是继续循环还是结束
             if !self.peek_token_is(&Token::Comma) {
@@ -386,43 +512,60 @@ impl<'a> Parser<'a> {
     /// 解析一个代码块 `{ ... }`。
     ///
     /// # 解析约定
-    /// - **调用者**负责消耗起始的 `{`。
-    /// - 此函数会持续解析内部的语句，直到遇到 `}` 或文件末尾 `Eof`。
-    /// - 此函数**不会**消耗最后的 `}`，将其留给调用者处理。
-    ///   这对于将代码块作为表达式（其值是最后一个表达式）的场景至关重要。
+    /// - 进入时 `current_token` 必须是起始的 `{`，此函数会消耗它。
+    /// - 此函数会持续解析内部的语句，直到遇到 `}`。如果先遇到了文件末尾 `Eof`，
+    ///   则报告缺少 `}` 的错误。
+    /// - 此函数**不会**越过最后的 `}`：返回时 `current_token` 正停在 `}` 上。
+    ///   这与其他解析函数“停在所解析结构的最后一个 Token 上”的约定一致，
+    ///   使得代码块可以直接作为表达式（其值是最后一个表达式）嵌入 Pratt 解析循环。
     ///
     /// # 错误恢复
     /// 这是解析器内第二个实现错误恢复循环的地方。如果块内某条语句解析失败，
     /// 它会记录错误，调用 `synchronize()` 跳到下一个安全点，然后继续解析块内的
     /// 其他语句，而不是让整个代码块的解析失败。
     fn parse_block_statement(&mut self) -> Result<BlockStatement, ParserError> {
+        let start = self.current_span;
+        self.next_token(); // 消耗 '{'
         let mut statements = Vec::new();
 
         while !self.current_token_is(&Token::RBrace) && !self.current_token_is(&Token::Eof) {
             match self.parse_statement() {
-                Ok(stmt) => statements.push(stmt),
+                Ok(stmt) => {
+                    statements.push(stmt);
+                    // 语句解析结束时停在它的最后一个 Token 上（在 Tipy 中分号是可选的，
+                    // 如果有分号，它已经被语句本身消耗），前进到下一条语句的开头。
+                    self.next_token();
+                }
                 Err(err) => {
-                    self.errors.push(CompilerError::Parser(err));
+                    self.record_error(err);
+                    // `synchronize` 已经停在下一条语句的开头
                     self.synchronize();
                 }
             }
-            // 在 Tipy 中分号是可选的，我们统一在循环末尾处理 Token 前进，
-            // 无论语句后面有没有分号。
-            self.next_token();
         }
-    
-        Ok(BlockStatement { statements })
+
+        if self.current_token_is(&Token::Eof) {
+            return Err(self.current_error("`}` to close the block".to_string()));
+        }
+
+        Ok(BlockStatement {
+            statements,
+            span: start.to(self.current_span),
+            id: self.new_node_id(),
+            closing_brace: self.current_span,
+        })
     }
     
     /// 解析返回语句 `ret <expression>;`
     fn parse_return_statement(&mut self) -> Result<Statement, ParserError> {
-        self.next_token(); // 消耗 `ret` 关键字
+        let start = self.current_span;
 
-        let value = if self.current_token_is(&Token::Semicolon) || self.current_token_is(&Token::RBrace) {
-            // 处理 `ret;` 或紧跟 `}` 的 `ret`
+        let value = if self.peek_token_is(&Token::Semicolon) || self.peek_token_is(&Token::RBrace) {
+            // 处理 `ret;` 或紧跟 `}` 的 `ret`。注意不能越过 `}`，它属于外层代码块。
             None
         } else {
             // 解析 `ret <expression>`
+            self.next_token(); // 消耗 `ret` 关键字
             Some(self.parse_expression(Precedence::Lowest)?)
         };
         
@@ -431,12 +574,15 @@ impl<'a> Parser<'a> {
             self.next_token();
         }
 
-        Ok(Statement::Return(ReturnStatement { value }))
+        Ok(Statement::Return(ReturnStatement { value, span: start.to(self.current_span) }))
     }
 
     /// 解析变量声明语句 `name: [~]type [= value];`
+    ///
+    /// 有初始值时可以省略类型，由语义分析推断：`x := 10;`，`y: ~ = f();`
     fn parse_variable_declaration_statement(&mut self) -> Result<Statement, ParserError> {
         // `parse_statement` 已经确认了当前是 Identifier
+        let start = self.current_span;
         let name = self.parse_identifier_string()?;
         
         self.expect_peek(&Token::Colon)?; // 消耗 ':'
@@ -449,14 +595,20 @@ impl<'a> Parser<'a> {
             false
         };
 
-        let var_type = self.parse_identifier_string()?;
-        
-        let value = if self.peek_token_is(&Token::Assign) {
-            self.next_token(); // 消耗类型, 前进到 '='
+        let (var_type, value) = if self.current_token_is(&Token::Assign) {
+            // 省略了类型，此时初始值是必需的
             self.next_token(); // 消耗 '=', 前进到表达式的开头
-            Some(self.parse_expression(Precedence::Lowest)?)
+            (None, Some(self.parse_expression(Precedence::Lowest)?))
         } else {
-            None // 没有初始值
+            let var_type = self.parse_type_string()?;
+            let value = if self.peek_token_is(&Token::Assign) {
+                self.next_token(); // 消耗类型, 前进到 '='
+                self.next_token(); // 消耗 '=', 前进到表达式的开头
+                Some(self.parse_expression(Precedence::Lowest)?)
+            } else {
+                None // 没有初始值
+            };
+            (Some(var_type), value)
         };
 
         // 同样，消耗可选的分号
@@ -464,7 +616,14 @@ impl<'a> Parser<'a> {
             self.next_token();
         }
 
-        Ok(Statement::VarDeclaration(VarDeclaration { name, is_mutable, var_type, value }))
+        Ok(Statement::VarDeclaration(VarDeclaration {
+            name,
+            is_mutable,
+            var_type,
+            value,
+            span: start.to(self.current_span),
+            id: self.new_node_id(),
+        }))
     }
 
     /// 解析一个表达式语句。
@@ -485,25 +644,26 @@ impl<'a> Parser<'a> {
 
     /// 解析 `while` 循环语句 `while <condition> { ... }`
     fn parse_while_statement(&mut self) -> Result<Statement, ParserError> {
+        let start = self.current_span;
         self.next_token(); // 消耗 `while`
         
         let condition = self.parse_expression(Precedence::Lowest)?;
         
         self.expect_peek(&Token::LBrace)?;
         let body = self.parse_block_statement()?;
-        // parse_block_statement 不消耗 '}'，所以我们在这里消耗
-        self.expect_peek(&Token::RBrace)?;
-        
-        Ok(Statement::While(WhileStatement { condition, body }))
+
+        Ok(Statement::While(WhileStatement { condition, body, span: start.to(self.current_span) }))
     }
     
     /// 解析 `break` 语句 `break [value];`
     fn parse_break_statement(&mut self) -> Result<Statement, ParserError> {
-        self.next_token(); // 消耗 `break`
+        let start = self.current_span;
 
-        let value = if self.current_token_is(&Token::Semicolon) || self.current_token_is(&Token::RBrace) {
+        // `break;` 或紧跟 `}` 的 `break` 不带值。注意不能越过 `}`，它属于外层代码块。
+        let value = if self.peek_token_is(&Token::Semicolon) || self.peek_token_is(&Token::RBrace) {
             None
         } else {
+            self.next_token(); // 消耗 `break`，前进到值表达式的开头
             Some(self.parse_expression(Precedence::Lowest)?)
         };
         
@@ -511,16 +671,17 @@ impl<'a> Parser<'a> {
             self.next_token();
         }
 
-        Ok(Statement::Break(BreakStatement { value }))
+        Ok(Statement::Break(BreakStatement { value, span: start.to(self.current_span) }))
     }
 
     /// 解析 `continue` 语句 `continue;`
     fn parse_continue_statement(&mut self) -> Result<Statement, ParserError> {
         // `continue` 后面没有值，所以直接创建节点即可
+        let start = self.current_span;
         if self.peek_token_is(&Token::Semicolon) {
             self.next_token();
         }
-        Ok(Statement::Continue(ContinueStatement))
+        Ok(Statement::Continue(ContinueStatement { span: start.to(self.current_span) }))
     }
 
     // --- 表达式解析 (Expression Parsing) ---
@@ -543,7 +704,7 @@ impl<'a> Parser<'a> {
             Token::Keyword(Keyword::If) => self.parse_if_expression(),
             Token::Keyword(Keyword::Loop) => self.parse_loop_expression(),
             Token::LBrace => self.parse_block_expression(),
-            _ => Err(self.current_error(format!("Expected an expression, but found {:?}", self.current_token))),
+            _ => Err(self.current_error("an expression".to_string())),
         }?;
 
         // --- 2. 中缀解析 (Infix Parsing) ---
@@ -554,14 +715,22 @@ impl<'a> Parser<'a> {
             // 如果下一个是 `(`, 我们就解析一个函数调用。
             match self.peek_token {
                 Token::Plus | Token::Minus | Token::Star | Token::Slash | Token::Equal |
-                Token::NotEqual | Token::LessThan | Token::LessEqual | Token::GreaterThan | Token::GreaterEqual => {
+                Token::NotEqual | Token::LessThan | Token::LessEqual | Token::GreaterThan | Token::GreaterEqual |
+                Token::LogicalAnd | Token::LogicalOr | Token::Percent | Token::Ampersand | Token::Pipe |
+                Token::Caret | Token::ShiftLeft | Token::ShiftRight => {
                     self.next_token();
                     left_expr = self.parse_infix_expression(left_expr)?;
                 }
-                Token::Assign => {
+                Token::Assign | Token::PlusAssign | Token::MinusAssign | Token::StarAssign | Token::SlashAssign |
+                Token::PercentAssign | Token::AmpersandAssign | Token::PipeAssign | Token::CaretAssign |
+                Token::ShiftLeftAssign | Token::ShiftRightAssign => {
                     self.next_token();
                     left_expr = self.parse_assignment_expression(left_expr)?;
                 }
+                Token::Keyword(Keyword::As) => {
+                    self.next_token();
+                    left_expr = self.parse_cast_expression(left_expr)?;
+                }
                 Token::LParen => {
                     self.next_token();
                     left_expr = self.parse_call_expression(left_expr)?;
@@ -579,24 +748,29 @@ impl<'a> Parser<'a> {
     // --- 前缀表达式解析函数 ---
 
     fn parse_identifier_expression(&mut self) -> Result<Expression, ParserError> {
-        self.parse_identifier_string().map(Expression::Identifier)
+        let name = self.parse_identifier_string()?;
+        Ok(Expression::Identifier(IdentifierExpression { name, span: self.current_span, id: self.new_node_id() }))
     }
     
     fn parse_literal_expression(&mut self) -> Result<Expression, ParserError> {
         // 我们已经确认 current_token 是 Literal，所以这里可以安全地 clone
-        Ok(Expression::Literal(
-            if let Token::Literal(lit) = &self.current_token {
-                lit.clone()
-            } else { unreachable!() }
-        ))
+        let value = if let Token::Literal(lit) = &self.current_token {
+            lit.clone()
+        } else { unreachable!() };
+        Ok(Expression::Literal(LiteralExpression { value, span: self.current_span, id: self.new_node_id() }))
     }
 
     fn parse_boolean_expression(&mut self) -> Result<Expression, ParserError> {
-        let value = self.current_token_is(&Token::Keyword(Keyword::True));
-        Ok(Expression::Literal(Literal::Boolean(value)))
+        let value = self.current_token == Token::Keyword(Keyword::True);
+        Ok(Expression::Literal(LiteralExpression {
+            value: Literal::Boolean(value),
+            span: self.current_span,
+            id: self.new_node_id(),
+        }))
     }
 
     fn parse_prefix_expression(&mut self) -> Result<Expression, ParserError> {
+        let start = self.current_span;
         let op = match self.current_token {
             Token::Minus => PrefixOperator::Minus,
             Token::Bang => PrefixOperator::Not,
@@ -604,7 +778,7 @@ impl<'a> Parser<'a> {
         };
         self.next_token(); // 消耗前缀操作符
         let right = Box::new(self.parse_expression(Precedence::Prefix)?);
-        Ok(Expression::Prefix(PrefixExpression { op, right }))
+        Ok(Expression::Prefix(PrefixExpression { op, right, span: start.to(self.current_span), id: self.new_node_id() }))
     }
 
     fn parse_grouped_expression(&mut self) -> Result<Expression, ParserError> {
@@ -614,39 +788,51 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    /// 解析 `if` 表达式。进入时 `current_token` 是 `if`（或 `elif`）。
     fn parse_if_expression(&mut self) -> Result<Expression, ParserError> {
-        self.next_token(); // 消耗 'if'
+        let start = self.current_span;
+        self.next_token(); // 消耗 'if' / 'elif'
         let condition = Box::new(self.parse_expression(Precedence::Lowest)?);
         
         self.expect_peek(&Token::LBrace)?;
         let consequence = self.parse_block_statement()?;
-        self.expect_peek(&Token::RBrace)?;
         
-        let alternative = if self.peek_token_is(&Token::Keyword(Keyword::Else)) {
-            self.next_token(); // 消耗 'else'
-            // `else if` 链，本质上是解析另一个 if 表达式
-            if self.peek_token_is(&Token::Keyword(Keyword::If)) {
+        // 注意：关键字必须精确比较，`peek_token_is` 只比较变体，会把所有关键字视为相同
+        let alternative = if self.peek_token == Token::Keyword(Keyword::Elif) {
+            // `elif` 链，本质上是解析另一个 if 表达式
+            self.next_token(); // 前进到 'elif'
+            Some(Box::new(self.parse_if_expression()?))
+        } else if self.peek_token == Token::Keyword(Keyword::Else) {
+            self.next_token(); // 前进到 'else'
+            // `else if` 链，与 `elif` 等价
+            if self.peek_token == Token::Keyword(Keyword::If) {
+                self.next_token(); // 前进到 'if'
                 Some(Box::new(self.parse_if_expression()?))
             } 
             // `else { ... }` 分支
             else {
                 self.expect_peek(&Token::LBrace)?;
                 let alt_block = self.parse_block_expression()?;
-                self.expect_peek(&Token::RBrace)?;
                 Some(Box::new(alt_block))
             }
         } else {
             None // 没有 else 分支
         };
 
-        Ok(Expression::If(IfExpression { condition, consequence, alternative }))
+        Ok(Expression::If(IfExpression {
+            condition,
+            consequence,
+            alternative,
+            span: start.to(self.current_span),
+            id: self.new_node_id(),
+        }))
     }
 
     fn parse_loop_expression(&mut self) -> Result<Expression, ParserError> {
+        let start = self.current_span;
         self.expect_peek(&Token::LBrace)?;
         let body = self.parse_block_statement()?;
-        self.expect_peek(&Token::RBrace)?;
-        Ok(Expression::Loop(LoopExpression { body }))
+        Ok(Expression::Loop(LoopExpression { body, span: start.to(self.current_span), id: self.new_node_id() }))
     }
     
     fn parse_block_expression(&mut self) -> Result<Expression, ParserError> {
@@ -662,12 +848,20 @@ impl<'a> Parser<'a> {
             Token::Minus => Operator::Minus,
             Token::Star => Operator::Multiply,
             Token::Slash => Operator::Divide,
+            Token::Percent => Operator::Remainder,
+            Token::Ampersand => Operator::BitAnd,
+            Token::Pipe => Operator::BitOr,
+            Token::Caret => Operator::BitXor,
+            Token::ShiftLeft => Operator::ShiftLeft,
+            Token::ShiftRight => Operator::ShiftRight,
             Token::Equal => Operator::Equal,
             Token::NotEqual => Operator::NotEqual,
             Token::LessThan => Operator::LessThan,
             Token::LessEqual => Operator::LessEqual,
             Token::GreaterThan => Operator::GreaterThan,
             Token::GreaterEqual => Operator::GreaterEqual,
+            Token::LogicalAnd => Operator::LogicalAnd,
+            Token::LogicalOr => Operator::LogicalOr,
             _ => unreachable!(),
         };
         
@@ -675,22 +869,52 @@ impl<'a> Parser<'a> {
         self.next_token(); // 消耗中缀操作符
         let right = Box::new(self.parse_expression(precedence)?);
         
-        Ok(Expression::Infix(InfixExpression { op, left: Box::new(left), right }))
+        let span = left.span().to(right.span());
+        Ok(Expression::Infix(InfixExpression { op, left: Box::new(left), right, span, id: self.new_node_id() }))
     }
     
     fn parse_assignment_expression(&mut self, left: Expression) -> Result<Expression, ParserError> {
         // 我们在 AST 层面已经将赋值目标的类型从 String 改为了 Expression，
         // 这里直接使用即可。至于 left 是否是合法的“左值”，由后续的语义分析阶段判断。
+        // 复合赋值 `x op= v` 记录下其中的运算符 `op`
+        let op = match self.current_token {
+            Token::Assign => None,
+            Token::PlusAssign => Some(Operator::Plus),
+            Token::MinusAssign => Some(Operator::Minus),
+            Token::StarAssign => Some(Operator::Multiply),
+            Token::SlashAssign => Some(Operator::Divide),
+            Token::PercentAssign => Some(Operator::Remainder),
+            Token::AmpersandAssign => Some(Operator::BitAnd),
+            Token::PipeAssign => Some(Operator::BitOr),
+            Token::CaretAssign => Some(Operator::BitXor),
+            Token::ShiftLeftAssign => Some(Operator::ShiftLeft),
+            Token::ShiftRightAssign => Some(Operator::ShiftRight),
+            _ => unreachable!(),
+        };
+        self.next_token(); // 消耗 '=' 或复合赋值运算符
         let value = self.parse_expression(Precedence::Assign)?;
+        let span = left.span().to(value.span());
         Ok(Expression::Assignment(AssignmentExpression {
             left: Box::new(left),
             value: Box::new(value),
+            op,
+            span,
+            id: self.new_node_id(),
         }))
     }
 
+    fn parse_cast_expression(&mut self, value: Expression) -> Result<Expression, ParserError> {
+        self.next_token(); // 消耗 'as'，前进到目标类型
+        let target_type = self.parse_type_string()?;
+        let span = value.span().to(self.current_span);
+        Ok(Expression::Cast(CastExpression { value: Box::new(value), target_type, span, id: self.new_node_id() }))
+    }
+
     fn parse_call_expression(&mut self, function: Expression) -> Result<Expression, ParserError> {
         let arguments = self.parse_call_arguments()?;
-        Ok(Expression::Call(CallExpression { function: Box::new(function), arguments }))
+        // 调用表达式的范围从被调用者开始，到右括号 `)` 结束
+        let span = function.span().to(self.current_span);
+        Ok(Expression::Call(CallExpression { function: Box::new(function), arguments, span, id: self.new_node_id() }))
     }
     
     fn parse_call_arguments(&mut self) -> Result<Vec<Expression>, ParserError> {
@@ -723,21 +947,51 @@ impl<'a> Parser<'a> {
     /// `current_token`，然后从 `lexer` 中请求下一个 Token 来填充 `peek_token`。
     ///
     /// # 错误处理
-    /// `lexer.next_token()` 返回的是 `Result<Token, LexerError>`。
+    /// `lexer.next_token()` 返回的是 `Result<SpannedToken, LexerError>`。
     /// 如果词法分析出错 (`Err`)，此函数会：
     /// 1. 将该 `LexerError` 包装成 `CompilerError` 并存入 `self.errors`。
     /// 2. 将 `peek_token` 设置为 `Eof`，以安全地终止后续的解析。
     fn next_token(&mut self) {
-        self.current_token = self.peek_token.clone();
+        self.current_token = std::mem::replace(&mut self.peek_token, Token::Eof);
+        self.current_span = self.peek_span;
+        self.current_docs = std::mem::take(&mut self.peek_docs);
+
+        // 词法错误之后不再向 Lexer 请求 Token，避免同一个错误被反复报告
+        if self.lexer_failed {
+            return;
+        }
 
-        // 从 Lexer 获取下一个 Token，并直接处理可能发生的词法错误
-        match self.lexer.next_token() {
-            Ok(token) => self.peek_token = token,
+        // 从 Lexer 获取下一个 Token，并直接处理可能发生的词法错误。
+        // 文档注释不会成为 peek_token，而是被收集起来：`///` 挂在下一个 Token 上，`//!` 属于整个文件。
+        // 没有被声明取走的 `///`（例如写在函数体内的）会在前进时被丢弃
+        match self.next_non_doc_token() {
+            Ok(SpannedToken { token, span }) => {
+                self.peek_token = token;
+                self.peek_span = span;
+            }
             Err(lex_err) => {
                 // 如果 Lexer 出错，将错误记录下来
                 self.errors.push(CompilerError::Lexer(lex_err));
-                // 并将 peek 设置为 Eof，以防解析器继续处理一个无效的流
+                self.lexer_failed = true;
+                // 并将 peek 设置为 Eof，以防解析器继续处理一个无效的流。
+                // Eof 的位置紧跟在当前 Token 之后。
                 self.peek_token = Token::Eof;
+                self.peek_span = Span {
+                    start_byte: self.current_span.end_byte,
+                    ..self.current_span
+                };
+            }
+        }
+    }
+
+    /// 从 Lexer 读取下一个不是文档注释的 Token，途中遇到的文档注释存入 `peek_docs` 和 `inner_docs`。
+    fn next_non_doc_token(&mut self) -> Result<SpannedToken, LexerError> {
+        loop {
+            let spanned = self.lexer.next_token()?;
+            match spanned.token {
+                Token::DocComment(text) => self.peek_docs.push(text),
+                Token::InnerDocComment(text) => self.inner_docs.push(text),
+                _ => return Ok(spanned),
             }
         }
     }
@@ -748,8 +1002,28 @@ impl<'a> Parser<'a> {
     fn parse_identifier_string(&mut self) -> Result<String, ParserError> {
         match &self.current_token {
             Token::Identifier(name) => Ok(name.clone()),
-            _ => Err(self.current_error("Expected an identifier".to_string())),
+            _ => Err(self.current_error("an identifier".to_string())),
+        }
+    }
+
+    /// 解析一个类型，并以字符串形式返回，e.g., `i32`、`^u8`、`^~bool`。
+    /// 结束时 `current_token` 是类型的最后一个 Token。
+    fn parse_type_string(&mut self) -> Result<String, ParserError> {
+        if !self.current_token_is(&Token::Caret) {
+            return match &self.current_token {
+                Token::Identifier(name) => Ok(name.clone()),
+                _ => Err(self.current_error("a type".to_string())),
+            };
+        }
+
+        let mut type_str = String::from("^");
+        self.next_token(); // 消耗 '^'
+        if self.current_token_is(&Token::Tilde) {
+            type_str.push('~');
+            self.next_token(); // 消耗 '~'
         }
+        type_str.push_str(&self.parse_type_string()?);
+        Ok(type_str)
     }
 
     
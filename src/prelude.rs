@@ -0,0 +1,55 @@
+// file: src/prelude.rs
+
+//! Tipy 的预导入模块 (prelude)。
+//!
+//! 这里的内置函数在语义分析开始之前被注册到符号表最外层的作用域中，
+//! 程序无需任何声明就可以直接调用它们。用户定义的同名函数会遮蔽内置函数。
+//!
+//! 内置函数没有 Tipy 源码形式的函数体，代码生成阶段会在调用处直接展开它们，
+//! 见 `CodeGen::compile_builtin_call`。
+
+use crate::types::Type;
+
+/// `print` / `println` 可以打印的类型。
+///
+/// 128 位整数不在其中：代码生成阶段通过 C 的 `printf` 打印，而它不支持 128 位整数。
+const PRINTABLE_TYPES: [Type; 15] = [
+    Type::Str,
+    Type::I8, Type::I16, Type::I32, Type::I64, Type::Isize,
+    Type::U8, Type::U16, Type::U32, Type::U64, Type::Usize,
+    Type::F32, Type::F64,
+    Type::Bool,
+    Type::Char,
+];
+
+/// 代码生成阶段为了实现内置函数而调用的 C 运行时库函数。
+///
+/// Tipy 函数在目标文件中的符号名就是它在源码中的名字，所以程序不能定义这些名字的函数，
+/// 否则内置函数会调用到用户的函数上，见 `SemanticError::ReservedFunctionName`。
+pub const RUNTIME_FUNCTIONS: [&str; 3] = ["printf", "memcmp", "abort"];
+
+/// 返回所有内置函数的名称和函数类型。
+pub fn builtin_functions() -> Vec<(&'static str, Type)> {
+    let print_overloads = |newline: bool| {
+        let mut overloads: Vec<Type> = PRINTABLE_TYPES
+            .iter()
+            .map(|t| Type::Function { params: vec![t.clone()], ret: Box::new(Type::Void) })
+            .collect();
+        // `println()` 只输出一个换行符
+        if newline {
+            overloads.push(Type::Function { params: Vec::new(), ret: Box::new(Type::Void) });
+        }
+        Type::Overloaded(overloads)
+    };
+
+    vec![
+        // `print(x)`: 把 `x` 打印到标准输出
+        ("print", print_overloads(false)),
+        // `println(x)`: 打印 `x` 并换行
+        ("println", print_overloads(true)),
+        // `len(s)`: 字符串的字节长度
+        ("len", Type::Function { params: vec![Type::Str], ret: Box::new(Type::Usize) }),
+        // `byte_at(s, i)`: 字符串的第 `i` 个字节（从 0 开始），越界时程序终止
+        ("byte_at", Type::Function { params: vec![Type::Str, Type::Usize], ret: Box::new(Type::U8) }),
+    ]
+}
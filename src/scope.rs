@@ -13,7 +13,13 @@ pub struct Symbol {
     pub symbol_type: Type,
     /// 符号是否是可变的。
     pub is_mutable: bool,
-    // 未来可以增加更多信息，如定义的位置 (span)，是否是函数参数等。
+    /// 符号被声明的位置，用于报告重复定义等错误。
+    pub span: Span,
+    /// 符号是否被读取过，用于报告未使用的变量。
+    pub used: bool,
+    /// 符号在声明时是否已经有值。只有声明时没有初始值的变量（`x: i32;`）为 `false`。
+    pub initialized: bool,
+    // 未来可以增加更多信息，如是否是函数参数等。
 }
 
 /// 符号表，用于在编译期间跟踪标识符的定义和作用域。
@@ -44,9 +50,14 @@ impl SymbolTable {
     /// 退出当前作用域（例如，在离开一个代码块时调用）。
     ///
     /// 这会从作用域栈的顶部弹出一个哈希表。为了安全，它会阻止弹出唯一的全局作用域。
-    pub fn leave_scope(&mut self) {
+    ///
+    /// # Returns
+    /// 被弹出的作用域中定义的所有符号，调用者可以借此检查未使用的变量。
+    pub fn leave_scope(&mut self) -> Vec<Symbol> {
         if self.scopes.len() > 1 {
-            self.scopes.pop();
+            self.scopes.pop().map(|scope| scope.into_values().collect()).unwrap_or_default()
+        } else {
+            Vec::new()
         }
     }
 
@@ -60,16 +71,25 @@ impl SymbolTable {
         let current_scope = self.scopes.last_mut().unwrap(); // 总会成功，因为总有全局作用域
         let name = symbol.name.clone();
 
-        if current_scope.contains_key(&name) {
-            // CHANGED: 返回结构化的错误，而不是 String。
-            // TODO: 这里需要一个 Span，暂时用 Default。
-            Err(SemanticError::SymbolAlreadyDefined { name, span: Span::default() })
+        if let Some(previous) = current_scope.get(&name) {
+            // 错误指向重复的那一次声明，并附带第一次声明的位置
+            Err(SemanticError::SymbolAlreadyDefined { name, span: symbol.span, previous_span: previous.span })
         } else {
             current_scope.insert(name, symbol);
             Ok(())
         }
     }
 
+    /// 将最内层的同名符号标记为“已使用”。
+    pub fn mark_used(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(symbol) = scope.get_mut(name) {
+                symbol.used = true;
+                return;
+            }
+        }
+    }
+
     /// 从内到外查找一个符号。
     ///
     /// 它会从最内层（当前）作用域开始查找，如果找不到，则向外层作用域继续查找，
@@ -1,5 +1,9 @@
 // src/token.rs
 
+use crate::diagnostics::Span;
+
+use std::fmt;
+
 /// 代表 Tipy 语言中的一个关键字。
 /// 关键字是语言保留的标识符，不能用作变量名或函数名。
 #[derive(Debug, Clone, PartialEq)]
@@ -39,6 +43,8 @@ pub enum Keyword {
     Free,
     /// `None` 关键字，用于表示 Option 类型的空值。
     None,
+    /// `as` 关键字，用于显式类型转换, e.g., `x as f64`。
+    As,
 }
 
 /// 代表一个字面量值。
@@ -47,10 +53,12 @@ pub enum Keyword {
 pub enum Literal {
     /// 字符串字面量, e.g., "Hello, Tipy!"
     String(String),
-    /// 整数类型字面量, e.g., 10, 42
-    Integer(i64),
-    /// 浮点数类型字面量, e.g., 3.14, 0.5
-    Float(f64),
+    /// 整数类型字面量, e.g., 10, 0xFF, 1_000u16
+    /// 第二个字段是类型后缀（如 `u16`），没有后缀时类型由上下文推断。
+    Integer(u64, Option<String>),
+    /// 浮点数类型字面量, e.g., 3.14, 1e-9, 0.5f32
+    /// 第二个字段是类型后缀（`f32` 或 `f64`）。
+    Float(f64, Option<String>),
     /// 字符串字面量, e.g., 'a'
     Char(char),
     /// 布尔值字面量, e.g., true
@@ -72,6 +80,13 @@ pub enum Token {
     /// 关键字，如 `if`, `ret`。
     Keyword(Keyword),
 
+    // --- 文档注释 ---
+    /// 外部文档注释 `/// ...`，描述紧跟在它后面的声明。
+    /// 内容是 `///` 之后的文本（不含换行符）。
+    DocComment(String),
+    /// 内部文档注释 `//! ...`，描述它所在的整个文件。
+    InnerDocComment(String),
+
     // --- 分隔符 (Delimiters) ---
     /// 左圆括号 `(`.
     LParen,
@@ -89,6 +104,26 @@ pub enum Token {
     // --- 运算符与特殊符号 ---
     /// 赋值符号 `=`.
     Assign,
+    /// 复合赋值 `+=`.
+    PlusAssign,
+    /// 复合赋值 `-=`.
+    MinusAssign,
+    /// 复合赋值 `*=`.
+    StarAssign,
+    /// 复合赋值 `/=`.
+    SlashAssign,
+    /// 复合赋值 `%=`.
+    PercentAssign,
+    /// 复合赋值 `&=`.
+    AmpersandAssign,
+    /// 复合赋值 `|=`.
+    PipeAssign,
+    /// 复合赋值 `^=`.
+    CaretAssign,
+    /// 复合赋值 `<<=`.
+    ShiftLeftAssign,
+    /// 复合赋值 `>>=`.
+    ShiftRightAssign,
     /// 加号 `+`.
     Plus,
     /// 减号 `-`.
@@ -97,6 +132,8 @@ pub enum Token {
     Star,
     /// 除号 `/`.
     Slash,
+    /// 取余 `%`.
+    Percent,
     /// 类型声明冒号 `:`.
     Colon,
     /// 可变性标记 `~`.
@@ -118,14 +155,131 @@ pub enum Token {
     /// 逻辑非 `!`.
     Bang, // Bang (!) 是构成 != 的一部分，也可能用于未来的逻辑非操作
 
+    // --- 逻辑运算符 ---
+    /// 短路逻辑与 `&&`.
+    LogicalAnd,
+    /// 短路逻辑或 `||`.
+    LogicalOr,
+
+    // --- 位运算符 ---
+    // 按位或、按位异或分别复用 `Pipe` (`|`) 和 `Caret` (`^`)，按位取反复用 `Bang` (`!`)
+    /// 按位与 `&`.
+    Ampersand,
+    /// 左移 `<<`.
+    ShiftLeft,
+    /// 右移 `>>`.
+    ShiftRight,
+
     
     // --- 用于函数定义 ---
     /// 函数返回类型箭头 `->`.
     Arrow,
 
     // --- 为未来版本准备的符号 ---
-    /// 指针类型符号 `^`.
+    /// 指针类型符号 `^`，在表达式中作为中缀运算符时表示按位异或。
     Caret,
-    /// 枚举变体分隔符 `|`.
+    /// 枚举变体分隔符 `|`，在表达式中作为中缀运算符时表示按位或。
     Pipe,
-}
\ No newline at end of file
+}
+
+/// 以源码中的写法打印关键字，e.g., `ret`。
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Keyword::Ret => "ret",
+            Keyword::If => "if",
+            Keyword::Else => "else",
+            Keyword::Elif => "elif",
+            Keyword::True => "true",
+            Keyword::False => "false",
+            Keyword::Loop => "loop",
+            Keyword::While => "while",
+            Keyword::Break => "break",
+            Keyword::Continue => "continue",
+            Keyword::Class => "class",
+            Keyword::Enum => "enum",
+            Keyword::Match => "match",
+            Keyword::New => "new",
+            Keyword::Free => "free",
+            Keyword::None => "None",
+            Keyword::As => "as",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// 以源码中的写法打印字面量，e.g., `10`, `"hi"`, `'a'`。
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::String(s) => write!(f, "{:?}", s),
+            Literal::Integer(i, suffix) => write!(f, "{}{}", i, suffix.as_deref().unwrap_or("")),
+            Literal::Float(x, suffix) => write!(f, "{:?}{}", x, suffix.as_deref().unwrap_or("")),
+            Literal::Char(c) => write!(f, "{:?}", c),
+            Literal::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// 为错误信息打印 Token 的用户友好描述，e.g., `` `;` ``、`` identifier `x` ``、`end of file`。
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Token::Eof => return write!(f, "end of file"),
+            Token::Identifier(name) => return write!(f, "identifier `{}`", name),
+            Token::Literal(lit) => return write!(f, "literal `{}`", lit),
+            Token::Keyword(kw) => return write!(f, "keyword `{}`", kw),
+            Token::DocComment(_) | Token::InnerDocComment(_) => return write!(f, "doc comment"),
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Assign => "=",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::StarAssign => "*=",
+            Token::SlashAssign => "/=",
+            Token::PercentAssign => "%=",
+            Token::AmpersandAssign => "&=",
+            Token::PipeAssign => "|=",
+            Token::CaretAssign => "^=",
+            Token::ShiftLeftAssign => "<<=",
+            Token::ShiftRightAssign => ">>=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Colon => ":",
+            Token::Tilde => "~",
+            Token::GreaterThan => ">",
+            Token::GreaterEqual => ">=",
+            Token::LessThan => "<",
+            Token::LessEqual => "<=",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::Bang => "!",
+            Token::LogicalAnd => "&&",
+            Token::LogicalOr => "||",
+            Token::Ampersand => "&",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
+            Token::Arrow => "->",
+            Token::Caret => "^",
+            Token::Pipe => "|",
+        };
+        write!(f, "`{}`", symbol)
+    }
+}
+
+/// 携带源码位置信息的 Token，是 `Lexer` 实际产出的单元。
+///
+/// `Token` 本身只描述“是什么”，`span` 则记录它“在哪里”，
+/// 这样解析器报告的每一个错误都能指向真实的行和列。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
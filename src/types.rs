@@ -32,6 +32,9 @@ pub enum Type {
         params: Vec<Type>,
         ret: Box<Type>,
     },
+    /// 一组同名的内置函数重载，e.g., `print` 对每种可以打印的类型都有一个版本。
+    /// 每个元素都是一个 `Type::Function`，调用时根据实参的类型选择其中之一。
+    Overloaded(Vec<Type>),
 
     // --- 用户自定义类型 (为未来预留) ---
     Struct { name: String },
@@ -46,13 +49,93 @@ pub enum Type {
     Error,
 }
 
+impl Type {
+    /// 是否为有符号整数类型 (`i8` .. `i128`, `isize`)。
+    pub fn is_signed_integer(&self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::I128 | Type::Isize)
+    }
+
+    /// 是否为无符号整数类型 (`u8` .. `u128`, `usize`)。
+    /// 无符号整数的除法、比较等运算需要使用与有符号整数不同的指令。
+    pub fn is_unsigned_integer(&self) -> bool {
+        matches!(self, Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::U128 | Type::Usize)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_signed_integer() || self.is_unsigned_integer()
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    /// 是否为数字类型，即可以参与 `+ - * /` 运算的类型。
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// 该类型的值能否存放在变量中。
+    ///
+    /// `void` 没有值，函数不能作为值保存，`Error` 只会出现在已经报告过的错误中。
+    pub fn is_storable(&self) -> bool {
+        !matches!(self, Type::Void | Type::Function { .. } | Type::Overloaded(_) | Type::Error)
+    }
+
+    /// 整数类型的位宽，非整数类型返回 `None`。
+    ///
+    /// `isize` / `usize` 与指针等宽。目前编译器只支持 64 位目标，所以它们固定为 64 位。
+    pub fn integer_bits(&self) -> Option<u32> {
+        match self {
+            Type::I8 | Type::U8 => Some(8),
+            Type::I16 | Type::U16 => Some(16),
+            Type::I32 | Type::U32 => Some(32),
+            Type::I64 | Type::U64 | Type::Isize | Type::Usize => Some(64),
+            Type::I128 | Type::U128 => Some(128),
+            _ => None,
+        }
+    }
+
+    /// 整数类型的取值范围 `(最小值, 最大值)`，非整数类型返回 `None`。
+    ///
+    /// 最大值使用 `u128` 是为了能表示 `u128::MAX`，最小值则需要 `i128` 来表示负数。
+    pub fn integer_range(&self) -> Option<(i128, u128)> {
+        let bits = self.integer_bits()?;
+        if self.is_signed_integer() {
+            Some((i128::MIN >> (128 - bits), (i128::MAX >> (128 - bits)) as u128))
+        } else {
+            Some((0, u128::MAX >> (128 - bits)))
+        }
+    }
+
+    /// 判断整数 `value` 是否在该整数类型的取值范围内。非整数类型总是返回 `false`。
+    pub fn contains_integer(&self, value: i128) -> bool {
+        match self.integer_range() {
+            Some((min, max)) => value >= min && (value < 0 || value as u128 <= max),
+            None => false,
+        }
+    }
+}
+
 /// 实现 Display trait，使得类型可以被方便地打印成用户友好的格式。
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Type::I8 => write!(f, "i8"),
+            Type::I16 => write!(f, "i16"),
             Type::I32 => write!(f, "i32"),
-            // ... 其他所有原生类型 ...
+            Type::I64 => write!(f, "i64"),
+            Type::I128 => write!(f, "i128"),
+            Type::Isize => write!(f, "isize"),
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::U128 => write!(f, "u128"),
+            Type::Usize => write!(f, "usize"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
             Type::Str => write!(f, "str"),
             Type::Pointer { is_mutable_ptr, is_mutable_pointee, pointee } => {
                 let mut s = String::new();
@@ -65,12 +148,11 @@ impl fmt::Display for Type {
                 let param_types = params.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
                 write!(f, "fn({}) -> {}", param_types, ret)
             }
+            Type::Overloaded(_) => write!(f, "<overloaded fn>"),
             Type::Struct { name } => write!(f, "{}", name),
             Type::Enum { name } => write!(f, "{}", name),
             Type::Void => write!(f, "void"),
             Type::Error => write!(f, "<type error>"),
-            // ... 为了简洁，省略了所有原生类型的匹配臂，但实际中应全部实现 ...
-            _ => write!(f, "unimplemented_type"), // 临时占位
         }
     }
 }
\ No newline at end of file